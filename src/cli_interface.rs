@@ -1,4 +1,553 @@
-fn cli_interface() {
-    println!("Hello, world!")
+//! The command line interface: the tree of subcommands, and how their results are printed
+use crate::ngf::json_mappings::bbox::RnÉtatCode;
+use crate::ngf::{Matricule, Territoire};
+use crate::save::Backend;
+use chrono::{NaiveDate, NaiveTime};
+use clap::{arg, command, value_parser, Command};
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+/// The arguments to find a point on a network
+fn args_recherche(commande: Command) -> Command {
+    commande
+        .arg(arg!(-n --network <network> "Réseau du point (voir la commande networks)").default_value("ngf"))
+        .arg(
+            arg!(--choix <strategie> "Choix du repère quand plusieurs correspondent : interactif, premier, exact (seulement le matricule exact) ou liste (échoue en listant les candidats)")
+                .value_parser(["interactif", "premier", "exact", "liste"])
+                .default_value("interactif"),
+        )
+        .arg(
+            arg!(--cid <cid> "Identifiant IGN du repère, ou URL de sa fiche, plutôt qu’un matricule")
+                .conflicts_with("point"),
+        )
+        .arg(arg!(
+            -g --groupe "Prend aussi le triplet et le repère primordial du repère"
+        ))
+}
+
+/// The options of a visit, to add or edit it
+fn args_visite(commande: Command) -> Command {
+    commande
+        .arg(
+            arg!(--heure <heure> "Heure de la visite (HH:MM)")
+                .value_parser(value_parser!(NaiveTime)),
+        )
+        .arg(
+            arg!(--etat <etat> "État constaté : bon, mauvais, detruit, imprenable, non-retrouve, deplace ou detruit-apres-observation")
+                .value_parser(value_parser!(RnÉtatCode)),
+        )
+        .arg(arg!(-r --remarques <remarques> "Remarques"))
+}
+
+/// The tree of the subcommands
+pub fn commande() -> Command {
+    command!()
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            arg!(--profil <profil> "Profil à utiliser : chaque profil a sa configuration et sa sauvegarde")
+                .global(true),
+        )
+        .arg(
+            arg!(--format <format> "Format des résultats (les commandes qui écrivent des fichiers n’acceptent que texte)")
+                .value_parser(["texte", "json"])
+                .default_value("texte")
+                .global(true),
+        )
+        .arg(
+            arg!(--couleur <quand> "Couleurs des résultats (auto : seulement dans un terminal, et si NO_COLOR n’est pas définie)")
+                .value_parser(["auto", "toujours", "jamais"])
+                .default_value("auto")
+                .global(true),
+        )
+        .arg(
+            arg!(-t --territoire <territoire> "Territoire des repères (france, guadeloupe, martinique, guyane, reunion, mayotte)")
+                .value_parser(value_parser!(Territoire))
+                .global(true),
+        )
+        .subcommand(args_recherche(
+            Command::new("show")
+                .about("Affiche la fiche d’un point, sauvegardé ou récupéré sur son réseau")
+                .arg(
+                    arg!([point] "Matricule du repère, ou identifiant (type/identifiant) d’un point")
+                        .required_unless_present("cid"),
+                ),
+        ))
+        .subcommand(
+            Command::new("search")
+                .about("Liste les points d’un réseau correspondant à une recherche")
+                .arg(arg!(<requete> "Matricule, complet ou non"))
+                .arg(arg!(-n --network <network> "Réseau des points (voir la commande networks)").default_value("ngf")),
+        )
+        .subcommand(
+            Command::new("nearby")
+                .about("Liste les points autour d’un point ou d’une position, du plus proche au plus loin")
+                .arg(
+                    arg!([point] "Matricule ou identifiant (type/identifiant) d’un point sauvegardé")
+                        .required_unless_present_all(["longitude", "latitude"]),
+                )
+                .arg(
+                    arg!(--longitude <longitude> "Longitude de la position")
+                        .requires("latitude")
+                        .conflicts_with("point")
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    arg!(--latitude <latitude> "Latitude de la position")
+                        .requires("longitude")
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    arg!(--rayon <metres> "Distance maximale")
+                        .value_parser(value_parser!(f64))
+                        .default_value("1000"),
+                )
+                .arg(arg!(-n --network <network> "Réseau des points (voir la commande networks)").default_value("ngf"))
+                .arg(arg!(--sauvegardes "Cherche parmi les points sauvegardés plutôt que sur le réseau")),
+        )
+        .subcommand(args_recherche(
+            Command::new("save")
+                .about("Récupère des points sur leur réseau et les sauvegarde")
+                .arg(
+                    arg!([point] ... "Matricule du repère, ou identifiant (type/identifiant) d’un point")
+                        .required_unless_present("cid"),
+                ),
+        ))
+        .subcommand(
+            Command::new("list")
+                .about("Liste les points sauvegardés, avec leurs visites")
+                .arg(arg!(--type <type> ... "Ne liste que les points de ce type (ngf, geod, crue…)"))
+                .arg(arg!(--departement <departement> ... "Ne liste que les points de ce département"))
+                .arg(arg!(--visites "Ne liste que les points visités"))
+                .arg(
+                    arg!(--"non-visites" "Ne liste que les points jamais visités")
+                        .conflicts_with("visites"),
+                ),
+        )
+        .subcommand(
+            Command::new("visit")
+                .about("Visites des points sauvegardés")
+                .subcommand_required(true)
+                .subcommand(args_visite(
+                    Command::new("add")
+                        .about("Ajoute une visite d’un point sauvegardé")
+                        .arg(arg!(<point> "Matricule ou identifiant (type/identifiant) du point visité"))
+                        .arg(
+                            arg!(--date <date> "Date de la visite (AAAA-MM-JJ, aujourd’hui si absente)")
+                                .value_parser(value_parser!(NaiveDate)),
+                        ),
+                ))
+                .subcommand(
+                    Command::new("list")
+                        .about("Liste les visites, de tous les points ou d’un point")
+                        .arg(arg!([point] "Matricule ou identifiant (type/identifiant) du point"))
+                        .arg(arg!(--"a-verifier" "Ne liste que les visites à vérifier")),
+                )
+                .subcommand(args_visite(
+                    Command::new("edit")
+                        .about("Modifie une visite")
+                        .arg(arg!(<id> "Identifiant de la visite").value_parser(value_parser!(u64)))
+                        .arg(
+                            arg!(--date <date> "Date de la visite (AAAA-MM-JJ)")
+                                .value_parser(value_parser!(NaiveDate)),
+                        ),
+                ))
+                .subcommand(
+                    Command::new("rm")
+                        .about("Supprime des visites, avec leurs photos")
+                        .arg(arg!(<id> ... "Identifiant de la visite").value_parser(value_parser!(u64))),
+                )
+                .subcommand(
+                    Command::new("suggest")
+                        .about("Propose des visites des points passés pendant une randonnée enregistrée en GPX")
+                        .arg(arg!(<trace> "Trace GPX").value_parser(value_parser!(PathBuf)))
+                        .arg(
+                            arg!(--corridor <metres> "Distance maximale entre la trace et les points")
+                                .value_parser(value_parser!(f64))
+                                .default_value("30"),
+                        )
+                        .arg(arg!(--ign "Cherche aussi les repères de l’IGN le long de la trace"))
+                        .arg(
+                            arg!(--date <date> "Date des visites, si la trace n’a pas d’heures (AAAA-MM-JJ)")
+                                .value_parser(value_parser!(NaiveDate)),
+                        )
+                        .arg(arg!(-y --oui "Crée toutes les visites sans demander (seule façon de les créer avec --format json)")),
+                )
+                .subcommand(
+                    Command::new("confirm")
+                        .about("Confirme des visites proposées depuis une trace GPX")
+                        .arg(arg!(<id> ... "Identifiant de la visite").value_parser(value_parser!(u64)))
+                        .arg(arg!(--rejette "Supprime les visites plutôt que de les confirmer")),
+                ),
+        )
+        .subcommand(
+            Command::new("photo")
+                .about("Photos des visites")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("import")
+                        .about("Ajoute des photos à une visite")
+                        .arg(arg!(<visite> "Identifiant de la visite").value_parser(value_parser!(u64)))
+                        .arg(arg!(<fichier> ... "Fichier de la photo").value_parser(value_parser!(PathBuf)))
+                        .arg(arg!(-l --legende <legende> "Légende des photos").default_value("")),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("Liste les photos, de toutes les visites ou d’une visite")
+                        .arg(arg!([visite] "Identifiant de la visite").value_parser(value_parser!(u64))),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Exporte les points sauvegardés, avec leurs visites")
+                .arg(
+                    arg!([format_export] "Format de l’export (kmz : KML avec les miniatures des photos, gpkg : GeoPackage avec les couches points, visites et photos, fgb : FlatGeobuf)")
+                        .value_parser(["geojson", "kml", "kmz", "gpx", "csv", "csv-visites", "gpkg", "fgb"])
+                        .value_name("format")
+                        .default_value("geojson"),
+                )
+                .arg(
+                    arg!(--dossiers <regroupement> "Regroupement des repères en KML et KMZ")
+                        .value_parser(["departement", "ligne"])
+                        .default_value("departement"),
+                )
+                .arg(
+                    arg!(--crs <systeme> "Système de coordonnées en GeoPackage et FlatGeobuf")
+                        .value_parser(["wgs84", "lambert93"])
+                        .default_value("wgs84"),
+                )
+                .arg(
+                    arg!(--colonnes <colonnes> "Colonnes du CSV, séparées par des virgules (champs des points, ou id, type, nom, longitude, latitude, altitude, departement, commune, etat, derniere_visite, nombre_de_visites, dernier_etat, nombre_de_photos, description)")
+                        .value_delimiter(','),
+                )
+                .arg(
+                    arg!(--separateur <separateur> "Séparateur du CSV")
+                        .value_parser(value_parser!(char))
+                        .default_value(","),
+                )
+                .arg(arg!(--id <id> ... "N’exporte que ce point (type/identifiant), dans l’ordre donné"))
+                .arg(
+                    arg!(--route <nom> "Ajoute au GPX une route passant par les points dans l’ordre des --id")
+                        .requires("id"),
+                )
+                .arg(arg!(--type <type> ... "N’exporte que les points de ce type (ngf, geod, crue…)"))
+                .arg(arg!(--departement <departement> ... "N’exporte que les points de ce département"))
+                .arg(arg!(--visites "N’exporte que les points visités"))
+                .arg(
+                    arg!(--"non-visites" "N’exporte que les points jamais visités")
+                        .conflicts_with("visites"),
+                )
+                .arg(
+                    arg!(-o --output <fichier> "Fichier de l’export (sortie standard si absent)")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Affiche ou modifie la configuration du profil")
+                .subcommand(
+                    Command::new("set")
+                        .about("Modifie la configuration")
+                        .arg(arg!(<cle> "Paramètre").value_parser(["territoire"]))
+                        .arg(arg!(<valeur> "Valeur")),
+                )
+                .subcommand(Command::new("profiles").about("Liste les profils")),
+        )
+        .subcommand(
+            Command::new("add-point")
+                .about("Ajoute un point d’un type personnalisé")
+                .arg(arg!(<type> "Type du point"))
+                .arg(arg!([champs] ... "Champs du point, sous la forme champ=valeur")),
+        )
+        .subcommand(
+            Command::new("add-crue")
+                .about("Ajoute un repère de crue")
+                .arg(arg!(<identifiant> "Identifiant du repère de crue"))
+                .arg(arg!(--riviere <riviere> "Rivière").required(true))
+                .arg(
+                    arg!(--date <date> "Date de la crue (AAAA-MM-JJ)")
+                        .required(true)
+                        .value_parser(value_parser!(NaiveDate)),
+                )
+                .arg(
+                    arg!(--longitude <longitude> "Longitude")
+                        .required(true)
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    arg!(--latitude <latitude> "Latitude")
+                        .required(true)
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(arg!(--niveau <niveau> "Niveau atteint par l’eau, en mètres").value_parser(value_parser!(f64)))
+                .arg(
+                    arg!(--reference <reference> "Référence du niveau")
+                        .value_parser(["sol", "echelle", "ngf-ign69", "ngf-ign78"])
+                        .default_value("sol"),
+                )
+                .arg(arg!(--trait "Trait de crue plutôt que repère de crue"))
+                .arg(arg!(--inscription <inscription> "Texte inscrit sur le repère"))
+                .arg(arg!(--commune <commune> "Commune"))
+                .arg(arg!(--insee <insee> "Numéro INSEE de la commune"))
+                .arg(arg!(--localisation <localisation> "Localisation"))
+                .arg(arg!(--support <support> "Support"))
+                .arg(
+                    arg!(--etat <etat> "État du repère : bon, mauvais, detruit, imprenable, non-retrouve, deplace ou detruit-apres-observation")
+                        .value_parser(value_parser!(RnÉtatCode))
+                        .default_value("bon"),
+                )
+                .arg(
+                    arg!(--"repere-ngf" <matricule> "Matricule du repère NGF proche")
+                        .value_parser(value_parser!(Matricule)),
+                )
+                .arg(
+                    arg!(--denivelee <denivelee> "Dénivelée depuis le repère NGF, en mètres")
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64)),
+                ),
+        )
+        .subcommand(Command::new("types").about("Liste les types de points personnalisés"))
+        .subcommand(Command::new("networks").about("Liste les réseaux disponibles"))
+        .subcommand(
+            Command::new("graph")
+                .about("Graphe des repères sauvegardés : chemins entre repères et export")
+                .arg(
+                    arg!(--autour <matricule> "Ajoute les repères récupérés autour de ce repère sauvegardé")
+                        .value_parser(value_parser!(Matricule)),
+                )
+                .arg(
+                    arg!(--proximite <metres> "Relie les repères plus proches que cette distance")
+                        .value_parser(value_parser!(f64))
+                        .default_value("0"),
+                )
+                .arg(
+                    arg!(--de <matricule> "Début du chemin à chercher")
+                        .requires("vers")
+                        .value_parser(value_parser!(Matricule)),
+                )
+                .arg(
+                    arg!(--vers <matricule> "Fin du chemin à chercher")
+                        .requires("de")
+                        .value_parser(value_parser!(Matricule)),
+                )
+                .arg(arg!(--"hors-ligne" "Autorise le chemin à passer par des repères seulement proches"))
+                .arg(
+                    arg!(--export <format> "Exporte le graphe")
+                        .value_parser(["dot", "graphml"]),
+                )
+                .arg(
+                    arg!(-o --output <fichier> "Fichier de l’export (sortie standard si absent)")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("import-csv")
+                .about("Importe des points depuis un fichier CSV : points personnalisés, ou repères NGF récupérés par matricule")
+                .arg(arg!(<fichier> "Fichier CSV, avec une ligne d’en-tête").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--type <type> "Type des lignes sans colonne type ni id (ngf, geod, ou type personnalisé)"))
+                .arg(
+                    arg!(--separateur <separateur> "Séparateur du CSV")
+                        .value_parser(value_parser!(char))
+                        .default_value(","),
+                ),
+        )
+        .subcommand(
+            Command::new("import-legacy")
+                .about("Importe une sauvegarde de l’interface Python")
+                .arg(
+                    arg!([save] "Dossier de la sauvegarde, ou fichier GeoJSON (liste les sauvegardes si absent)")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Copie la sauvegarde actuelle vers un autre backend")
+                .arg(
+                    arg!(<backend> "Backend de destination (json, sqlite)")
+                        .value_parser(value_parser!(Backend)),
+                )
+                .arg(arg!(
+                    --switch "Utilise ensuite le backend de destination"
+                )),
+        )
+        .subcommand(
+            Command::new("osm")
+                .about("Compare les repères NGF aux points géodésiques (man_made=survey_point) d’un extrait OpenStreetMap")
+                .arg(arg!(<extrait> "Extrait OpenStreetMap (.osm.pbf ou .osm)").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--repere <matricule> ... "Compare aussi ce repère, récupéré sur le site de l’IGN"))
+                .arg(arg!(--ign "Compare aussi les repères de l’IGN situés autour des points géodésiques de l’extrait"))
+                .arg(
+                    arg!(--tolerance <metres> "Distance maximale entre un repère et son point géodésique")
+                        .value_parser(value_parser!(f64))
+                        .default_value("30"),
+                )
+                .arg(
+                    arg!(--osc <fichier> "Écrit un fichier osmChange créant les repères manquants")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(--taches <fichier> "Écrit la liste des repères manquants à cartographier")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("site")
+                .about("Génère un site HTML statique des points sauvegardés, consultable hors ligne")
+                .arg(arg!(<dossier> "Dossier du site").value_parser(value_parser!(PathBuf))),
+        )
+        .subcommand(
+            Command::new("field-sheets")
+                .about("Génère un PDF imprimable des fiches de terrain des repères, pour une sortie")
+                .arg(arg!([matricule] ... "Matricule du repère, sauvegardé ou récupéré sur le site de l’IGN"))
+                .arg(arg!(--sauvegardes "Ajoute tous les repères sauvegardés"))
+                .arg(
+                    arg!(--departement <departement> ... "Ne garde que les repères sauvegardés de ce département")
+                        .requires("sauvegardes"),
+                )
+                .arg(
+                    arg!(--"non-visites" "Ne garde que les repères sauvegardés jamais visités")
+                        .requires("sauvegardes"),
+                )
+                .arg(arg!(--titre <titre> "Titre du document").default_value("Fiches de terrain"))
+                .arg(
+                    arg!(-o --output <fichier> "Fichier PDF")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
 }
 
+/// The format of the results
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Texte,
+    Json,
+}
+
+/// Where the results are printed, and how
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sortie {
+    pub format: Format,
+    pub couleurs: bool,
+}
+
+impl Sortie {
+    /// The output chosen with the global options of the command line
+    pub fn new(matches: &clap::ArgMatches) -> Sortie {
+        let format = match matches.get_one::<String>("format").map(String::as_str) {
+            Some("json") => Format::Json,
+            _ => Format::Texte,
+        };
+        let couleurs = match matches.get_one::<String>("couleur").map(String::as_str) {
+            Some("toujours") => true,
+            Some("jamais") => false,
+            _ => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        };
+        Sortie { format, couleurs }
+    }
+
+    /// Prints a text, with its colours if they are enabled
+    pub fn texte(&self, texte: impl fmt::Display) {
+        let texte = texte.to_string();
+        if self.couleurs {
+            println!("{texte}");
+        } else {
+            println!("{}", sans_couleurs(&texte));
+        }
+    }
+
+    /// Asks a question on the same line, with its colours if they are enabled, and returns the
+    /// answer of the user (empty if stdin is closed)
+    pub fn demande(&self, question: impl fmt::Display) -> String {
+        let question = question.to_string();
+        if self.couleurs {
+            print!("{question}");
+        } else {
+            print!("{}", sans_couleurs(&question));
+        }
+        io::stdout().flush().unwrap_or_default();
+        let mut réponse = String::new();
+        io::stdin().read_line(&mut réponse).unwrap_or(0);
+        réponse.trim().to_string()
+    }
+
+    /// Prints a value as JSON
+    pub fn json(&self, valeur: &impl Serialize) {
+        match serde_json::to_string_pretty(valeur) {
+            Ok(json) => println!("{json}"),
+            Err(err) => eprintln!("Could not write the JSON: {err}"),
+        }
+    }
+}
+
+/// The text without its ANSI escape sequences
+pub fn sans_couleurs(texte: &str) -> String {
+    let mut résultat = String::with_capacity(texte.len());
+    let mut caractères = texte.chars().peekable();
+    while let Some(caractère) = caractères.next() {
+        if caractère != '\x1b' {
+            résultat.push(caractère);
+            continue;
+        }
+        if caractères.next_if_eq(&'[').is_some() {
+            while caractères
+                .next_if(|c| c.is_ascii_digit() || *c == ';')
+                .is_some()
+            {}
+            // Some fiches miss the final m of the sequence, then the letter is part of the text
+            caractères.next_if_eq(&'m');
+        }
+    }
+    résultat
+}
+
+#[test]
+fn test_commande() {
+    commande().debug_assert();
+    let matches = commande()
+        .try_get_matches_from([
+            "gdb",
+            "visit",
+            "add",
+            "T'.D.S3 - 50",
+            "--etat",
+            "mauvais",
+            "--heure",
+            "14:30",
+            "--format",
+            "json",
+        ])
+        .unwrap();
+    assert_eq!(Sortie::new(&matches).format, Format::Json);
+    let (_, visit_matches) = matches.subcommand().unwrap();
+    let (_, add_matches) = visit_matches.subcommand().unwrap();
+    assert_eq!(
+        add_matches.get_one::<RnÉtatCode>("etat"),
+        Some(&RnÉtatCode::MauvaisÉtat)
+    );
+    assert_eq!(
+        add_matches.get_one::<NaiveTime>("heure"),
+        NaiveTime::from_hms_opt(14, 30, 0).as_ref()
+    );
+    assert!(commande().try_get_matches_from(["gdb", "show"]).is_err());
+    assert!(commande()
+        .try_get_matches_from(["gdb", "export", "csv", "--visites", "--non-visites"])
+        .is_err());
+}
+
+#[test]
+fn test_sans_couleurs() {
+    assert_eq!(
+        sans_couleurs("\x1b[94mÉtat\x1b[39m : \x1b[93Mauvais état\x1b[39m"),
+        "État : Mauvais état"
+    );
+    assert_eq!(sans_couleurs("[pas de couleur]"), "[pas de couleur]");
+}
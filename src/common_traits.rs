@@ -1,8 +1,58 @@
+use std::{fmt, str::FromStr};
+
 pub trait Identified {
     /// This function should be able to identifiy any object from any network
-    /// The returned string MUST include :
+    /// The returned string MUST include :
     /// - The network name
     /// - Either a matricule or an ID used by the network to identify it’s objects
-    /// (to ensure unique identification across all networks)
+    ///   (to ensure unique identification across all networks)
+    ///
+    /// It is written `network/key`, and can be parsed back with ObjectId::from_str
     fn object_id(&self) -> String;
-}
\ No newline at end of file
+}
+
+/// An object id, split into the network name and the key used by the network
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct ObjectId {
+    pub network: String,
+    pub key: String,
+}
+
+impl ObjectId {
+    pub fn new(network: &str, key: &str) -> ObjectId {
+        ObjectId {
+            network: network.to_string(),
+            key: key.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.key)
+    }
+}
+
+impl FromStr for ObjectId {
+    type Err = String;
+
+    /// The network name can not contain a `/`, but the key can
+    ///
+    /// # Examples
+    /// ```
+    /// use geodesie_de_bureau::common_traits::ObjectId;
+    /// assert_eq!(
+    ///     "ngf/T'.D.S3 - 50".parse::<ObjectId>(),
+    ///     Ok(ObjectId::new("ngf", "T'.D.S3 - 50"))
+    /// );
+    /// assert!("T'.D.S3 - 50".parse::<ObjectId>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((network, key)) if !network.is_empty() && !key.is_empty() => {
+                Ok(ObjectId::new(network, key))
+            }
+            _ => Err(format!("Invalid object id: '{s}' (expected 'network/key')")),
+        }
+    }
+}
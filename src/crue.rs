@@ -0,0 +1,164 @@
+//! Flood marks (repères de crue), most of which are not in the NGF network. A flood mark records
+//! the level reached by a river during a flood, and can be tied to a nearby NGF benchmark to
+//! know its altitude
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::common_traits::Identified;
+use crate::ngf::{json_mappings::bbox::*, Matricule, RepèreNivellement};
+
+/// What the level of the flood is measured from
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum RéférenceNiveau {
+    /// The level is an altitude in this system
+    Altitude(NivfReaCode),
+    /// The level is a height above the ground at the foot of the mark
+    HauteurAuSol,
+    /// The level is a reading of the hydrometric scale of the river
+    Échelle,
+}
+
+impl fmt::Display for RéférenceNiveau {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RéférenceNiveau::Altitude(système) => write!(f, "altitude {système}"),
+            RéférenceNiveau::HauteurAuSol => write!(f, "hauteur au-dessus du sol"),
+            RéférenceNiveau::Échelle => write!(f, "lecture d’échelle"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RepèreDeCrue {
+    /// Identifies the mark (for instance the number given by the local flood observatory)
+    pub identifiant: String,
+    /// Either RepèreDeCrue or TraitDeCrue
+    pub rn_type: RnTypeCode,
+    pub état: RnÉtatCode,
+
+    pub rivière: String,
+    pub date_crue: NaiveDate,
+    /// The level reached by the water, in metres
+    pub niveau: Option<f64>,
+    pub référence_niveau: RéférenceNiveau,
+    /// What is written on the mark
+    pub inscription: String,
+
+    pub longitude: f64,
+    pub latitude: f64,
+    pub insee: String,
+    pub commune: String,
+    pub localisation: Option<String>,
+    pub support: String,
+
+    /// Matricule of the nearby NGF benchmark the mark is levelled from
+    pub repère_ngf: Option<Matricule>,
+    /// Height of the mark above the NGF benchmark, in metres
+    pub dénivelée: Option<f64>,
+    pub remarques: String,
+}
+
+impl RepèreDeCrue {
+    /// The altitude of the flood level. It is either given directly by the level, or computed
+    /// from the altitude of the NGF benchmark the mark is levelled from
+    pub fn altitude(&self, repère_ngf: Option<&RepèreNivellement>) -> Option<f64> {
+        if let RéférenceNiveau::Altitude(_) = self.référence_niveau {
+            return self.niveau;
+        }
+        let repère_ngf = repère_ngf?;
+        if self.repère_ngf.as_ref() != Some(&repère_ngf.matricule) {
+            return None;
+        }
+        Some(repère_ngf.altitude_m()? + self.dénivelée?)
+    }
+}
+
+impl Identified for RepèreDeCrue {
+    fn object_id(&self) -> String {
+        format!("crue/{}", self.identifiant)
+    }
+}
+
+impl fmt::Display for RepèreDeCrue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "\x1b[91m=============== {rn_type} ===============\x1b[39m",
+            rn_type = self.rn_type
+        )?;
+        writeln!(f, "\x1b[94mIdentifiant\x1b[39m : {}", self.identifiant)?;
+        writeln!(f, "\x1b[94mÉtat\x1b[39m : {}", self.état)?;
+        writeln!(f)?;
+        writeln!(f, "\x1b[91m=== Crue ===\x1b[39m")?;
+        writeln!(f, "\x1b[94mRivière\x1b[39m : {}", self.rivière)?;
+        writeln!(
+            f,
+            "\x1b[94mDate\x1b[39m : {}",
+            self.date_crue.format("%d/%m/%Y")
+        )?;
+        if let Some(niveau) = self.niveau {
+            writeln!(
+                f,
+                "\x1b[94mNiveau\x1b[39m : {niveau}m ({})",
+                self.référence_niveau
+            )?;
+        }
+        if !self.inscription.is_empty() {
+            writeln!(f, "\x1b[94mInscription\x1b[39m : « {} »", self.inscription)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "\x1b[91m=== Localisation ===\x1b[39m")?;
+        writeln!(f, "\x1b[94mLongitude\x1b[39m : {}", self.longitude)?;
+        writeln!(f, "\x1b[94mLatitude\x1b[39m : {}", self.latitude)?;
+        writeln!(f, "\x1b[94mNuméro insee\x1b[39m : {}", self.insee)?;
+        writeln!(f, "\x1b[94mCommune\x1b[39m : {}", self.commune)?;
+        if let Some(localisation) = &self.localisation {
+            writeln!(f, "\x1b[94mLocalisation\x1b[39m : {localisation}")?;
+        }
+        writeln!(f, "\x1b[94mSupport\x1b[39m : {}", self.support)?;
+        if let Some(repère_ngf) = &self.repère_ngf {
+            writeln!(f, "\x1b[94mRepère NGF\x1b[39m : {repère_ngf}")?;
+            if let Some(dénivelée) = self.dénivelée {
+                writeln!(f, "└╴ \x1b[94mdénivelée\x1b[39m : {dénivelée:+}m")?;
+            }
+        }
+        if !self.remarques.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "\x1b[91m=== Remarques ===\x1b[39m")?;
+            writeln!(f, "{}", self.remarques)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_altitude() {
+    let mut repère_ngf = crate::save::repère_test("T'.D.S3 - 50");
+    let mut crue = RepèreDeCrue {
+        identifiant: "31-GARONNE-1875-1".to_string(),
+        rn_type: RnTypeCode::RepèreDeCrue,
+        état: RnÉtatCode::BonÉtat,
+        rivière: "Garonne".to_string(),
+        date_crue: NaiveDate::from_ymd_opt(1875, 6, 23).unwrap(),
+        niveau: Some(2.1),
+        référence_niveau: RéférenceNiveau::HauteurAuSol,
+        inscription: "CRUE DU 23 JUIN 1875".to_string(),
+        longitude: 1.4092,
+        latitude: 43.6493,
+        insee: "31555".to_string(),
+        commune: "Toulouse".to_string(),
+        localisation: None,
+        support: "MAISON".to_string(),
+        repère_ngf: Some("T’.D.S3 - 50".parse().unwrap()),
+        dénivelée: Some(1.5),
+        remarques: "".to_string(),
+    };
+    assert_eq!(crue.altitude(None), None);
+    assert_eq!(crue.altitude(Some(&repère_ngf)), Some(125.719 + 1.5));
+    repère_ngf.matricule = "T'.D.S3 - 52".parse().unwrap();
+    assert_eq!(crue.altitude(Some(&repère_ngf)), None);
+    crue.référence_niveau = RéférenceNiveau::Altitude(NivfReaCode::NgfIgn1969);
+    crue.niveau = Some(128.2);
+    assert_eq!(crue.altitude(None), Some(128.2));
+}
@@ -0,0 +1,436 @@
+//! User-defined point types, for the networks that have no IGN API (cadastral markers, flood
+//! marks, foreign benchmarks…). A type is defined by a JSON file in data/types, in the same
+//! format as template_db/data/types/ngf.json:
+//! ```json
+//! {
+//!     "matricule": "str",
+//!     "altitude": "float|None",
+//!     "etat": "Bon état|Mauvais état|Détruit"
+//! }
+//! ```
+//! The first field identifies the point. A field that is not `str`, `int`, `float` or `bool` is
+//! an enumeration of at least two values. The fields named `longitude` and `latitude`, if any,
+//! locate it
+use crate::common_traits::Identified;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{error::Error, fmt, fs, path::Path};
+
+/// The names of the field kinds that are not enumerations
+const KIND_NAMES: [&str; 4] = ["str", "int", "float", "bool"];
+
+/// The type names used by the points that are not user-defined
+pub const RESERVED_TYPE_NAMES: [&str; 4] = ["ngf", "geod", "crue", "autre"];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum FieldKind {
+    Str,
+    Int,
+    Float,
+    Bool,
+    /// One of these values
+    Enumeration(Vec<String>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct FieldType {
+    pub kind: FieldKind,
+    /// If the value may be null (`|None`)
+    pub optional: bool,
+}
+
+impl FieldType {
+    /// Parses a type such as `str`, `float|None` or `Bon état|Mauvais état`
+    pub fn parse(specification: &str) -> Result<FieldType, String> {
+        let mut parts: Vec<&str> = specification.split('|').map(str::trim).collect();
+        let optional = parts.contains(&"None");
+        parts.retain(|part| *part != "None");
+        let kind = match parts.as_slice() {
+            ["str"] => FieldKind::Str,
+            ["int"] => FieldKind::Int,
+            ["float"] => FieldKind::Float,
+            ["bool"] => FieldKind::Bool,
+            [_] | [] => return Err(format!("unknown type '{specification}'")),
+            _ if parts
+                .iter()
+                .any(|part| part.is_empty() || KIND_NAMES.contains(part)) =>
+            {
+                return Err(format!(
+                    "'{specification}' is neither a type nor an enumeration of values"
+                ))
+            }
+            _ => FieldKind::Enumeration(parts.iter().map(|part| part.to_string()).collect()),
+        };
+        Ok(FieldType { kind, optional })
+    }
+
+    /// Checks that the value matches the type
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        let valid = match (&self.kind, value) {
+            (_, Value::Null) => self.optional,
+            (FieldKind::Str, Value::String(_)) => true,
+            (FieldKind::Int, Value::Number(number)) => number.is_i64() || number.is_u64(),
+            (FieldKind::Float, Value::Number(_)) => true,
+            (FieldKind::Bool, Value::Bool(_)) => true,
+            (FieldKind::Enumeration(values), Value::String(string)) => values.contains(string),
+            _ => false,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("{value} is not a valid {self}"))
+        }
+    }
+
+    /// Turns a value typed by the user into a JSON value of this type
+    pub fn parse_value(&self, input: &str) -> Result<Value, String> {
+        let input = input.trim();
+        if input.is_empty() || input == "None" {
+            return if self.optional {
+                Ok(Value::Null)
+            } else {
+                Err(format!("a value is required ({self})"))
+            };
+        }
+        let value = match self.kind {
+            FieldKind::Str | FieldKind::Enumeration(_) => Value::String(input.to_string()),
+            FieldKind::Int => Value::from(
+                input
+                    .parse::<i64>()
+                    .map_err(|_| format!("'{input}' is not an integer"))?,
+            ),
+            FieldKind::Float => Value::from(
+                input
+                    .replace(',', ".")
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{input}' is not a number"))?,
+            ),
+            FieldKind::Bool => Value::Bool(match input.to_lowercase().as_str() {
+                "true" | "oui" | "yes" | "1" => true,
+                "false" | "non" | "no" | "0" => false,
+                _ => return Err(format!("'{input}' is not a boolean")),
+            }),
+        };
+        self.check(&value)?;
+        Ok(value)
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            FieldKind::Str => write!(f, "str")?,
+            FieldKind::Int => write!(f, "int")?,
+            FieldKind::Float => write!(f, "float")?,
+            FieldKind::Bool => write!(f, "bool")?,
+            FieldKind::Enumeration(values) => write!(f, "{}", values.join("|"))?,
+        }
+        if self.optional {
+            write!(f, "|None")?;
+        }
+        Ok(())
+    }
+}
+
+/// A user-defined point type
+#[derive(Clone, PartialEq, Debug)]
+pub struct PointType {
+    pub name: String,
+    /// The fields, in the order of the definition file
+    pub fields: Vec<(String, FieldType)>,
+}
+
+impl PointType {
+    /// Reads a type from its definition. The name of the type is the name of the file
+    pub fn from_definition(name: &str, definition: &str) -> Result<PointType, Box<dyn Error>> {
+        if RESERVED_TYPE_NAMES.contains(&name) {
+            return Err(format!("The type name '{name}' is reserved").into());
+        }
+        let Value::Object(object) = serde_json::from_str::<Value>(definition)? else {
+            return Err(format!("The definition of the type '{name}' is not a JSON object").into());
+        };
+        let mut fields: Vec<(String, FieldType)> = vec![];
+        for (field, specification) in object {
+            let Value::String(specification) = specification else {
+                return Err(
+                    format!("The type of the field '{field}' of '{name}' is not a string").into(),
+                );
+            };
+            let field_type = FieldType::parse(&specification).map_err(|err| {
+                format!("The field '{field}' of '{name}' has an invalid type: {err}")
+            })?;
+            fields.push((field, field_type));
+        }
+        if fields.is_empty() {
+            return Err(format!("The type '{name}' has no field").into());
+        }
+        Ok(PointType {
+            name: name.to_string(),
+            fields,
+        })
+    }
+
+    /// The field that identifies the points of this type
+    pub fn key_field(&self) -> &str {
+        self.fields[0].0.as_str()
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldType> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, field_type)| field_type)
+    }
+
+    /// Checks every field of the point, and returns all the errors found
+    pub fn validate(&self, champs: &Map<String, Value>) -> Result<(), Vec<String>> {
+        let mut errors: Vec<String> = vec![];
+        for (field, field_type) in &self.fields {
+            if let Err(err) = field_type.check(champs.get(field).unwrap_or(&Value::Null)) {
+                errors.push(format!("{field}: {err}"));
+            }
+        }
+        for field in champs.keys() {
+            if self.field(field).is_none() {
+                errors.push(format!(
+                    "{field}: unknown field for the type '{}'",
+                    self.name
+                ));
+            }
+        }
+        match champs.get(self.key_field()) {
+            Some(Value::String(key)) if key.is_empty() => errors.push(format!(
+                "{}: the identifier can not be empty",
+                self.key_field()
+            )),
+            Some(Value::Null) | None => errors.push(format!(
+                "{}: the identifier can not be null",
+                self.key_field()
+            )),
+            _ => {}
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Creates a point of this type from `field=value` pairs typed by the user
+    pub fn point_from_input(
+        &self,
+        inputs: &[(String, String)],
+    ) -> Result<PointPersonnalisé, Vec<String>> {
+        let mut champs: Map<String, Value> = Map::new();
+        let mut errors: Vec<String> = vec![];
+        for (field, field_type) in &self.fields {
+            let input = inputs
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, input)| input.as_str())
+                .unwrap_or("");
+            match field_type.parse_value(input) {
+                Ok(value) => {
+                    champs.insert(field.clone(), value);
+                }
+                Err(err) => errors.push(format!("{field}: {err}")),
+            }
+        }
+        for (name, _) in inputs {
+            if self.field(name).is_none() {
+                errors.push(format!(
+                    "{name}: unknown field for the type '{}'",
+                    self.name
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let point = PointPersonnalisé {
+            type_name: self.name.clone(),
+            clé: self.key_field().to_string(),
+            champs,
+        };
+        self.validate(&point.champs)?;
+        Ok(point)
+    }
+}
+
+impl fmt::Display for PointType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "\x1b[91m{}\x1b[39m", self.name)?;
+        for (field, field_type) in &self.fields {
+            writeln!(f, "\x1b[94m{field}\x1b[39m : {field_type}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads all the types defined in `directory`. ngf.json, which describes the NGF points, is
+/// skipped
+pub fn load_types(directory: &Path) -> Result<Vec<PointType>, Box<dyn Error>> {
+    let mut types: Vec<PointType> = vec![];
+    if !directory.is_dir() {
+        return Ok(types);
+    }
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or(format!("Invalid type file name: {}", path.display()))?;
+        if RESERVED_TYPE_NAMES.contains(&name) {
+            continue;
+        }
+        types.push(PointType::from_definition(
+            name,
+            fs::read_to_string(&path)?.as_str(),
+        )?);
+    }
+    types.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(types)
+}
+
+/// A point of a user-defined type
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PointPersonnalisé {
+    pub type_name: String,
+    /// The field that identifies the point (see PointType::key_field)
+    pub clé: String,
+    /// The values of the fields, in the order of the type
+    pub champs: Map<String, Value>,
+}
+
+impl PointPersonnalisé {
+    /// The value of the key field, that identifies the point
+    pub fn identifiant(&self) -> String {
+        match self.champs.get(&self.clé) {
+            Some(Value::String(string)) => string.clone(),
+            Some(value) => value.to_string(),
+            None => "".to_string(),
+        }
+    }
+
+    /// (longitude, latitude), if the type has such fields
+    pub fn coordonnées(&self) -> Option<(f64, f64)> {
+        Some((
+            self.champs.get("longitude")?.as_f64()?,
+            self.champs.get("latitude")?.as_f64()?,
+        ))
+    }
+}
+
+impl Identified for PointPersonnalisé {
+    fn object_id(&self) -> String {
+        format!("{}/{}", self.type_name, self.identifiant())
+    }
+}
+
+impl fmt::Display for PointPersonnalisé {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "\x1b[91m=============== {} ===============\x1b[39m",
+            self.type_name
+        )?;
+        for (field, value) in &self.champs {
+            let value = match value {
+                Value::String(string) => string.clone(),
+                Value::Null => continue,
+                value => value.to_string(),
+            };
+            writeln!(f, "\x1b[94m{field}\x1b[39m : {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_point_type() {
+    let point_type = PointType::from_definition(
+        "borne_cadastrale",
+        r#"{
+            "numero": "str",
+            "commune": "str",
+            "longitude": "float",
+            "latitude": "float",
+            "altitude": "float|None",
+            "etat": "Bon état|Mauvais état|Détruit"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(point_type.key_field(), "numero");
+    assert_eq!(
+        point_type.field("etat"),
+        Some(&FieldType {
+            kind: FieldKind::Enumeration(vec![
+                "Bon état".to_string(),
+                "Mauvais état".to_string(),
+                "Détruit".to_string()
+            ]),
+            optional: false
+        })
+    );
+    assert_eq!(
+        point_type.field("altitude").unwrap().to_string(),
+        "float|None"
+    );
+
+    let input = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.to_string()))
+            .collect()
+    };
+    let point = point_type
+        .point_from_input(&input(&[
+            ("numero", "B-12"),
+            ("commune", "Toulouse"),
+            ("longitude", "1,409"),
+            ("latitude", "43.649"),
+            ("etat", "Bon état"),
+        ]))
+        .unwrap();
+    assert_eq!(point.identifiant(), "B-12");
+    assert_eq!(point.coordonnées(), Some((1.409, 43.649)));
+    assert_eq!(point.champs.get("altitude"), Some(&Value::Null));
+
+    let errors = point_type
+        .point_from_input(&input(&[
+            ("numero", "B-13"),
+            ("longitude", "est"),
+            ("latitude", "43.649"),
+            ("etat", "Neuf"),
+            ("couleur", "rouge"),
+        ]))
+        .unwrap_err();
+    assert_eq!(errors.len(), 4);
+    assert!(PointType::from_definition("ngf", r#"{"matricule": "str"}"#).is_err());
+
+    // The identity does not depend on the order of the fields
+    let mut champs = point.champs.clone();
+    let numéro = champs.shift_remove("numero").unwrap();
+    champs.insert("numero".to_string(), numéro);
+    let point = PointPersonnalisé { champs, ..point };
+    assert_eq!(point.object_id(), "borne_cadastrale/B-12");
+}
+
+#[test]
+fn test_field_type() {
+    assert_eq!(
+        FieldType::parse("int|None").unwrap().to_string(),
+        "int|None"
+    );
+    assert_eq!(
+        FieldType::parse("Oui|Non").unwrap().kind,
+        FieldKind::Enumeration(vec!["Oui".to_string(), "Non".to_string()])
+    );
+    for invalide in ["string", "date", "int|float", "Oui|", "None", ""] {
+        assert!(FieldType::parse(invalide).is_err(), "{invalide}");
+    }
+    assert!(PointType::from_definition("borne", r#"{"numero": "string"}"#).is_err());
+}
@@ -0,0 +1,417 @@
+//! CSV export of the points and of the visits, with the columns chosen by the user, and CSV import
+//! of the lists kept in spreadsheets. The import creates points of the custom types, or fetches
+//! the points of the networks (NGF benchmarks by matricule, geodetic sites…)
+use super::*;
+use crate::common_traits::ObjectId;
+use crate::custom_types::PointType;
+use crate::network::Registry;
+use std::{error::Error, fmt, io};
+
+/// The columns of the points that are not fields of the points themselves
+pub const COLONNES_POINTS: [&str; 14] = [
+    "id",
+    "type",
+    "nom",
+    "longitude",
+    "latitude",
+    "altitude",
+    "departement",
+    "commune",
+    "etat",
+    "derniere_visite",
+    "nombre_de_visites",
+    "dernier_etat",
+    "nombre_de_photos",
+    "description",
+];
+
+/// The columns exported when the user does not choose them
+pub const COLONNES_POINTS_PAR_DÉFAUT: [&str; 10] = [
+    "id",
+    "type",
+    "nom",
+    "longitude",
+    "latitude",
+    "altitude",
+    "departement",
+    "commune",
+    "etat",
+    "derniere_visite",
+];
+
+pub const COLONNES_VISITES: [&str; 8] = [
+    "id",
+    "objet",
+    "date",
+    "heure",
+    "etat",
+    "remarques",
+    "a_verifier",
+    "nombre_de_photos",
+];
+
+/// The text of a JSON value in a cell
+fn cellule(valeur: &Value) -> String {
+    match valeur {
+        Value::Null => "".to_string(),
+        Value::String(texte) => texte.clone(),
+        valeur => valeur.to_string(),
+    }
+}
+
+/// The fields of the point itself, as serialized (the fields of the type for custom points)
+fn champs(objet: &Objet) -> serde_json::Map<String, Value> {
+    match objet {
+        Objet::Autre(point) => point.champs.clone(),
+        objet => match serde_json::to_value(objet) {
+            // Objet is serialized as {"NGF": {…}}
+            Ok(Value::Object(variante)) => match variante.into_iter().next() {
+                Some((_, Value::Object(champs))) => champs,
+                _ => serde_json::Map::new(),
+            },
+            _ => serde_json::Map::new(),
+        },
+    }
+}
+
+/// The value of a column for a point. Coordinates and altitudes are written as numbers, with a dot
+fn valeur_point(
+    point: &PointExporté,
+    champs: &serde_json::Map<String, Value>,
+    colonne: &str,
+) -> String {
+    let coordonnées = point.objet.coordonnées();
+    match colonne {
+        "id" => point.objet.object_id(),
+        "type" => point.objet.type_name().to_string(),
+        "nom" => point.nom(),
+        "longitude" => coordonnées
+            .map(|(longitude, _)| longitude.to_string())
+            .unwrap_or_default(),
+        "latitude" => coordonnées
+            .map(|(_, latitude)| latitude.to_string())
+            .unwrap_or_default(),
+        "altitude" => point
+            .altitude()
+            .map(|altitude| altitude.to_string())
+            .unwrap_or_default(),
+        "departement" => point.département().unwrap_or_default(),
+        "commune" => point.commune().unwrap_or_default(),
+        "etat" => point
+            .état()
+            .map(|état| état.to_string())
+            .unwrap_or_default(),
+        "derniere_visite" => point
+            .dernière_visite
+            .map(|date| date.to_string())
+            .unwrap_or_default(),
+        "nombre_de_visites" => point.nombre_de_visites.to_string(),
+        "dernier_etat" => point
+            .dernier_état
+            .as_ref()
+            .map(|état| état.to_string())
+            .unwrap_or_default(),
+        "nombre_de_photos" => point.photos.len().to_string(),
+        "description" => gpx::description(&point.objet),
+        champ => champs.get(champ).map(cellule).unwrap_or_default(),
+    }
+}
+
+/// Writes the points as CSV. The columns are those of COLONNES_POINTS, or fields of the points
+/// (such as `support` or the fields of the custom types)
+pub fn exporte_points<W: io::Write>(
+    points: &[PointExporté],
+    colonnes: &[String],
+    délimiteur: u8,
+    writer: W,
+) -> Result<(), Box<dyn Error>> {
+    let champs: Vec<serde_json::Map<String, Value>> =
+        points.iter().map(|point| champs(&point.objet)).collect();
+    for colonne in colonnes {
+        if !COLONNES_POINTS.contains(&colonne.as_str())
+            && champs.iter().all(|champs| !champs.contains_key(colonne))
+        {
+            return Err(format!("Unknown column: '{colonne}'").into());
+        }
+    }
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(délimiteur)
+        .from_writer(writer);
+    writer.write_record(colonnes)?;
+    for (point, champs) in points.iter().zip(&champs) {
+        writer.write_record(
+            colonnes
+                .iter()
+                .map(|colonne| valeur_point(point, champs, colonne)),
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the visits as CSV, with columns among COLONNES_VISITES
+pub fn exporte_visites<W: io::Write>(
+    visites: &[Visite],
+    photos: &[Photo],
+    colonnes: &[String],
+    délimiteur: u8,
+    writer: W,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(colonne) = colonnes
+        .iter()
+        .find(|colonne| !COLONNES_VISITES.contains(&colonne.as_str()))
+    {
+        return Err(format!(
+            "Unknown column: '{colonne}' (expected {})",
+            COLONNES_VISITES.join(", ")
+        )
+        .into());
+    }
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(délimiteur)
+        .from_writer(writer);
+    writer.write_record(colonnes)?;
+    for visite in visites {
+        writer.write_record(colonnes.iter().map(|colonne| {
+            match colonne.as_str() {
+                "id" => visite.id.to_string(),
+                "objet" => visite.objet.clone(),
+                "date" => visite.date.to_string(),
+                "heure" => visite
+                    .heure
+                    .map(|heure| heure.format("%H:%M").to_string())
+                    .unwrap_or_default(),
+                "etat" => visite
+                    .état
+                    .as_ref()
+                    .map(|état| état.to_string())
+                    .unwrap_or_default(),
+                "remarques" => visite.remarques.clone(),
+                "a_verifier" => visite.à_vérifier.to_string(),
+                _ => photos
+                    .iter()
+                    .filter(|photo| photo.visite == visite.id)
+                    .count()
+                    .to_string(),
+            }
+        }))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// An error on a row of an imported file
+#[derive(Clone, PartialEq, Debug)]
+pub struct ErreurLigne {
+    /// The line number in the file, starting at 1 with the header
+    pub ligne: u64,
+    pub message: String,
+}
+
+impl fmt::Display for ErreurLigne {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.ligne, self.message)
+    }
+}
+
+/// The points read from a CSV file, and the rows that could not be read
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ImportCsv {
+    pub objets: Vec<Objet>,
+    pub erreurs: Vec<ErreurLigne>,
+}
+
+/// The columns that can hold the key of a point of a network, by priority
+const COLONNES_CLÉ: [&str; 5] = ["matricule", "numero", "numéro", "identifiant", "nom"];
+
+/// Reads a point from a row, given as (column, value) pairs
+fn importe_ligne(
+    ligne: &[(String, String)],
+    type_par_défaut: Option<&str>,
+    types: &[PointType],
+    registry: &Registry,
+) -> Result<Objet, String> {
+    let valeur = |colonne: &str| {
+        ligne
+            .iter()
+            .find(|(nom, valeur)| nom == colonne && !valeur.is_empty())
+            .map(|(_, valeur)| valeur.as_str())
+    };
+    // The id column, as exported, gives both the type and the key
+    let id: Option<ObjectId> = valeur("id").map(str::parse).transpose()?;
+    let type_name = valeur("type")
+        .or(id.as_ref().map(|id| id.network.as_str()))
+        .or(type_par_défaut)
+        .ok_or("No type (add a type column or choose a type)")?;
+    if let Some(network) = registry.get(type_name) {
+        let clé = match &id {
+            Some(id) => id.key.as_str(),
+            None => COLONNES_CLÉ
+                .iter()
+                .find_map(|colonne| valeur(colonne))
+                .ok_or(format!(
+                    "No key for the network '{type_name}' (expected a column among {})",
+                    COLONNES_CLÉ.join(", ")
+                ))?,
+        };
+        return network
+            .fetch(clé)
+            .map_err(|err| format!("Could not fetch '{clé}': {err}"));
+    }
+    let Some(point_type) = types.iter().find(|point_type| point_type.name == type_name) else {
+        return Err(format!("Unknown type: '{type_name}'"));
+    };
+    // The other columns of the spreadsheet are ignored
+    let inputs: Vec<(String, String)> = ligne
+        .iter()
+        .filter(|(nom, _)| point_type.field(nom).is_some())
+        .cloned()
+        .collect();
+    point_type
+        .point_from_input(&inputs)
+        .map(Objet::Autre)
+        .map_err(|errors| errors.join(", "))
+}
+
+/// Reads the points of a CSV file. The type of each row is given by its `type` or `id` column, or
+/// else by `type_par_défaut`. Only an unreadable header is an error: the errors of the rows are
+/// returned with the points
+pub fn importe<R: io::Read>(
+    mut reader: R,
+    type_par_défaut: Option<&str>,
+    types: &[PointType],
+    registry: &Registry,
+    délimiteur: u8,
+) -> Result<ImportCsv, Box<dyn Error>> {
+    // The whole file is read to number the lines: the csv crate skips the empty lines, and then
+    // gives the line of the empty line to the next record
+    let mut texte = String::new();
+    reader.read_to_string(&mut texte)?;
+    let ligne_no = |position: Option<&::csv::Position>| {
+        let Some(position) = position else {
+            return 0;
+        };
+        let début = (position.byte() as usize).min(texte.len());
+        let vides = texte[début..]
+            .chars()
+            .take_while(|c| *c == '\n' || *c == '\r')
+            .filter(|c| *c == '\n')
+            .count();
+        (texte[..début].matches('\n').count() + vides + 1) as u64
+    };
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(délimiteur)
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(texte.as_bytes());
+    let entêtes: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|entête| entête.to_lowercase())
+        .collect();
+    let mut import = ImportCsv::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                import.erreurs.push(ErreurLigne {
+                    ligne: ligne_no(err.position()),
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let ligne: Vec<(String, String)> = entêtes
+            .iter()
+            .cloned()
+            .zip(record.iter().map(str::to_string))
+            .collect();
+        if ligne.iter().all(|(_, valeur)| valeur.is_empty()) {
+            continue;
+        }
+        match importe_ligne(&ligne, type_par_défaut, types, registry) {
+            Ok(objet) => import.objets.push(objet),
+            Err(message) => import.erreurs.push(ErreurLigne {
+                ligne: ligne_no(record.position()),
+                message,
+            }),
+        }
+    }
+    Ok(import)
+}
+
+#[test]
+fn test_csv() {
+    use crate::network::{Area, Network, SearchResult};
+
+    /// A network that knows a single benchmark
+    struct Réseau;
+    impl Network for Réseau {
+        fn name(&self) -> &str {
+            "ngf"
+        }
+        fn description(&self) -> &str {
+            ""
+        }
+        fn search(&self, _: &str) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+            Ok(vec![])
+        }
+        fn fetch(&self, key: &str) -> Result<Objet, Box<dyn Error>> {
+            match key {
+                "T'.D.S3 - 50" => Ok(Objet::Ngf(Box::new(crate::save::repère_test(key)))),
+                _ => Err("not found".into()),
+            }
+        }
+        fn fetch_area(&self, _: &Area) -> Result<Vec<Objet>, Box<dyn Error>> {
+            Ok(vec![])
+        }
+    }
+    let mut registry = Registry::empty();
+    registry.register(Box::new(Réseau));
+    let borne =
+        PointType::from_definition("borne", r#"{"numero": "str", "altitude": "float|None"}"#)
+            .unwrap();
+
+    let fichier = "type;matricule;numero;altitude;note\n\
+        ngf;T'.D.S3 - 50;;;\n\
+        borne;;B12;153.2;à revoir\n\
+        \n\
+        ngf;T'.D.S3 - 99;;;\n\
+        borne;;B13;haut;\n\
+        cadastre;;C1;;\n";
+    let import = importe(fichier.as_bytes(), None, &[borne], &registry, b';').unwrap();
+    assert_eq!(import.objets.len(), 2);
+    assert_eq!(import.objets[0].object_id(), "ngf/T'.D.S3 - 50@452592");
+    assert_eq!(import.objets[1].object_id(), "borne/B12");
+    assert_eq!(
+        import
+            .erreurs
+            .iter()
+            .map(|erreur| erreur.ligne)
+            .collect::<Vec<_>>(),
+        vec![5, 6, 7]
+    );
+    assert!(import.erreurs[1]
+        .to_string()
+        .starts_with("line 6: altitude"));
+
+    let points: Vec<PointExporté> = import
+        .objets
+        .into_iter()
+        .map(|objet| PointExporté::new(objet, &[], &[]))
+        .collect();
+    let colonnes: Vec<String> = ["id", "altitude", "support", "numero"]
+        .map(str::to_string)
+        .to_vec();
+    let mut csv: Vec<u8> = vec![];
+    exporte_points(&points, &colonnes, b',', &mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "id,altitude,support,numero\n\
+        ngf/T'.D.S3 - 50@452592,125.719,PONCEAU,\n\
+        borne/B12,153.2,,B12\n"
+    );
+    let colonnes = vec!["inconnue".to_string()];
+    assert!(exporte_points(&points, &colonnes, b',', io::sink()).is_err());
+    assert!(exporte_visites(&[], &[], &colonnes, b',', io::sink()).is_err());
+}
@@ -0,0 +1,334 @@
+//! FlatGeobuf export (https://flatgeobuf.org), a single layer of the points with typed columns.
+//! The header and the features are FlatBuffers tables, which are encoded here. There is no
+//! spatial index, which the format allows for small files
+use super::projection::Crs;
+use super::*;
+use std::{cmp::Reverse, io::Write};
+
+/// The magic bytes of version 3 of the format
+const MAGIC: [u8; 8] = [b'f', b'g', b'b', 3, b'f', b'g', b'b', 0];
+/// The Point value of the GeometryType enum
+const GEOMETRY_POINT: u8 = 1;
+
+/// A field of a FlatBuffers table
+enum Champ {
+    U8(u8),
+    U16(u16),
+    I32(i32),
+    U64(u64),
+    Texte(String),
+    Octets(Vec<u8>),
+    Réels(Vec<f64>),
+    Table(Table),
+    Tables(Vec<Table>),
+}
+
+impl Champ {
+    /// The size of the field in its table: scalars are inline, the rest is an offset
+    fn taille(&self) -> usize {
+        match self {
+            Champ::U8(_) => 1,
+            Champ::U16(_) => 2,
+            Champ::U64(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// A FlatBuffers table: the fields with their index in the schema
+#[derive(Default)]
+struct Table(Vec<(u16, Champ)>);
+
+impl Table {
+    fn champ(mut self, index: u16, champ: Champ) -> Table {
+        self.0.push((index, champ));
+        self
+    }
+}
+
+fn aligne(buffer: &mut Vec<u8>, alignement: usize) {
+    buffer.resize(buffer.len().next_multiple_of(alignement), 0);
+}
+
+fn écris_u32(buffer: &mut [u8], position: usize, valeur: u32) {
+    buffer[position..position + 4].copy_from_slice(&valeur.to_le_bytes());
+}
+
+/// Appends the vtable then the table, then the objects its fields point to (offsets must point
+/// forward). Returns the position of the table
+fn écris_table(buffer: &mut Vec<u8>, table: &Table) -> usize {
+    // The largest fields first, so that they are aligned without much padding
+    let mut ordre: Vec<usize> = (0..table.0.len()).collect();
+    ordre.sort_by_key(|&k| Reverse(table.0[k].1.taille()));
+    let mut disposition: Vec<(usize, usize)> = vec![];
+    let mut taille_table: usize = 4;
+    for k in ordre {
+        let taille = table.0[k].1.taille();
+        taille_table = taille_table.next_multiple_of(taille);
+        disposition.push((k, taille_table));
+        taille_table += taille;
+    }
+
+    aligne(buffer, 2);
+    let vtable = buffer.len();
+    let emplacements = table
+        .0
+        .iter()
+        .map(|(index, _)| *index + 1)
+        .max()
+        .unwrap_or(0);
+    buffer.extend_from_slice(&(4 + 2 * emplacements).to_le_bytes());
+    buffer.extend_from_slice(&(taille_table as u16).to_le_bytes());
+    for emplacement in 0..emplacements {
+        let décalage = disposition
+            .iter()
+            .find(|(k, _)| table.0[*k].0 == emplacement)
+            .map_or(0, |(_, décalage)| *décalage as u16);
+        buffer.extend_from_slice(&décalage.to_le_bytes());
+    }
+
+    aligne(buffer, 8);
+    let position = buffer.len();
+    buffer.extend_from_slice(&((position - vtable) as i32).to_le_bytes());
+    buffer.resize(position + taille_table, 0);
+    let mut enfants: Vec<(usize, &Champ)> = vec![];
+    for (k, décalage) in disposition {
+        let champ = &table.0[k].1;
+        let début = position + décalage;
+        match champ {
+            Champ::U8(valeur) => buffer[début] = *valeur,
+            Champ::U16(valeur) => buffer[début..début + 2].copy_from_slice(&valeur.to_le_bytes()),
+            Champ::I32(valeur) => buffer[début..début + 4].copy_from_slice(&valeur.to_le_bytes()),
+            Champ::U64(valeur) => buffer[début..début + 8].copy_from_slice(&valeur.to_le_bytes()),
+            _ => enfants.push((début, champ)),
+        }
+    }
+    for (début, champ) in enfants {
+        let cible = écris_objet(buffer, champ);
+        écris_u32(buffer, début, (cible - début) as u32);
+    }
+    position
+}
+
+/// Appends a string, a vector or a table. Returns its position
+fn écris_objet(buffer: &mut Vec<u8>, champ: &Champ) -> usize {
+    match champ {
+        Champ::Texte(texte) => {
+            aligne(buffer, 4);
+            let position = buffer.len();
+            buffer.extend_from_slice(&(texte.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(texte.as_bytes());
+            buffer.push(0);
+            position
+        }
+        Champ::Octets(octets) => {
+            aligne(buffer, 4);
+            let position = buffer.len();
+            buffer.extend_from_slice(&(octets.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(octets);
+            position
+        }
+        Champ::Réels(réels) => {
+            // The elements, after the length, are aligned on 8 bytes
+            aligne(buffer, 4);
+            if buffer.len().is_multiple_of(8) {
+                buffer.extend_from_slice(&[0; 4]);
+            }
+            let position = buffer.len();
+            buffer.extend_from_slice(&(réels.len() as u32).to_le_bytes());
+            for réel in réels {
+                buffer.extend_from_slice(&réel.to_le_bytes());
+            }
+            position
+        }
+        Champ::Table(table) => écris_table(buffer, table),
+        Champ::Tables(tables) => {
+            aligne(buffer, 4);
+            let position = buffer.len();
+            buffer.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+            buffer.resize(position + 4 + 4 * tables.len(), 0);
+            for (index, table) in tables.iter().enumerate() {
+                let début = position + 4 + 4 * index;
+                let cible = écris_table(buffer, table);
+                écris_u32(buffer, début, (cible - début) as u32);
+            }
+            position
+        }
+        Champ::U8(_) | Champ::U16(_) | Champ::I32(_) | Champ::U64(_) => {
+            unreachable!("scalars are inline")
+        }
+    }
+}
+
+/// The FlatBuffers buffer of the table, prefixed by its size
+fn buffer_préfixé(table: &Table) -> Vec<u8> {
+    let mut buffer = vec![0; 4];
+    let racine = écris_table(&mut buffer, table);
+    écris_u32(&mut buffer, 0, racine as u32);
+    let mut préfixé = (buffer.len() as u32).to_le_bytes().to_vec();
+    préfixé.append(&mut buffer);
+    préfixé
+}
+
+/// The ColumnType of an attribute
+fn type_colonne(attribut: &Attribut) -> u8 {
+    match attribut {
+        Attribut::Entier(_) => 7,
+        Attribut::Réel(_) => 10,
+        Attribut::Texte(_) => 11,
+        Attribut::Date(_) => 13,
+    }
+}
+
+/// The properties of a feature: the index of the column then the value, for the non-null values
+fn propriétés(attributs: &[(&str, Attribut)]) -> Vec<u8> {
+    let mut octets: Vec<u8> = vec![];
+    let texte = |octets: &mut Vec<u8>, texte: &str| {
+        octets.extend_from_slice(&(texte.len() as u32).to_le_bytes());
+        octets.extend_from_slice(texte.as_bytes());
+    };
+    for (index, (_, attribut)) in attributs.iter().enumerate() {
+        let colonne = (index as u16).to_le_bytes();
+        match attribut {
+            Attribut::Texte(Some(valeur)) => {
+                octets.extend_from_slice(&colonne);
+                texte(&mut octets, valeur);
+            }
+            Attribut::Réel(Some(valeur)) => {
+                octets.extend_from_slice(&colonne);
+                octets.extend_from_slice(&valeur.to_le_bytes());
+            }
+            Attribut::Entier(Some(valeur)) => {
+                octets.extend_from_slice(&colonne);
+                octets.extend_from_slice(&valeur.to_le_bytes());
+            }
+            Attribut::Date(Some(valeur)) => {
+                octets.extend_from_slice(&colonne);
+                texte(&mut octets, &valeur.to_string());
+            }
+            _ => {}
+        }
+    }
+    octets
+}
+
+/// Writes the points that have coordinates as a FlatGeobuf file, in the chosen coordinate system
+/// (see sans_coordonnées)
+pub fn flatgeobuf<W: Write>(
+    points: &[PointExporté],
+    crs: Crs,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let points: Vec<(&PointExporté, (f64, f64))> = points
+        .iter()
+        .filter_map(|point| {
+            let (longitude, latitude) = point.objet.coordonnées()?;
+            Some((point, crs.projette(longitude, latitude)))
+        })
+        .collect();
+    let colonnes = colonnes_attributs()
+        .iter()
+        .map(|(nom, attribut)| {
+            Table::default()
+                .champ(0, Champ::Texte(nom.to_string()))
+                .champ(1, Champ::U8(type_colonne(attribut)))
+        })
+        .collect();
+    let système = Table::default()
+        .champ(0, Champ::Texte("EPSG".to_string()))
+        .champ(1, Champ::I32(crs.epsg()))
+        .champ(2, Champ::Texte(crs.nom().to_string()))
+        .champ(4, Champ::Texte(crs.wkt().to_string()));
+    let mut entête = Table::default()
+        .champ(0, Champ::Texte("points".to_string()))
+        .champ(2, Champ::U8(GEOMETRY_POINT))
+        .champ(7, Champ::Tables(colonnes))
+        .champ(8, Champ::U64(points.len() as u64))
+        .champ(9, Champ::U16(0))
+        .champ(10, Champ::Table(système))
+        .champ(11, Champ::Texte("Géodésie de Bureau".to_string()));
+    if let Some((_, premier)) = points.first() {
+        let (mut min_x, mut min_y) = *premier;
+        let (mut max_x, mut max_y) = (min_x, min_y);
+        for (_, (x, y)) in &points {
+            (min_x, min_y) = (min_x.min(*x), min_y.min(*y));
+            (max_x, max_y) = (max_x.max(*x), max_y.max(*y));
+        }
+        entête = entête.champ(1, Champ::Réels(vec![min_x, min_y, max_x, max_y]));
+    }
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&buffer_préfixé(&entête))?;
+    for (point, (x, y)) in points {
+        let géométrie = Table::default().champ(1, Champ::Réels(vec![x, y]));
+        let feature = Table::default()
+            .champ(0, Champ::Table(géométrie))
+            .champ(1, Champ::Octets(propriétés(&point.attributs())));
+        writer.write_all(&buffer_préfixé(&feature))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_flatgeobuf() {
+    // Reads the FlatBuffers tables back: the position of a field, or None if it is absent
+    fn u32_à(buffer: &[u8], position: usize) -> usize {
+        u32::from_le_bytes(buffer[position..position + 4].try_into().unwrap()) as usize
+    }
+    fn champ(buffer: &[u8], table: usize, index: usize) -> Option<usize> {
+        let soffset = i32::from_le_bytes(buffer[table..table + 4].try_into().unwrap());
+        let vtable = (table as i64 - soffset as i64) as usize;
+        let taille_vtable = u16::from_le_bytes([buffer[vtable], buffer[vtable + 1]]) as usize;
+        if 4 + 2 * index >= taille_vtable {
+            return None;
+        }
+        let début = vtable + 4 + 2 * index;
+        match u16::from_le_bytes([buffer[début], buffer[début + 1]]) {
+            0 => None,
+            décalage => Some(table + décalage as usize),
+        }
+    }
+    fn suit(buffer: &[u8], position: usize) -> usize {
+        position + u32_à(buffer, position)
+    }
+
+    let mut repère = crate::save::repère_test("T'.D.S3 - 50");
+    repère.longitude = 1.409197;
+    repère.latitude = 43.649276;
+    let points = vec![PointExporté::new(Objet::Ngf(Box::new(repère)), &[], &[])];
+    let mut fichier: Vec<u8> = vec![];
+    flatgeobuf(&points, Crs::Wgs84, &mut fichier).unwrap();
+    assert_eq!(fichier[..8], MAGIC);
+
+    let taille_entête = u32_à(&fichier, 8);
+    let entête = &fichier[12..12 + taille_entête];
+    let racine = u32_à(entête, 0);
+    let nom = suit(entête, champ(entête, racine, 0).unwrap());
+    assert_eq!(&entête[nom + 4..nom + 4 + u32_à(entête, nom)], b"points");
+    let nombre = champ(entête, racine, 8).unwrap();
+    assert_eq!(nombre % 8, 0);
+    assert_eq!(entête[nombre], 1);
+    let index_node_size = champ(entête, racine, 9).unwrap();
+    assert_eq!(entête[index_node_size..index_node_size + 2], [0, 0]);
+    let colonnes = suit(entête, champ(entête, racine, 7).unwrap());
+    assert_eq!(u32_à(entête, colonnes), colonnes_attributs().len());
+    let altitude = suit(entête, colonnes + 4 + 4 * 3);
+    assert_eq!(entête[champ(entête, altitude, 1).unwrap()], 10);
+
+    let feature = &fichier[16 + taille_entête..];
+    assert_eq!(u32_à(&fichier, 12 + taille_entête), feature.len());
+    let racine = u32_à(feature, 0);
+    let géométrie = suit(feature, champ(feature, racine, 0).unwrap());
+    let xy = suit(feature, champ(feature, géométrie, 1).unwrap());
+    assert_eq!((xy + 4) % 8, 0);
+    assert_eq!(
+        f64::from_le_bytes(feature[xy + 4..xy + 12].try_into().unwrap()),
+        1.409197
+    );
+    let propriétés = suit(feature, champ(feature, racine, 1).unwrap());
+    // The first property is the id, in column 0
+    assert_eq!(feature[propriétés + 4..propriétés + 6], [0, 0]);
+    assert_eq!(
+        u32_à(feature, propriétés + 6),
+        "ngf/T'.D.S3 - 50@452592".len()
+    );
+}
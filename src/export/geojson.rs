@@ -0,0 +1,89 @@
+//! GeoJSON export, as a FeatureCollection whose markers follow the simplestyle-spec
+//! (https://github.com/mapbox/simplestyle-spec), which geojson.io, uMap and GitHub understand
+use super::*;
+use serde_json::{json, Map};
+
+/// The feature of a point. Points without coordinates have a null geometry
+pub fn feature(point: &PointExporté) -> Value {
+    let mut properties = Map::new();
+    properties.insert("id".to_string(), json!(point.objet.object_id()));
+    properties.insert("type".to_string(), json!(point.objet.type_name()));
+    properties.insert("nom".to_string(), json!(point.nom()));
+    properties.insert("altitude".to_string(), json!(point.altitude()));
+    properties.insert("departement".to_string(), json!(point.département()));
+    properties.insert("commune".to_string(), json!(point.commune()));
+    properties.insert(
+        "etat".to_string(),
+        json!(point.état().map(|état| état.to_string())),
+    );
+    properties.insert(
+        "derniere_visite".to_string(),
+        json!(point.dernière_visite.map(|date| date.to_string())),
+    );
+    properties.insert(
+        "nombre_de_visites".to_string(),
+        json!(point.nombre_de_visites),
+    );
+    properties.insert(
+        "dernier_etat".to_string(),
+        json!(point.dernier_état.as_ref().map(|état| état.to_string())),
+    );
+    properties.insert("nombre_de_photos".to_string(), json!(point.photos.len()));
+    // simplestyle-spec
+    properties.insert("title".to_string(), json!(point.nom()));
+    properties.insert(
+        "marker-color".to_string(),
+        json!(couleur_état(point.état().as_ref())),
+    );
+    properties.insert(
+        "marker-symbol".to_string(),
+        json!(if point.nombre_de_visites > 0 {
+            "star"
+        } else {
+            "circle"
+        }),
+    );
+    json!({
+        "type": "Feature",
+        "geometry": point.objet.coordonnées().map(|(longitude, latitude)| json!({
+            "type": "Point",
+            "coordinates": [longitude, latitude],
+        })),
+        "properties": properties,
+    })
+}
+
+/// The FeatureCollection of the points
+pub fn feature_collection(points: &[PointExporté]) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": points.iter().map(feature).collect::<Vec<Value>>(),
+    })
+}
+
+#[test]
+fn test_geojson() {
+    let mut repère = crate::save::repère_test("T'.D.S3 - 50");
+    repère.longitude = 1.409197;
+    repère.latitude = 43.649276;
+    let point = PointExporté {
+        objet: Objet::Ngf(Box::new(repère)),
+        dernière_visite: Some("2024-06-01".parse().unwrap()),
+        nombre_de_visites: 2,
+        dernier_état: Some(RnÉtatCode::Détruit),
+        photos: vec![],
+    };
+    let collection = feature_collection(&[point]);
+    assert_eq!(collection["type"], "FeatureCollection");
+    let feature = &collection["features"][0];
+    assert_eq!(
+        feature["geometry"]["coordinates"],
+        json!([1.409197, 43.649276])
+    );
+    let properties = &feature["properties"];
+    assert_eq!(properties["id"], "ngf/T'.D.S3 - 50@452592");
+    assert_eq!(properties["etat"], "Détruit");
+    assert_eq!(properties["derniere_visite"], "2024-06-01");
+    assert_eq!(properties["nombre_de_photos"], 0);
+    assert_eq!(properties["marker-color"], "#c62828");
+}
@@ -0,0 +1,287 @@
+//! GeoPackage export (OGC 12-128r18), written directly with SQLite: a `points` layer, a `visites`
+//! layer located at the visited points and a `photos` table, with typed columns, in the chosen
+//! coordinate system
+use super::projection::Crs;
+use super::*;
+use rusqlite::{params, params_from_iter, types::ToSql, Connection};
+use std::{error::Error, fs, path::Path};
+
+/// "GPKG" in ASCII, the application id of the GeoPackage files
+const APPLICATION_ID: i32 = 0x4750_4B47;
+/// Version 1.3.0 of the standard
+const USER_VERSION: i32 = 10300;
+
+const SCHÉMA: &str = "
+CREATE TABLE gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+INSERT INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system');
+CREATE TABLE visites (
+    fid INTEGER PRIMARY KEY AUTOINCREMENT,
+    geom POINT,
+    id INTEGER NOT NULL UNIQUE,
+    objet TEXT NOT NULL,
+    date DATE NOT NULL,
+    etat TEXT,
+    remarques TEXT
+);
+CREATE TABLE photos (
+    fid INTEGER PRIMARY KEY AUTOINCREMENT,
+    id INTEGER NOT NULL UNIQUE,
+    visite INTEGER NOT NULL REFERENCES visites(id),
+    fichier TEXT NOT NULL,
+    legende TEXT
+);
+";
+
+/// A point in the GeoPackage binary format: the header, without envelope, then the WKB point,
+/// both little-endian
+fn géométrie(srs_id: i32, (x, y): (f64, f64)) -> Vec<u8> {
+    let mut blob = vec![b'G', b'P', 0, 0b0000_0001];
+    blob.extend_from_slice(&srs_id.to_le_bytes());
+    blob.push(1);
+    blob.extend_from_slice(&1u32.to_le_bytes());
+    blob.extend_from_slice(&x.to_le_bytes());
+    blob.extend_from_slice(&y.to_le_bytes());
+    blob
+}
+
+/// The SQL type of the columns of an attribute
+fn type_sql(attribut: &Attribut) -> &'static str {
+    match attribut {
+        Attribut::Texte(_) => "TEXT",
+        Attribut::Réel(_) => "DOUBLE",
+        Attribut::Entier(_) => "INTEGER",
+        Attribut::Date(_) => "DATE",
+    }
+}
+
+fn valeur_sql(attribut: &Attribut) -> Box<dyn ToSql> {
+    match attribut.clone() {
+        Attribut::Texte(texte) => Box::new(texte),
+        Attribut::Réel(réel) => Box::new(réel),
+        Attribut::Entier(entier) => Box::new(entier),
+        Attribut::Date(date) => Box::new(date.map(|date| date.to_string())),
+    }
+}
+
+/// The bounds (min x, min y, max x, max y) of the coordinates
+fn emprise(coordonnées: &[(f64, f64)]) -> Option<(f64, f64, f64, f64)> {
+    coordonnées.iter().fold(None, |emprise, &(x, y)| {
+        Some(match emprise {
+            None => (x, y, x, y),
+            Some((min_x, min_y, max_x, max_y)) => {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            }
+        })
+    })
+}
+
+/// Writes the points, their visits (among `visites`) and their photos to a new GeoPackage file,
+/// replacing the file if it exists
+pub fn geopackage(
+    chemin: &Path,
+    points: &[PointExporté],
+    visites: &[Visite],
+    crs: Crs,
+) -> Result<(), Box<dyn Error>> {
+    if chemin.exists() {
+        fs::remove_file(chemin)?;
+    }
+    let mut connection = Connection::open(chemin)?;
+    connection.pragma_update(None, "application_id", APPLICATION_ID)?;
+    connection.pragma_update(None, "user_version", USER_VERSION)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHÉMA)?;
+    transaction.execute(
+        "INSERT INTO gpkg_spatial_ref_sys VALUES (?1, ?2, 'EPSG', ?2, ?3, NULL)",
+        params![crs.nom(), crs.epsg(), crs.wkt()],
+    )?;
+
+    let colonnes = colonnes_attributs();
+    let définitions: Vec<String> = colonnes
+        .iter()
+        .map(|(nom, attribut)| format!("{nom} {}", type_sql(attribut)))
+        .collect();
+    transaction.execute_batch(&format!(
+        "CREATE TABLE points (fid INTEGER PRIMARY KEY AUTOINCREMENT, geom POINT, {});",
+        définitions.join(", ")
+    ))?;
+    let insertion = format!(
+        "INSERT INTO points (geom, {}) VALUES (?1, {})",
+        colonnes
+            .iter()
+            .map(|(nom, _)| *nom)
+            .collect::<Vec<&str>>()
+            .join(", "),
+        (2..colonnes.len() + 2)
+            .map(|index| format!("?{index}"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let mut coordonnées: Vec<(f64, f64)> = vec![];
+    for point in points {
+        let position = point
+            .objet
+            .coordonnées()
+            .map(|(longitude, latitude)| crs.projette(longitude, latitude));
+        coordonnées.extend(position);
+        let mut valeurs: Vec<Box<dyn ToSql>> = vec![Box::new(
+            position.map(|position| géométrie(crs.epsg(), position)),
+        )];
+        valeurs.extend(
+            point
+                .attributs()
+                .iter()
+                .map(|(_, attribut)| valeur_sql(attribut)),
+        );
+        transaction.execute(&insertion, params_from_iter(valeurs.iter()))?;
+
+        let id = point.objet.object_id();
+        for visite in visites.iter().filter(|visite| visite.objet == id) {
+            transaction.execute(
+                "INSERT INTO visites (geom, id, objet, date, etat, remarques) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    position.map(|position| géométrie(crs.epsg(), position)),
+                    visite.id as i64,
+                    visite.objet,
+                    visite.date.to_string(),
+                    visite.état.as_ref().map(|état| état.to_string()),
+                    visite.remarques,
+                ],
+            )?;
+        }
+        for photo in &point.photos {
+            transaction.execute(
+                "INSERT OR IGNORE INTO photos (id, visite, fichier, legende) VALUES (?1, ?2, ?3, ?4)",
+                params![photo.id as i64, photo.visite as i64, photo.fichier, photo.légende],
+            )?;
+        }
+    }
+
+    let emprise = emprise(&coordonnées);
+    for (table, data_type, description) in [
+        ("points", "features", "Points enregistrés"),
+        ("visites", "features", "Visites des points"),
+        ("photos", "attributes", "Photos prises pendant les visites"),
+    ] {
+        let features = data_type == "features";
+        let emprise = emprise.filter(|_| features);
+        transaction.execute(
+            "INSERT INTO gpkg_contents (table_name, data_type, identifier, description, min_x, min_y, max_x, max_y, srs_id)
+            VALUES (?1, ?2, ?1, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                table,
+                data_type,
+                description,
+                emprise.map(|emprise| emprise.0),
+                emprise.map(|emprise| emprise.1),
+                emprise.map(|emprise| emprise.2),
+                emprise.map(|emprise| emprise.3),
+                Some(crs.epsg()).filter(|_| features),
+            ],
+        )?;
+        if features {
+            transaction.execute(
+                "INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', 'POINT', ?2, 0, 0)",
+                params![table, crs.epsg()],
+            )?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+#[test]
+fn test_geopackage() {
+    let mut repère = crate::save::repère_test("T'.D.S3 - 50");
+    repère.longitude = 1.409197;
+    repère.latitude = 43.649276;
+    let visites = vec![Visite {
+        id: 4,
+        objet: "ngf/T'.D.S3 - 50@452592".to_string(),
+        date: "2024-06-01".parse().unwrap(),
+        heure: None,
+        état: Some(RnÉtatCode::MauvaisÉtat),
+        remarques: "".to_string(),
+        à_vérifier: false,
+    }];
+    let photos = vec![Photo {
+        id: 7,
+        visite: 4,
+        fichier: "photo.jpg".to_string(),
+        légende: "De face".to_string(),
+    }];
+    let points = vec![PointExporté::new(
+        Objet::Ngf(Box::new(repère)),
+        &visites,
+        &photos,
+    )];
+    let chemin = crate::save::temporary_directory("points.gpkg");
+    geopackage(&chemin, &points, &visites, Crs::Lambert93).unwrap();
+
+    let connection = Connection::open(&chemin).unwrap();
+    let application_id: i32 = connection
+        .query_row("PRAGMA application_id", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(application_id, APPLICATION_ID);
+    let (nom, visites, altitude, géométrie): (String, i64, f64, Vec<u8>) = connection
+        .query_row(
+            "SELECT nom, nombre_de_visites, altitude, geom FROM points",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap();
+    assert_eq!(nom, "T'.D.S3 - 50");
+    assert_eq!(visites, 1);
+    assert_eq!(altitude, points[0].altitude().unwrap());
+    assert_eq!(&géométrie[..8], &[b'G', b'P', 0, 1, 0x6a, 0x08, 0, 0]);
+    let x = f64::from_le_bytes(géométrie[13..21].try_into().unwrap());
+    assert!((x - 571_630.0).abs() < 10.0);
+    let (type_sql, srs_id): (String, i32) = connection
+        .query_row(
+            "SELECT geometry_type_name, srs_id FROM gpkg_geometry_columns WHERE table_name = 'visites'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((type_sql.as_str(), srs_id), ("POINT", 2154));
+    let légende: String = connection
+        .query_row("SELECT legende FROM photos WHERE visite = 4", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(légende, "De face");
+    let _ = fs::remove_file(&chemin);
+}
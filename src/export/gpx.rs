@@ -0,0 +1,134 @@
+//! GPX export for handheld GPS units: the points as waypoints and, for a planned trip, a route
+//! through them in order
+use super::*;
+use std::fmt::Write;
+
+/// The Garmin symbol of the waypoints of this type of point
+fn symbole(objet: &Objet) -> &'static str {
+    match objet {
+        Objet::Ngf(_) => "Pin, Blue",
+        Objet::Site(_) => "Flag, Red",
+        Objet::Crue(_) => "Dam",
+        Objet::Autre(_) => "Waypoint",
+    }
+}
+
+/// Where to find the point: its localisation, its support and the part of the support
+pub fn description(objet: &Objet) -> String {
+    let parties: Vec<Option<String>> = match objet {
+        Objet::Ngf(repère) => vec![
+            repère.localisation.clone(),
+            Some(repère.support.clone()),
+            repère.partie_support.clone(),
+        ],
+        Objet::Site(site) => vec![
+            site.localisation.clone(),
+            site.points.first().and_then(|point| point.support.clone()),
+        ],
+        Objet::Crue(crue) => vec![crue.localisation.clone(), Some(crue.support.clone())],
+        Objet::Autre(point) => vec![
+            champ_texte(&point.champs, &["localisation"]),
+            champ_texte(&point.champs, &["support"]),
+            champ_texte(&point.champs, &["partie_support"]),
+        ],
+    };
+    parties
+        .into_iter()
+        .flatten()
+        .map(|partie| partie.trim().to_string())
+        .filter(|partie| !partie.is_empty())
+        .collect::<Vec<String>>()
+        .join(". ")
+}
+
+/// The GPX document. If `route` is given, it also has a route with this name through the points,
+/// in their order. The points without coordinates are left out, see sans_coordonnées
+pub fn gpx(points: &[PointExporté], route: Option<&str>) -> String {
+    let mut gpx = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <gpx version=\"1.1\" creator=\"Géodésie de Bureau\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    let points: Vec<(&PointExporté, (f64, f64))> = points
+        .iter()
+        .filter_map(|point| Some((point, point.objet.coordonnées()?)))
+        .collect();
+    for (point, (longitude, latitude)) in &points {
+        writeln!(gpx, "    <wpt lat=\"{latitude}\" lon=\"{longitude}\">").unwrap();
+        if let Some(altitude) = point.altitude() {
+            writeln!(gpx, "        <ele>{altitude}</ele>").unwrap();
+        }
+        writeln!(gpx, "        <name>{}</name>", échappe_xml(&point.nom())).unwrap();
+        let description = description(&point.objet);
+        if !description.is_empty() {
+            writeln!(gpx, "        <desc>{}</desc>", échappe_xml(&description)).unwrap();
+        }
+        writeln!(gpx, "        <sym>{}</sym>", symbole(&point.objet)).unwrap();
+        writeln!(
+            gpx,
+            "        <type>{}</type>",
+            échappe_xml(point.objet.type_name())
+        )
+        .unwrap();
+        writeln!(gpx, "    </wpt>").unwrap();
+    }
+    if let Some(route) = route {
+        writeln!(
+            gpx,
+            "    <rte>\n        <name>{}</name>",
+            échappe_xml(route)
+        )
+        .unwrap();
+        for (point, (longitude, latitude)) in &points {
+            writeln!(
+                gpx,
+                "        <rtept lat=\"{latitude}\" lon=\"{longitude}\"><name>{}</name><sym>{}</sym></rtept>",
+                échappe_xml(&point.nom()),
+                symbole(&point.objet)
+            )
+            .unwrap();
+        }
+        writeln!(gpx, "    </rte>").unwrap();
+    }
+    gpx.push_str("</gpx>\n");
+    gpx
+}
+
+#[test]
+fn test_gpx() {
+    let point = |matricule: &str, latitude: f64| {
+        let mut repère = crate::save::repère_test(matricule);
+        repère.latitude = latitude;
+        repère.localisation = Some("AU PONT".to_string());
+        repère.support = "PONCEAU".to_string();
+        repère.partie_support = None;
+        PointExporté {
+            objet: Objet::Ngf(Box::new(repère)),
+            dernière_visite: None,
+            nombre_de_visites: 0,
+            dernier_état: None,
+            photos: vec![],
+        }
+    };
+    let points = vec![point("T'.D.S3 - 52", 43.65), point("T'.D.S3 - 50", 43.64)];
+    let document = gpx(&points, Some("Sortie <Garonne>"));
+    assert!(document.contains("<name>T'.D.S3 - 52</name>\n        <desc>AU PONT. PONCEAU</desc>"));
+    assert!(document.contains("<sym>Pin, Blue</sym>"));
+    assert!(document.contains("<name>Sortie &lt;Garonne&gt;</name>"));
+    let route = &document[document.find("<rte>").unwrap()..];
+    assert!(route.find("T'.D.S3 - 52").unwrap() < route.find("T'.D.S3 - 50").unwrap());
+    assert!(!gpx(&points, None).contains("<rte>"));
+    let sans_coordonnées = PointExporté {
+        objet: Objet::Autre(crate::custom_types::PointPersonnalisé {
+            type_name: "borne".to_string(),
+            clé: "numéro".to_string(),
+            champs: serde_json::json!({ "numéro": "B12" })
+                .as_object()
+                .unwrap()
+                .clone(),
+        }),
+        ..points[0].clone()
+    };
+    let points = vec![points[0].clone(), sans_coordonnées];
+    assert_eq!(super::sans_coordonnées(&points), vec![&points[1]]);
+    assert!(!gpx(&points, None).contains("B12"));
+}
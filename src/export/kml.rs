@@ -0,0 +1,319 @@
+//! KML export for Google Earth, and KMZ export bundling thumbnails of the photos. The placemarks
+//! are styled by the state of the points and by whether we visited them, and grouped into folders
+use super::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Write as _,
+    io::{Cursor, Seek, Write},
+    str::FromStr,
+};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+/// The size of the thumbnails of the KMZ export, in pixels
+const TAILLE_MINIATURE: u32 = 400;
+
+/// How the placemarks are grouped into folders
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Dossiers {
+    Département,
+    /// The levelling line of the benchmarks (the other points are in their own folder)
+    Ligne,
+}
+
+impl FromStr for Dossiers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "departement" => Ok(Dossiers::Département),
+            "ligne" => Ok(Dossiers::Ligne),
+            _ => Err(format!(
+                "Unknown grouping: '{s}' (expected departement or ligne)"
+            )),
+        }
+    }
+}
+
+impl Dossiers {
+    fn nom(&self, point: &PointExporté) -> String {
+        match (self, &point.objet) {
+            (Dossiers::Département, _) => match point.département() {
+                Some(département) => format!("Département {département}"),
+                None => "Sans département".to_string(),
+            },
+            (Dossiers::Ligne, Objet::Ngf(repère)) => repère.matricule.ligne().to_string(),
+            (Dossiers::Ligne, _) => "Autres points".to_string(),
+        }
+    }
+}
+
+/// The states that have a style, and their codes
+const ÉTATS: [(Option<RnÉtatCode>, &str); 8] = [
+    (Some(RnÉtatCode::BonÉtat), "E"),
+    (Some(RnÉtatCode::MauvaisÉtat), "M"),
+    (Some(RnÉtatCode::Imprenable), "I"),
+    (Some(RnÉtatCode::NonRetrouvé), "N"),
+    (Some(RnÉtatCode::PresuméDéplacé), "P"),
+    (Some(RnÉtatCode::Détruit), "D"),
+    (Some(RnÉtatCode::DétruitAprèsObservation), "Y"),
+    (None, "inconnu"),
+];
+
+/// The id of the style of the point
+fn style(point: &PointExporté) -> String {
+    let état = point.état();
+    let code = ÉTATS
+        .iter()
+        .find(|(candidat, _)| *candidat == état)
+        .map(|(_, code)| *code)
+        .unwrap_or("inconnu");
+    let visite = if point.nombre_de_visites > 0 {
+        "visite"
+    } else {
+        "non-visite"
+    };
+    format!("{code}-{visite}")
+}
+
+/// A `#rrggbb` colour in the `aabbggrr` format of KML
+fn couleur_kml(couleur: &str) -> String {
+    let couleur = couleur.trim_start_matches('#');
+    format!("ff{}{}{}", &couleur[4..6], &couleur[2..4], &couleur[0..2])
+}
+
+/// The image to show for each photo id
+type Sources = HashMap<u64, String>;
+
+/// The HTML balloon of the point
+fn bulle(point: &PointExporté, sources: &Sources) -> String {
+    let mut lignes: Vec<(&str, String)> = vec![];
+    match &point.objet {
+        Objet::Ngf(repère) => {
+            lignes.push(("Matricule", repère.matricule.to_string()));
+            lignes.push((
+                "Altitude",
+                format!("{}m ({})", repère.altitude, repère.système_altimétrique),
+            ));
+            lignes.push(("Type", repère.rn_type.to_string()));
+            lignes.push(("État", repère.état.to_string()));
+            lignes.push((
+                "Commune",
+                format!("{} ({})", repère.commune, repère.département),
+            ));
+            if let Some(localisation) = &repère.localisation {
+                lignes.push(("Localisation", localisation.clone()));
+            }
+            lignes.push(("Support", repère.support.clone()));
+            if let Some(partie) = &repère.partie_support {
+                lignes.push(("Partie du support", partie.clone()));
+            }
+            lignes.push(("Voie suivie", repère.voie_suivie.clone()));
+            lignes.push(("Dernière visite IGN", repère.dernière_visite.clone()));
+        }
+        objet => {
+            lignes.push(("Identifiant", point.nom()));
+            lignes.push(("Type", objet.type_name().to_string()));
+            if let Some(altitude) = point.altitude() {
+                lignes.push(("Altitude", format!("{altitude}m")));
+            }
+            if let Some(état) = point.état() {
+                lignes.push(("État", état.to_string()));
+            }
+            if let Some(commune) = point.commune() {
+                lignes.push(("Commune", commune));
+            }
+        }
+    }
+    lignes.push(("Visites", point.nombre_de_visites.to_string()));
+    if let Some(date) = point.dernière_visite {
+        lignes.push(("Notre dernière visite", date.to_string()));
+    }
+    if let Some(état) = &point.dernier_état {
+        lignes.push(("État constaté", état.to_string()));
+    }
+    let mut html = String::from("<table>");
+    for (label, valeur) in lignes.into_iter().filter(|(_, valeur)| !valeur.is_empty()) {
+        write!(
+            html,
+            "<tr><th align=\"left\">{label}</th><td>{}</td></tr>",
+            échappe_xml(&valeur)
+        )
+        .unwrap();
+    }
+    html.push_str("</table>");
+    if let Objet::Ngf(repère) = &point.objet {
+        write!(
+            html,
+            "<p><a href=\"{}\">Fiche en ligne</a></p>",
+            échappe_xml(&repère.fiche_url)
+        )
+        .unwrap();
+    }
+    for photo in &point.photos {
+        if let Some(source) = sources.get(&photo.id) {
+            write!(
+                html,
+                "<p><img src=\"{}\" width=\"{TAILLE_MINIATURE}\"/><br/>{}</p>",
+                échappe_xml(source),
+                échappe_xml(&photo.légende)
+            )
+            .unwrap();
+        }
+    }
+    html
+}
+
+/// The KML document
+fn document(points: &[PointExporté], dossiers: Dossiers, sources: &Sources) -> String {
+    let mut kml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n\
+        <Document>\n\
+        \x20   <name>Géodésie de Bureau</name>\n",
+    );
+    for (état, code) in ÉTATS {
+        for (visite, icône) in [("visite", "wht-stars"), ("non-visite", "wht-blank")] {
+            writeln!(
+                kml,
+                "    <Style id=\"{code}-{visite}\"><IconStyle><color>{}</color><Icon><href>http://maps.google.com/mapfiles/kml/paddle/{icône}.png</href></Icon></IconStyle></Style>",
+                couleur_kml(couleur_état(état.as_ref()))
+            )
+            .unwrap();
+        }
+    }
+    let mut par_dossier: BTreeMap<String, Vec<&PointExporté>> = BTreeMap::new();
+    for point in points {
+        par_dossier
+            .entry(dossiers.nom(point))
+            .or_default()
+            .push(point);
+    }
+    for (dossier, points) in par_dossier {
+        writeln!(
+            kml,
+            "    <Folder>\n        <name>{}</name>",
+            échappe_xml(&dossier)
+        )
+        .unwrap();
+        for point in points {
+            writeln!(kml, "        <Placemark>").unwrap();
+            writeln!(
+                kml,
+                "            <name>{}</name>",
+                échappe_xml(&point.nom())
+            )
+            .unwrap();
+            writeln!(kml, "            <styleUrl>#{}</styleUrl>", style(point)).unwrap();
+            writeln!(
+                kml,
+                "            <description><![CDATA[{}]]></description>",
+                bulle(point, sources).replace("]]>", "]]]]><![CDATA[>")
+            )
+            .unwrap();
+            if let Some((longitude, latitude)) = point.objet.coordonnées() {
+                let altitude = point.altitude().unwrap_or(0.0);
+                writeln!(
+                    kml,
+                    "            <Point><coordinates>{longitude},{latitude},{altitude}</coordinates></Point>"
+                )
+                .unwrap();
+            }
+            writeln!(kml, "        </Placemark>").unwrap();
+        }
+        writeln!(kml, "    </Folder>").unwrap();
+    }
+    kml.push_str("</Document>\n</kml>\n");
+    kml
+}
+
+/// The KML export. The balloons show the photos from their files
+pub fn kml(points: &[PointExporté], dossiers: Dossiers) -> String {
+    let sources: Sources = points
+        .iter()
+        .flat_map(|point| &point.photos)
+        .map(|photo| (photo.id, format!("file://{}", photo.fichier)))
+        .collect();
+    document(points, dossiers, &sources)
+}
+
+/// A JPEG thumbnail of the photo
+pub(crate) fn miniature(photo: &Photo) -> Result<Vec<u8>, Box<dyn Error>> {
+    let image = image::open(&photo.fichier)?.thumbnail(TAILLE_MINIATURE, TAILLE_MINIATURE);
+    let mut jpeg: Vec<u8> = vec![];
+    image::DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)?;
+    Ok(jpeg)
+}
+
+/// The KMZ export: the KML document and the thumbnails of the photos. Returns the photos that
+/// could not be read, which are left out
+pub fn kmz<W: Write + Seek>(
+    points: &[PointExporté],
+    dossiers: Dossiers,
+    writer: W,
+) -> Result<Vec<Photo>, Box<dyn Error>> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+    let mut sources: Sources = HashMap::new();
+    let mut illisibles: Vec<Photo> = vec![];
+    for photo in points.iter().flat_map(|point| &point.photos) {
+        if sources.contains_key(&photo.id) {
+            continue;
+        }
+        match miniature(photo) {
+            Ok(jpeg) => {
+                let chemin = format!("files/photo-{}.jpg", photo.id);
+                zip.start_file(chemin.as_str(), options)?;
+                zip.write_all(&jpeg)?;
+                sources.insert(photo.id, chemin);
+            }
+            Err(_) => illisibles.push(photo.clone()),
+        }
+    }
+    // Google Earth reads the first .kml file of the archive
+    zip.start_file("doc.kml", options)?;
+    zip.write_all(document(points, dossiers, &sources).as_bytes())?;
+    zip.finish()?;
+    Ok(illisibles)
+}
+
+#[test]
+fn test_kml() {
+    let point = |matricule: &str, département: &str, visites: usize, photo: u64| {
+        let mut repère = crate::save::repère_test(matricule);
+        repère.département = département.to_string();
+        PointExporté {
+            objet: Objet::Ngf(Box::new(repère)),
+            dernière_visite: None,
+            nombre_de_visites: visites,
+            dernier_état: None,
+            photos: vec![Photo {
+                id: photo,
+                visite: 1,
+                fichier: "/nulle/part.jpg".to_string(),
+                légende: "Vue <de face>".to_string(),
+            }],
+        }
+    };
+    let points = vec![
+        point("T'.D.S3 - 50", "31", 1, 1),
+        point("N.P.K3Q3 - 56", "32", 0, 2),
+    ];
+    let kml = kml(&points, Dossiers::Département);
+    assert!(kml.contains("<name>Département 31</name>"));
+    assert!(kml.contains("<name>T'.D.S3 - 50</name>\n            <styleUrl>#E-visite</styleUrl>"));
+    assert!(kml.contains("<styleUrl>#E-non-visite</styleUrl>"));
+    assert!(kml.contains("<Style id=\"D-visite\"><IconStyle><color>ff2828c6</color>"));
+    assert!(
+        kml.contains("<img src=\"file:///nulle/part.jpg\" width=\"400\"/><br/>Vue &lt;de face&gt;")
+    );
+    assert_eq!(Dossiers::Ligne.nom(&points[1]), "N.P.K3Q3");
+
+    // The photo does not exist, so the KMZ has no thumbnail
+    let mut kmz_bytes: Vec<u8> = vec![];
+    let illisibles = kmz(&points, Dossiers::Ligne, Cursor::new(&mut kmz_bytes)).unwrap();
+    assert_eq!(illisibles.len(), 2);
+    let archive = zip::ZipArchive::new(Cursor::new(kmz_bytes)).unwrap();
+    assert_eq!(archive.file_names().collect::<Vec<_>>(), vec!["doc.kml"]);
+}
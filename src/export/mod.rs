@@ -0,0 +1,345 @@
+//! Exports of the saved points to formats other tools can read, with what the visits say about
+//! each point
+pub mod csv;
+pub mod flatgeobuf;
+pub mod geojson;
+pub mod geopackage;
+pub mod gpx;
+pub mod kml;
+pub mod projection;
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::common_traits::Identified;
+use crate::custom_types::PointPersonnalisé;
+use crate::ngf::json_mappings::bbox::RnÉtatCode;
+use crate::save::{Objet, Photo, Store, StoreResult, Visite};
+
+/// A saved point, with the summary of its visits
+#[derive(Clone, PartialEq, Debug)]
+pub struct PointExporté {
+    pub objet: Objet,
+    pub dernière_visite: Option<NaiveDate>,
+    pub nombre_de_visites: usize,
+    /// The state recorded by the last visit that recorded one
+    pub dernier_état: Option<RnÉtatCode>,
+    /// The photos taken during the visits
+    pub photos: Vec<Photo>,
+}
+
+impl PointExporté {
+    /// Summarises the confirmed visits of the point (the visits and photos may be those of any
+    /// point)
+    pub fn new(objet: Objet, visites: &[Visite], photos: &[Photo]) -> PointExporté {
+        let id = objet.object_id();
+        let mut visites: Vec<&Visite> = visites
+            .iter()
+            .filter(|visite| visite.objet == id && !visite.à_vérifier)
+            .collect();
+        visites.sort_by_key(|visite| (visite.date, visite.id));
+        PointExporté {
+            dernière_visite: visites.last().map(|visite| visite.date),
+            nombre_de_visites: visites.len(),
+            dernier_état: visites.iter().rev().find_map(|visite| visite.état.clone()),
+            photos: photos
+                .iter()
+                .filter(|photo| visites.iter().any(|visite| visite.id == photo.visite))
+                .cloned()
+                .collect(),
+            objet,
+        }
+    }
+
+    /// The key of the point in its network, such as the matricule of a benchmark (without the cid
+    /// that tells homonyms apart)
+    pub fn nom(&self) -> String {
+        if let Objet::Ngf(repère) = &self.objet {
+            return repère.matricule.to_string();
+        }
+        let id = self.objet.object_id();
+        match id.split_once('/') {
+            Some((_, nom)) => nom.to_string(),
+            None => id,
+        }
+    }
+
+    pub fn altitude(&self) -> Option<f64> {
+        match &self.objet {
+            Objet::Ngf(repère) => repère.altitude_m(),
+            Objet::Site(site) => site.points.iter().find_map(|point| point.altitude),
+            Objet::Crue(crue) => crue.altitude(None),
+            Objet::Autre(point) => point.champs.get("altitude").and_then(Value::as_f64),
+        }
+    }
+
+    pub fn département(&self) -> Option<String> {
+        let département = match &self.objet {
+            Objet::Ngf(repère) => repère.département.clone(),
+            Objet::Site(site) => site.département.clone(),
+            Objet::Crue(crue) => département_from_insee(&crue.insee),
+            Objet::Autre(point) => champ_texte(&point.champs, &["département", "departement"])
+                .or_else(|| {
+                    champ_texte(&point.champs, &["insee"])
+                        .map(|insee| département_from_insee(&insee))
+                })
+                .unwrap_or_default(),
+        };
+        Some(département).filter(|département| !département.is_empty())
+    }
+
+    pub fn commune(&self) -> Option<String> {
+        let commune = match &self.objet {
+            Objet::Ngf(repère) => repère.commune.clone(),
+            Objet::Site(site) => site.commune.clone(),
+            Objet::Crue(crue) => crue.commune.clone(),
+            Objet::Autre(point) => champ_texte(&point.champs, &["commune"]).unwrap_or_default(),
+        };
+        Some(commune).filter(|commune| !commune.is_empty())
+    }
+
+    /// The state of the point: the one seen during the last visit, or else the one known by the
+    /// network
+    pub fn état(&self) -> Option<RnÉtatCode> {
+        self.dernier_état.clone().or(match &self.objet {
+            Objet::Ngf(repère) => Some(repère.état.clone()),
+            Objet::Crue(crue) => Some(crue.état.clone()),
+            _ => None,
+        })
+    }
+
+    /// The attributes of the point in the GIS exports, with their names
+    pub fn attributs(&self) -> Vec<(&'static str, Attribut)> {
+        vec![
+            ("id", Attribut::Texte(Some(self.objet.object_id()))),
+            (
+                "type",
+                Attribut::Texte(Some(self.objet.type_name().to_string())),
+            ),
+            ("nom", Attribut::Texte(Some(self.nom()))),
+            ("altitude", Attribut::Réel(self.altitude())),
+            ("departement", Attribut::Texte(self.département())),
+            ("commune", Attribut::Texte(self.commune())),
+            (
+                "etat",
+                Attribut::Texte(self.état().map(|état| état.to_string())),
+            ),
+            ("derniere_visite", Attribut::Date(self.dernière_visite)),
+            (
+                "nombre_de_visites",
+                Attribut::Entier(Some(self.nombre_de_visites as i64)),
+            ),
+            (
+                "dernier_etat",
+                Attribut::Texte(self.dernier_état.as_ref().map(|état| état.to_string())),
+            ),
+            (
+                "nombre_de_photos",
+                Attribut::Entier(Some(self.photos.len() as i64)),
+            ),
+        ]
+    }
+}
+
+/// A typed attribute of the points in the GIS exports
+#[derive(Clone, PartialEq, Debug)]
+pub enum Attribut {
+    Texte(Option<String>),
+    Réel(Option<f64>),
+    Entier(Option<i64>),
+    Date(Option<NaiveDate>),
+}
+
+impl Attribut {
+    /// The value of the attribute in JSON, with the dates as YYYY-MM-DD
+    pub fn json(&self) -> Value {
+        match self {
+            Attribut::Texte(texte) => serde_json::json!(texte),
+            Attribut::Réel(réel) => serde_json::json!(réel),
+            Attribut::Entier(entier) => serde_json::json!(entier),
+            Attribut::Date(date) => serde_json::json!(date.map(|date| date.to_string())),
+        }
+    }
+}
+
+/// The names and types of the attributes of every point (see PointExporté::attributs)
+pub fn colonnes_attributs() -> Vec<(&'static str, Attribut)> {
+    let point = PointPersonnalisé {
+        type_name: String::new(),
+        clé: String::new(),
+        champs: serde_json::Map::new(),
+    };
+    PointExporté::new(Objet::Autre(point), &[], &[]).attributs()
+}
+
+/// The département of a commune, from its INSEE number (3 digits overseas)
+fn département_from_insee(insee: &str) -> String {
+    let longueur = if insee.starts_with("97") { 3 } else { 2 };
+    insee.chars().take(longueur).collect()
+}
+
+fn champ_texte(champs: &serde_json::Map<String, Value>, noms: &[&str]) -> Option<String> {
+    noms.iter()
+        .find_map(|nom| champs.get(*nom))
+        .and_then(|valeur| match valeur {
+            Value::String(texte) => Some(texte.clone()),
+            Value::Null => None,
+            valeur => Some(valeur.to_string()),
+        })
+}
+
+/// Escapes the text of XML elements and attributes
+pub(crate) fn échappe_xml(texte: &str) -> String {
+    texte
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The colour of the markers of the points in this state
+pub fn couleur_état(état: Option<&RnÉtatCode>) -> &'static str {
+    match état {
+        Some(RnÉtatCode::BonÉtat) => "#2e7d32",
+        Some(RnÉtatCode::MauvaisÉtat) | Some(RnÉtatCode::Imprenable) => "#f9a825",
+        Some(RnÉtatCode::NonRetrouvé) | Some(RnÉtatCode::PresuméDéplacé) => "#6a1b9a",
+        Some(RnÉtatCode::Détruit) | Some(RnÉtatCode::DétruitAprèsObservation) => "#c62828",
+        None => "#757575",
+    }
+}
+
+/// Which points to export. Empty lists accept every point
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Filtre {
+    /// Object ids of the points (see Identified). The points are exported in this order, for
+    /// instance the order of a planned trip
+    pub ids: Vec<String>,
+    /// Type names of the points (see Objet::type_name)
+    pub types: Vec<String>,
+    pub départements: Vec<String>,
+    /// Only the visited points (true) or the points never visited (false)
+    pub visité: Option<bool>,
+}
+
+impl Filtre {
+    pub fn accepte(&self, point: &PointExporté) -> bool {
+        (self.ids.is_empty() || self.ids.contains(&point.objet.object_id()))
+            && (self.types.is_empty() || self.types.iter().any(|t| t == point.objet.type_name()))
+            && (self.départements.is_empty()
+                || point
+                    .département()
+                    .is_some_and(|département| self.départements.contains(&département)))
+            && self
+                .visité
+                .is_none_or(|visité| visité == (point.nombre_de_visites > 0))
+    }
+}
+
+/// The points without coordinates, which the GPX and FlatGeobuf exports leave out
+pub fn sans_coordonnées(points: &[PointExporté]) -> Vec<&PointExporté> {
+    points
+        .iter()
+        .filter(|point| point.objet.coordonnées().is_none())
+        .collect()
+}
+
+/// The points of the store accepted by the filter
+pub fn points_exportés(store: &dyn Store, filtre: &Filtre) -> StoreResult<Vec<PointExporté>> {
+    let visites = store.visites()?;
+    let photos = store.photos()?;
+    let mut points: Vec<PointExporté> = store
+        .objets()?
+        .into_iter()
+        .map(|objet| PointExporté::new(objet, &visites, &photos))
+        .filter(|point| filtre.accepte(point))
+        .collect();
+    if !filtre.ids.is_empty() {
+        if let Some(id) = filtre
+            .ids
+            .iter()
+            .find(|id| points.iter().all(|point| &point.objet.object_id() != *id))
+        {
+            return Err(format!("'{id}' is not saved, or is left out by the filters").into());
+        }
+        points.sort_by_key(|point| {
+            let id = point.objet.object_id();
+            filtre.ids.iter().position(|candidat| *candidat == id)
+        });
+    }
+    Ok(points)
+}
+
+#[test]
+fn test_points_exportés() {
+    use crate::save::{repère_test, MemoryStore, SaveJSON};
+    let visite = |id: u64, objet: &str, date: &str, état: Option<RnÉtatCode>| Visite {
+        id,
+        objet: objet.to_string(),
+        date: date.parse().unwrap(),
+        heure: None,
+        état,
+        remarques: "".to_string(),
+        à_vérifier: false,
+    };
+    let store = MemoryStore::new(SaveJSON {
+        objets: vec![
+            Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))),
+            Objet::Ngf(Box::new(repère_test("T'.D.S3 - 52"))),
+        ],
+        visites: vec![
+            visite(
+                1,
+                "ngf/T'.D.S3 - 50@452592",
+                "2024-05-01",
+                Some(RnÉtatCode::MauvaisÉtat),
+            ),
+            visite(2, "ngf/T'.D.S3 - 50@452592", "2024-06-01", None),
+            visite(3, "ngf/T'.D.S3 - 52@452592", "2023-01-01", None),
+        ],
+        photos: vec![Photo {
+            id: 1,
+            visite: 1,
+            fichier: "photo.jpg".to_string(),
+            légende: "".to_string(),
+        }],
+        ..Default::default()
+    });
+    let points = points_exportés(&store, &Filtre::default()).unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].nom(), "T'.D.S3 - 50");
+    assert_eq!(points[0].nombre_de_visites, 2);
+    assert_eq!(
+        points[0].dernière_visite,
+        Some("2024-06-01".parse().unwrap())
+    );
+    assert_eq!(points[0].état(), Some(RnÉtatCode::MauvaisÉtat));
+    assert_eq!(points[0].photos.len(), 1);
+    assert_eq!(points[1].état(), Some(RnÉtatCode::BonÉtat));
+
+    let filtre = Filtre {
+        types: vec!["crue".to_string()],
+        ..Default::default()
+    };
+    assert!(points_exportés(&store, &filtre).unwrap().is_empty());
+    let filtre = Filtre {
+        départements: vec!["31".to_string()],
+        visité: Some(false),
+        ..Default::default()
+    };
+    assert!(points_exportés(&store, &filtre).unwrap().is_empty());
+    let filtre = Filtre {
+        ids: vec![
+            "ngf/T'.D.S3 - 52@452592".to_string(),
+            "ngf/T'.D.S3 - 50@452592".to_string(),
+        ],
+        ..Default::default()
+    };
+    let points = points_exportés(&store, &filtre).unwrap();
+    assert_eq!(points[0].nom(), "T'.D.S3 - 52");
+    let filtre = Filtre {
+        ids: vec!["ngf/T'.D.S3 - 99".to_string()],
+        ..Default::default()
+    };
+    assert!(points_exportés(&store, &filtre).is_err());
+    assert_eq!(département_from_insee("97411"), "974");
+}
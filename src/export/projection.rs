@@ -0,0 +1,107 @@
+//! The coordinate reference systems of the GIS exports. The points are stored in WGS84 and
+//! projected in Lambert-93 when asked (RGF93 and WGS84 are the same at the precision of the
+//! benchmarks)
+use std::{f64::consts::FRAC_PI_4, fmt, str::FromStr};
+
+/// Semi-major axis of the GRS80 ellipsoid, in metres
+const GRS80_A: f64 = 6_378_137.0;
+/// Flattening of the GRS80 ellipsoid
+const GRS80_F: f64 = 1.0 / 298.257_222_101;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Crs {
+    #[default]
+    Wgs84,
+    /// RGF93 v1 / Lambert-93, the projection of metropolitan France
+    Lambert93,
+}
+
+impl Crs {
+    /// The EPSG code of the system
+    pub fn epsg(&self) -> i32 {
+        match self {
+            Crs::Wgs84 => 4326,
+            Crs::Lambert93 => 2154,
+        }
+    }
+
+    pub fn nom(&self) -> &'static str {
+        match self {
+            Crs::Wgs84 => "WGS 84",
+            Crs::Lambert93 => "RGF93 v1 / Lambert-93",
+        }
+    }
+
+    /// The definition of the system in WKT
+    pub fn wkt(&self) -> &'static str {
+        match self {
+            Crs::Wgs84 => {
+                r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]"#
+            }
+            Crs::Lambert93 => {
+                r#"PROJCS["RGF93 v1 / Lambert-93",GEOGCS["RGF93 v1",DATUM["Reseau_Geodesique_Francais_1993",SPHEROID["GRS 1980",6378137,298.257222101,AUTHORITY["EPSG","7019"]],TOWGS84[0,0,0,0,0,0,0],AUTHORITY["EPSG","6171"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4171"]],PROJECTION["Lambert_Conformal_Conic_2SP"],PARAMETER["latitude_of_origin",46.5],PARAMETER["central_meridian",3],PARAMETER["standard_parallel_1",49],PARAMETER["standard_parallel_2",44],PARAMETER["false_easting",700000],PARAMETER["false_northing",6600000],UNIT["metre",1,AUTHORITY["EPSG","9001"]],AXIS["Easting",EAST],AXIS["Northing",NORTH],AUTHORITY["EPSG","2154"]]"#
+            }
+        }
+    }
+
+    /// The (x, y) coordinates of a (longitude, latitude) point in this system
+    pub fn projette(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        match self {
+            Crs::Wgs84 => (longitude, latitude),
+            Crs::Lambert93 => lambert93(longitude, latitude),
+        }
+    }
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EPSG:{}", self.epsg())
+    }
+}
+
+impl FromStr for Crs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wgs84" | "epsg:4326" => Ok(Crs::Wgs84),
+            "lambert93" | "epsg:2154" => Ok(Crs::Lambert93),
+            _ => Err(format!(
+                "Unknown coordinate system: '{s}' (expected wgs84 or lambert93)"
+            )),
+        }
+    }
+}
+
+/// Projects a point in Lambert-93, a Lambert conformal conic projection with two standard
+/// parallels (44° and 49°) on the GRS80 ellipsoid
+fn lambert93(longitude: f64, latitude: f64) -> (f64, f64) {
+    let e = (2.0 * GRS80_F - GRS80_F * GRS80_F).sqrt();
+    let m = |phi: f64| phi.cos() / (1.0 - (e * phi.sin()).powi(2)).sqrt();
+    let t = |phi: f64| {
+        (FRAC_PI_4 - phi / 2.0).tan()
+            / ((1.0 - e * phi.sin()) / (1.0 + e * phi.sin())).powf(e / 2.0)
+    };
+    let (phi_0, phi_1, phi_2) = (46.5f64.to_radians(), 44f64.to_radians(), 49f64.to_radians());
+    let n = (m(phi_1).ln() - m(phi_2).ln()) / (t(phi_1).ln() - t(phi_2).ln());
+    let f = m(phi_1) / (n * t(phi_1).powf(n));
+    let rho = |phi: f64| GRS80_A * f * t(phi).powf(n);
+    let theta = n * (longitude - 3.0).to_radians();
+    let rho_phi = rho(latitude.to_radians());
+    (
+        700_000.0 + rho_phi * theta.sin(),
+        6_600_000.0 + rho(phi_0) - rho_phi * theta.cos(),
+    )
+}
+
+#[test]
+fn test_projection() {
+    let (x, y) = Crs::Lambert93.projette(3.0, 46.5);
+    assert!((x - 700_000.0).abs() < 0.01 && (y - 6_600_000.0).abs() < 0.01);
+    // T'.D.S3 - 50, whose fiche gives E = 571.63 km and N = 6284.65 km
+    let (x, y) = Crs::Lambert93.projette(1.409197, 43.649276);
+    assert!((x - 571_630.0).abs() < 10.0, "{x}");
+    assert!((y - 6_284_650.0).abs() < 10.0, "{y}");
+    assert_eq!(Crs::Wgs84.projette(1.4, 43.6), (1.4, 43.6));
+    assert_eq!("Lambert93".parse(), Ok(Crs::Lambert93));
+}
@@ -0,0 +1,417 @@
+//! Printable field sheets for an outing: a PDF with a block per benchmark, holding what is needed
+//! to find it on the ground, a QR code to its IGN fiche and room for notes
+use crate::ngf::RepèreNivellement;
+use printpdf::{
+    path::PaintMode, BuiltinFont, Color, Greyscale, IndirectFontRef, Line, Mm, PdfDocument,
+    PdfLayerReference, Point, Rect,
+};
+use qrcode::{EcLevel, QrCode};
+use std::error::Error;
+
+const LARGEUR_PAGE: f32 = 210.0;
+const HAUTEUR_PAGE: f32 = 297.0;
+const MARGE: f32 = 15.0;
+/// Font sizes, in points
+const TAILLE_TITRE: f32 = 13.0;
+const TAILLE_TEXTE: f32 = 9.0;
+/// Heights in millimetres
+const HAUTEUR_TITRE: f32 = 8.0;
+const INTERLIGNE: f32 = 4.2;
+const CÔTÉ_QR: f32 = 30.0;
+const HAUTEUR_NOTES: f32 = 28.0;
+const ESPACE_ENTRE_BLOCS: f32 = 8.0;
+/// The width of the text, left of the QR code
+const LARGEUR_TEXTE: f32 = LARGEUR_PAGE - 2.0 * MARGE - CÔTÉ_QR - 5.0;
+
+/// The width of a text in Helvetica, in millimetres. The widths of the glyphs are rounded
+/// classes of those of the font, which is enough to wrap the lines
+fn largeur(texte: &str, taille: f32, gras: bool) -> f32 {
+    let millièmes: f32 = texte
+        .chars()
+        .map(|caractère| match caractère {
+            ' ' | '\'' | ',' | '.' | ';' | ':' | '!' | '|' | 'i' | 'j' | 'l' | 'I' => 278.0,
+            'f' | 't' | 'r' | '(' | ')' | '-' | '/' => 333.0,
+            'm' | 'M' | 'W' | 'w' => 833.0,
+            'A'..='Z' | '&' => 690.0,
+            _ => 556.0,
+        })
+        .sum();
+    let millièmes = if gras { millièmes * 1.06 } else { millièmes };
+    // 1 pt = 25.4 / 72 mm
+    millièmes / 1000.0 * taille * 25.4 / 72.0
+}
+
+/// Splits the text into lines of at most `largeur_max` millimetres, between words
+fn coupe(texte: &str, largeur_max: f32) -> Vec<String> {
+    let mut lignes: Vec<String> = vec![];
+    let mut ligne = String::new();
+    for mot in texte.split_whitespace() {
+        let candidate = if ligne.is_empty() {
+            mot.to_string()
+        } else {
+            format!("{ligne} {mot}")
+        };
+        if largeur(&candidate, TAILLE_TEXTE, false) > largeur_max && !ligne.is_empty() {
+            lignes.push(ligne);
+            ligne = mot.to_string();
+        } else {
+            ligne = candidate;
+        }
+    }
+    if !ligne.is_empty() || lignes.is_empty() {
+        lignes.push(ligne);
+    }
+    lignes
+}
+
+/// The labelled fields of the block of a benchmark
+fn champs(repère: &RepèreNivellement) -> Vec<(&'static str, String)> {
+    let non_vide = |texte: &Option<String>| texte.clone().filter(|texte| !texte.trim().is_empty());
+    let mut voie = repère.voie_suivie.clone();
+    if let Some(de) = non_vide(&repère.voie_de) {
+        voie += &format!(", de {de}");
+    }
+    if let Some(vers) = non_vide(&repère.voie_vers) {
+        voie += &format!(" à {vers}");
+    }
+    if let Some(pk) = non_vide(&repère.voie_pk) {
+        voie += &format!(", PK {pk}");
+    }
+    let mut champs = vec![
+        (
+            "Altitude",
+            format!(
+                "{} m ({}, {})",
+                repère.altitude, repère.système_altimétrique, repère.altitude_type
+            ),
+        ),
+        (
+            "État IGN",
+            format!(
+                "{}, dernière visite {}",
+                repère.état, repère.dernière_visite
+            ),
+        ),
+        (
+            "Commune",
+            format!("{} ({})", repère.commune, repère.département),
+        ),
+        ("Voie suivie", voie),
+        (
+            "Localisation",
+            non_vide(&repère.localisation).unwrap_or_default(),
+        ),
+        ("Support", repère.support.clone()),
+        (
+            "Partie du support",
+            non_vide(&repère.partie_support).unwrap_or_default(),
+        ),
+        (
+            "Repèrement horizontal",
+            non_vide(&repère.repèrement_horizontal).unwrap_or_default(),
+        ),
+        (
+            "Repèrement vertical",
+            non_vide(&repère.repèrement_vertical).unwrap_or_default(),
+        ),
+        (
+            "Coordonnées",
+            format!(
+                "{:.6}, {:.6} (latitude, longitude WGS84)",
+                repère.latitude, repère.longitude
+            ),
+        ),
+        (
+            "E, N (km)",
+            format!("{}, {} ({})", repère.e, repère.n, repère.référentiel),
+        ),
+        ("Remarques", repère.remarques.clone()),
+    ];
+    champs.retain(|(_, valeur)| !valeur.trim().is_empty());
+    champs
+}
+
+/// A field, with its value split into lines and the width of its label
+struct ChampCoupé {
+    étiquette: String,
+    largeur_étiquette: f32,
+    lignes: Vec<String>,
+}
+
+/// The block of a benchmark, laid out
+struct Bloc<'a> {
+    repère: &'a RepèreNivellement,
+    champs: Vec<ChampCoupé>,
+    hauteur: f32,
+}
+
+impl Bloc<'_> {
+    fn new(repère: &RepèreNivellement) -> Bloc<'_> {
+        let champs: Vec<ChampCoupé> = champs(repère)
+            .into_iter()
+            .map(|(étiquette, valeur)| {
+                let étiquette = format!("{étiquette} : ");
+                let largeur_étiquette = largeur(&étiquette, TAILLE_TEXTE, true);
+                ChampCoupé {
+                    lignes: coupe(&valeur, LARGEUR_TEXTE - largeur_étiquette),
+                    étiquette,
+                    largeur_étiquette,
+                }
+            })
+            .collect();
+        let lignes: usize = champs.iter().map(|champ| champ.lignes.len()).sum();
+        let hauteur =
+            HAUTEUR_TITRE + (lignes as f32 * INTERLIGNE).max(CÔTÉ_QR) + 3.0 + HAUTEUR_NOTES;
+        Bloc {
+            repère,
+            champs,
+            hauteur,
+        }
+    }
+}
+
+/// Groups the blocks into pages, in order
+fn pages<'a>(blocs: Vec<Bloc<'a>>) -> Vec<Vec<Bloc<'a>>> {
+    let hauteur_utile = HAUTEUR_PAGE - 2.0 * MARGE;
+    let mut pages: Vec<Vec<Bloc>> = vec![];
+    let mut hauteur = f32::INFINITY;
+    for bloc in blocs {
+        let hauteur_bloc = bloc.hauteur + ESPACE_ENTRE_BLOCS;
+        match pages.last_mut() {
+            Some(page) if hauteur + hauteur_bloc <= hauteur_utile + ESPACE_ENTRE_BLOCS => {
+                hauteur += hauteur_bloc;
+                page.push(bloc);
+            }
+            _ => {
+                hauteur = hauteur_bloc;
+                pages.push(vec![bloc]);
+            }
+        }
+    }
+    pages
+}
+
+fn trait_(calque: &PdfLayerReference, points: &[(f32, f32)]) {
+    calque.add_line(Line {
+        points: points
+            .iter()
+            .map(|(x, y)| (Point::new(Mm(*x), Mm(*y)), false))
+            .collect(),
+        is_closed: false,
+    });
+}
+
+/// Draws the QR code of the text, with its top left corner at (x, y)
+fn qr_code(calque: &PdfLayerReference, texte: &str, x: f32, y: f32) -> Result<(), Box<dyn Error>> {
+    let code = QrCode::with_error_correction_level(texte, EcLevel::M)?;
+    let modules = code.width();
+    let côté = CÔTÉ_QR / modules as f32;
+    let couleurs = code.to_colors();
+    calque.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+    for (rangée, couleurs) in couleurs.chunks(modules).enumerate() {
+        // The consecutive dark modules of a row are drawn as one rectangle
+        let mut colonne = 0;
+        while colonne < modules {
+            if couleurs[colonne] != qrcode::Color::Dark {
+                colonne += 1;
+                continue;
+            }
+            let début = colonne;
+            while colonne < modules && couleurs[colonne] == qrcode::Color::Dark {
+                colonne += 1;
+            }
+            let haut = y - rangée as f32 * côté;
+            calque.add_rect(
+                Rect::new(
+                    Mm(x + début as f32 * côté),
+                    Mm(haut - côté),
+                    Mm(x + colonne as f32 * côté),
+                    Mm(haut),
+                )
+                .with_mode(PaintMode::Fill),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Draws the block with its top at `haut`
+fn dessine_bloc(
+    calque: &PdfLayerReference,
+    bloc: &Bloc,
+    haut: f32,
+    normale: &IndirectFontRef,
+    grasse: &IndirectFontRef,
+) -> Result<(), Box<dyn Error>> {
+    let noir = Color::Greyscale(Greyscale::new(0.0, None));
+    let gris = Color::Greyscale(Greyscale::new(0.6, None));
+    calque.set_fill_color(noir.clone());
+    calque.use_text(
+        format!("Repère {}", bloc.repère.matricule),
+        TAILLE_TITRE,
+        Mm(MARGE),
+        Mm(haut - 5.0),
+        grasse,
+    );
+    let mut y = haut - HAUTEUR_TITRE - 3.0;
+    for champ in &bloc.champs {
+        calque.use_text(&champ.étiquette, TAILLE_TEXTE, Mm(MARGE), Mm(y), grasse);
+        for ligne in &champ.lignes {
+            calque.use_text(
+                ligne,
+                TAILLE_TEXTE,
+                Mm(MARGE + champ.largeur_étiquette),
+                Mm(y),
+                normale,
+            );
+            y -= INTERLIGNE;
+        }
+    }
+    qr_code(
+        calque,
+        &bloc.repère.fiche_url,
+        LARGEUR_PAGE - MARGE - CÔTÉ_QR,
+        haut - HAUTEUR_TITRE,
+    )?;
+
+    // The notes: a box with ruled lines, under the fields and the QR code
+    let haut_notes = haut - bloc.hauteur + HAUTEUR_NOTES;
+    let (gauche, droite) = (MARGE, LARGEUR_PAGE - MARGE);
+    let bas = haut - bloc.hauteur;
+    let ligne_de_base = haut_notes - 4.5;
+    calque.set_fill_color(noir.clone());
+    calque.use_text(
+        "Notes",
+        TAILLE_TEXTE,
+        Mm(gauche + 1.5),
+        Mm(ligne_de_base),
+        grasse,
+    );
+    // The boxes to tick the state seen on the ground
+    let mut x = gauche + 25.0;
+    calque.use_text(
+        "État constaté :",
+        TAILLE_TEXTE,
+        Mm(x),
+        Mm(ligne_de_base),
+        normale,
+    );
+    x += largeur("État constaté : ", TAILLE_TEXTE, false);
+    calque.set_outline_color(noir);
+    calque.set_outline_thickness(0.3);
+    for état in ["Bon", "Mauvais", "Détruit", "Non retrouvé"] {
+        trait_(
+            calque,
+            &[
+                (x, ligne_de_base),
+                (x + 2.8, ligne_de_base),
+                (x + 2.8, ligne_de_base + 2.8),
+                (x, ligne_de_base + 2.8),
+                (x, ligne_de_base),
+            ],
+        );
+        calque.use_text(état, TAILLE_TEXTE, Mm(x + 4.0), Mm(ligne_de_base), normale);
+        x += 4.0 + largeur(état, TAILLE_TEXTE, false) + 5.0;
+    }
+    calque.set_outline_color(gris);
+    calque.set_outline_thickness(0.5);
+    trait_(
+        calque,
+        &[
+            (gauche, haut_notes),
+            (droite, haut_notes),
+            (droite, bas),
+            (gauche, bas),
+            (gauche, haut_notes),
+        ],
+    );
+    let mut ligne = haut_notes - 11.0;
+    while ligne > bas + 2.0 {
+        trait_(calque, &[(gauche + 1.5, ligne), (droite - 1.5, ligne)]);
+        ligne -= 7.0;
+    }
+    Ok(())
+}
+
+/// The PDF of the field sheets of the benchmarks, in their order
+pub fn pdf(repères: &[RepèreNivellement], titre: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let pages = pages(repères.iter().map(Bloc::new).collect());
+    let (document, première_page, premier_calque) =
+        PdfDocument::new(titre, Mm(LARGEUR_PAGE), Mm(HAUTEUR_PAGE), "Fiches");
+    let normale = document.add_builtin_font(BuiltinFont::Helvetica)?;
+    let grasse = document.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let nombre_de_pages = pages.len().max(1);
+    for (numéro, blocs) in pages.iter().enumerate() {
+        let calque = if numéro == 0 {
+            document.get_page(première_page).get_layer(premier_calque)
+        } else {
+            let (page, calque) = document.add_page(Mm(LARGEUR_PAGE), Mm(HAUTEUR_PAGE), "Fiches");
+            document.get_page(page).get_layer(calque)
+        };
+        let mut haut = HAUTEUR_PAGE - MARGE;
+        for bloc in blocs {
+            dessine_bloc(&calque, bloc, haut, &normale, &grasse)?;
+            haut -= bloc.hauteur + ESPACE_ENTRE_BLOCS;
+        }
+        calque.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+        calque.use_text(
+            format!("{titre} – {}/{nombre_de_pages}", numéro + 1),
+            7.0,
+            Mm(MARGE),
+            Mm(MARGE / 2.0),
+            &normale,
+        );
+    }
+    Ok(document.save_to_bytes()?)
+}
+
+#[test]
+fn test_coupe() {
+    assert_eq!(coupe("", 50.0), vec![""]);
+    assert_eq!(coupe("MUR  DU PONT", 100.0), vec!["MUR DU PONT"]);
+    let lignes = coupe(
+        "MUR EN RETOUR COTE FLEUVE, FACE AMONT, A 2 M DE L'ANGLE DU PARAPET",
+        40.0,
+    );
+    assert!(lignes.len() > 2);
+    assert!(lignes
+        .iter()
+        .all(|ligne| largeur(ligne, TAILLE_TEXTE, false) <= 40.0));
+    assert_eq!(
+        lignes.join(" "),
+        "MUR EN RETOUR COTE FLEUVE, FACE AMONT, A 2 M DE L'ANGLE DU PARAPET"
+    );
+}
+
+#[test]
+fn test_pdf() {
+    use crate::save::repère_test;
+    let repère = repère_test("T'.D.S3 - 50");
+    let champs = champs(&repère);
+    assert_eq!(
+        champs[0],
+        (
+            "Altitude",
+            "125,719 m (NGF-IGN 1969, Altitude normale)".to_string()
+        )
+    );
+    assert!(champs.contains(&(
+        "Partie du support",
+        "MUR EN RETOUR COTE FLEUVE, FACE AMONT".to_string()
+    )));
+    // The empty vertical repèrement is left out
+    assert!(champs
+        .iter()
+        .all(|(étiquette, _)| *étiquette != "Repèrement vertical"));
+
+    let repères: Vec<RepèreNivellement> = (50..54)
+        .map(|numéro| repère_test(&format!("T'.D.S3 - {numéro}")))
+        .collect();
+    let mises_en_page = pages(repères.iter().map(Bloc::new).collect());
+    assert_eq!(
+        mises_en_page.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![3, 1]
+    );
+    let pdf = pdf(&repères, "Sortie du 1er juin").unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+}
@@ -0,0 +1,58 @@
+//! Can deserialize the output of the WFS API of the Géoplateforme (layer GEODESIE:data_geod) for
+//! geodetic points. Every field is optional, as the layer describes very different points
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WfsFeatureCollection {
+    #[serde(default)]
+    pub features: Vec<WfsFeature>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WfsFeature {
+    pub geometry: Option<WfsGeometry>,
+    pub properties: WfsProperties,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WfsGeometry {
+    pub coordinates: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct WfsProperties {
+    /// The cid of the point (geo_cid in the URL of the fiches)
+    pub id: Option<u32>,
+    /// The name of the point, for instance « 3155504 b »
+    pub nom: String,
+    /// The number of the site
+    pub sit_no: Option<String>,
+    pub sit_nom: Option<String>,
+    pub fiche_url: Option<String>,
+    /// For instance « Pilier », « Borne », « Repère »
+    #[serde(rename = "type")]
+    pub point_type: String,
+    pub type_info: Option<String>,
+    pub etat: Option<String>,
+    pub vis_date: Option<String>,
+    pub obs_date: Option<String>,
+    /// Longitude
+    pub cg1_coord1: Option<f64>,
+    /// Latitude
+    pub cg1_coord2: Option<f64>,
+    /// Ellipsoidal height
+    pub cg1_coord3: Option<f64>,
+    /// Altitude
+    pub cp1_coord3: Option<f64>,
+    /// Altimetric system of the altitude
+    pub cp1_srv: Option<String>,
+    pub entite_no: Option<String>,
+    pub insee: Option<String>,
+    pub commune: Option<String>,
+    pub localisation: Option<String>,
+    pub support: Option<String>,
+    pub remarque: Option<String>,
+    /// Information about the linked levelling benchmark
+    pub jumeau_info: Option<String>,
+}
@@ -0,0 +1,255 @@
+//! IGN geodetic sites (sites géodésiques): triangulation pillars, RBF points… They are fetched
+//! from the WFS API of the Géoplateforme, which the Python CLI already used for the benchmarks
+pub mod json_mappings;
+pub mod site;
+pub use site::{PointGéodésique, SiteGéodésique};
+
+use crate::{
+    network::{Area, Network, SearchResult},
+    save::Objet,
+};
+use json_mappings::{WfsFeature, WfsFeatureCollection};
+use std::error::Error;
+
+const WFS_URL: &str = "https://data.geopf.fr/wfs";
+
+/// Returns the number of the geodetic site mentioned by the géod_info of a benchmark
+///
+/// # Examples
+/// ```
+/// use geodesie_de_bureau::geod::sit_no_from_géod_info;
+/// assert_eq!(
+///     sit_no_from_géod_info("POINT b DU SITE GEODESIQUE 3155504"),
+///     Some("3155504".to_string())
+/// );
+/// assert_eq!(sit_no_from_géod_info(""), None);
+/// ```
+pub fn sit_no_from_géod_info(géod_info: &str) -> Option<String> {
+    let (_, after) = géod_info.split_once("SITE GEODESIQUE")?;
+    let sit_no: String = after
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    if sit_no.is_empty() {
+        None
+    } else {
+        Some(sit_no)
+    }
+}
+
+/// The URL of the fiche of a geodetic point
+pub fn fiche_url(cid: u32) -> String {
+    format!("https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=0&geo_cid={cid}")
+}
+
+/// Fetches the geodetic points matching a CQL filter
+fn wfs_features(cql_filter: &str) -> Result<Vec<WfsFeature>, Box<dyn Error>> {
+    let resp: String = reqwest::blocking::Client::new()
+        .get(WFS_URL)
+        .query(&[
+            ("SERVICE", "WFS"),
+            ("VERSION", "2.0.0"),
+            ("REQUEST", "GetFeature"),
+            ("TYPENAME", "GEODESIE:data_geod"),
+            ("OUTPUTFORMAT", "application/json"),
+            (
+                "cql_filter",
+                format!("{cql_filter} and domaine='geod'").as_str(),
+            ),
+        ])
+        .send()?
+        .text()?;
+    Ok(serde_json::from_str::<WfsFeatureCollection>(resp.as_str())?.features)
+}
+
+/// Quotes a string for a CQL filter
+fn cql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Groups the points by site, in the order of the features. Points without a site number are
+/// skipped
+pub fn sites_from_features(features: Vec<WfsFeature>) -> Vec<SiteGéodésique> {
+    let mut sites: Vec<SiteGéodésique> = vec![];
+    for feature in features {
+        let prop = feature.properties;
+        let Some(numéro) = prop.sit_no.clone().filter(|sit_no| !sit_no.is_empty()) else {
+            continue;
+        };
+        let (longitude, latitude) = match (&feature.geometry, prop.cg1_coord1, prop.cg1_coord2) {
+            (_, Some(longitude), Some(latitude)) => (longitude, latitude),
+            (Some(geometry), _, _) if geometry.coordinates.len() >= 2 => {
+                (geometry.coordinates[0], geometry.coordinates[1])
+            }
+            _ => continue,
+        };
+        let cid = prop.id.unwrap_or(0);
+        let point = PointGéodésique {
+            cid,
+            nom: prop.nom,
+            fiche_url: prop.fiche_url.unwrap_or_else(|| fiche_url(cid)),
+            point_type: prop.point_type,
+            type_complément: prop.type_info,
+            état: prop.etat,
+            dernière_visite: prop.vis_date,
+            dernière_observation: prop.obs_date,
+            longitude,
+            latitude,
+            hauteur_ellipsoïdale: prop.cg1_coord3,
+            altitude: prop.cp1_coord3,
+            système_altimétrique: prop.cp1_srv,
+            support: prop.support,
+            jumeau: prop.jumeau_info.filter(|jumeau| !jumeau.is_empty()),
+            remarques: prop.remarque.filter(|remarque| !remarque.is_empty()),
+        };
+        match sites.iter_mut().find(|site| site.numéro == numéro) {
+            Some(site) => site.points.push(point),
+            None => sites.push(SiteGéodésique {
+                numéro,
+                nom: prop.sit_nom.unwrap_or_default(),
+                département: prop.entite_no.unwrap_or_default(),
+                insee: prop.insee.unwrap_or_default(),
+                commune: prop.commune.unwrap_or_default(),
+                localisation: prop
+                    .localisation
+                    .filter(|localisation| !localisation.is_empty()),
+                points: vec![point],
+            }),
+        }
+    }
+    for site in sites.iter_mut() {
+        site.points.sort_by(|a, b| a.nom.cmp(&b.nom));
+    }
+    sites
+}
+
+/// Returns the numbers of the sites starting with `query`
+pub fn search_sites(query: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut numbers: Vec<String> = sites_from_features(wfs_features(
+        format!(
+            "sit_no LIKE {}",
+            cql_string(format!("{}%", query.trim()).as_str())
+        )
+        .as_str(),
+    )?)
+    .into_iter()
+    .map(|site| site.numéro)
+    .collect();
+    numbers.sort();
+    Ok(numbers)
+}
+
+/// Fetches a site and all its points
+pub fn fetch_site(numéro: &str) -> Result<SiteGéodésique, Box<dyn Error>> {
+    sites_from_features(wfs_features(
+        format!("sit_no={}", cql_string(numéro)).as_str(),
+    )?)
+    .into_iter()
+    .next()
+    .ok_or(format!("No geodetic site with number '{numéro}'").into())
+}
+
+/// Fetches all the sites of the area
+pub fn sites_in_area(area: &Area) -> Result<Vec<SiteGéodésique>, Box<dyn Error>> {
+    Ok(sites_from_features(wfs_features(
+        format!(
+            "BBOX(geom,{},{},{},{},'EPSG:4326')",
+            area.latitude_min, area.longitude_min, area.latitude_max, area.longitude_max
+        )
+        .as_str(),
+    )?))
+}
+
+/// The IGN geodetic sites, as a Network
+pub struct Geod;
+
+impl Network for Geod {
+    fn name(&self) -> &str {
+        "geod"
+    }
+
+    fn description(&self) -> &str {
+        "Sites géodésiques de l’IGN (piliers, points RBF…)"
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+        Ok(search_sites(query)?
+            .into_iter()
+            .map(|numéro| SearchResult {
+                key: numéro,
+                cid: None,
+            })
+            .collect())
+    }
+
+    fn fetch(&self, key: &str) -> Result<Objet, Box<dyn Error>> {
+        Ok(Objet::Site(Box::new(fetch_site(key)?)))
+    }
+
+    fn fetch_area(&self, area: &Area) -> Result<Vec<Objet>, Box<dyn Error>> {
+        Ok(sites_in_area(area)?
+            .into_iter()
+            .map(|site| Objet::Site(Box::new(site)))
+            .collect())
+    }
+}
+
+#[test]
+fn test_sites_from_features() {
+    let collection: WfsFeatureCollection = serde_json::from_str(
+        r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [1.4432, 43.6045]},
+                    "properties": {
+                        "id": 1234,
+                        "nom": "3155504 b",
+                        "sit_no": "3155504",
+                        "type": "Repère",
+                        "insee": "31555",
+                        "commune": "Toulouse",
+                        "entite_no": "31",
+                        "jumeau_info": "T'.D.S3 - 50"
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [1.4431, 43.6046]},
+                    "properties": {
+                        "id": 1233,
+                        "nom": "3155504 a",
+                        "sit_no": "3155504",
+                        "type": "Pilier",
+                        "cp1_coord3": 146.52,
+                        "cg1_coord3": 196.1
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": null,
+                    "properties": {"nom": "sans site"}
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let sites = sites_from_features(collection.features);
+    assert_eq!(sites.len(), 1);
+    let site = &sites[0];
+    assert_eq!(site.numéro, "3155504");
+    assert_eq!(site.commune, "Toulouse");
+    assert_eq!(
+        site.points
+            .iter()
+            .map(|point| point.nom.as_str())
+            .collect::<Vec<_>>(),
+        vec!["3155504 a", "3155504 b"]
+    );
+    assert_eq!(site.points[0].altitude, Some(146.52));
+    assert_eq!(site.points[1].fiche_url, fiche_url(1234));
+    assert_eq!(site.points[1].jumeau.as_deref(), Some("T'.D.S3 - 50"));
+    assert_eq!(site.coordonnées(), Some((1.4431, 43.6046)));
+}
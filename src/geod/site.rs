@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::common_traits::Identified;
+
+/// One point of a geodetic site (a pillar, a benchmark, an eccentric point…)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PointGéodésique {
+    /// The cid of the point, used by the fiche URL
+    pub cid: u32,
+    /// For instance « 3155504 b »
+    pub nom: String,
+    pub fiche_url: String,
+    pub point_type: String,
+    pub type_complément: Option<String>,
+    pub état: Option<String>,
+    pub dernière_visite: Option<String>,
+    pub dernière_observation: Option<String>,
+
+    pub longitude: f64,
+    pub latitude: f64,
+    /// Height above the ellipsoid, in metres
+    pub hauteur_ellipsoïdale: Option<f64>,
+    /// Altitude in metres
+    pub altitude: Option<f64>,
+    pub système_altimétrique: Option<String>,
+
+    pub support: Option<String>,
+    /// What the API says about the levelling benchmark of this point, if any
+    pub jumeau: Option<String>,
+    pub remarques: Option<String>,
+}
+
+/// An IGN geodetic site (triangulation pillar, RBF site…), made of one or several points
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SiteGéodésique {
+    /// The number of the site, for instance « 3155504 » (département, commune and number)
+    pub numéro: String,
+    pub nom: String,
+    pub département: String,
+    pub insee: String,
+    pub commune: String,
+    pub localisation: Option<String>,
+    pub points: Vec<PointGéodésique>,
+}
+
+impl SiteGéodésique {
+    /// (longitude, latitude) of the first point of the site
+    pub fn coordonnées(&self) -> Option<(f64, f64)> {
+        self.points
+            .first()
+            .map(|point| (point.longitude, point.latitude))
+    }
+}
+
+impl Identified for SiteGéodésique {
+    fn object_id(&self) -> String {
+        format!("geod/{}", self.numéro)
+    }
+}
+
+impl fmt::Display for SiteGéodésique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "\x1b[91m=============== Site géodésique ===============\x1b[39m"
+        )?;
+        writeln!(f, "\x1b[94mNuméro\x1b[39m : {}", self.numéro)?;
+        if !self.nom.is_empty() {
+            writeln!(f, "\x1b[94mNom\x1b[39m : {}", self.nom)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "\x1b[91m=== Localisation ===\x1b[39m")?;
+        writeln!(f, "\x1b[94mDépartement\x1b[39m : {}", self.département)?;
+        writeln!(f, "\x1b[94mNuméro insee\x1b[39m : {}", self.insee)?;
+        writeln!(f, "\x1b[94mCommune\x1b[39m : {}", self.commune)?;
+        if let Some(localisation) = &self.localisation {
+            writeln!(f, "\x1b[94mLocalisation\x1b[39m : {localisation}")?;
+        }
+        for point in &self.points {
+            writeln!(f)?;
+            writeln!(f, "\x1b[91m=== Point {} ===\x1b[39m", point.nom)?;
+            writeln!(f, "\x1b[94mFiche en ligne\x1b[39m : {}", point.fiche_url)?;
+            match &point.type_complément {
+                Some(complément) if !complément.is_empty() => writeln!(
+                    f,
+                    "\x1b[94mType\x1b[39m : {} ({complément})",
+                    point.point_type
+                )?,
+                _ => writeln!(f, "\x1b[94mType\x1b[39m : {}", point.point_type)?,
+            }
+            if let Some(état) = &point.état {
+                writeln!(f, "\x1b[94mÉtat\x1b[39m : {état}")?;
+            }
+            if let Some(dernière_visite) = &point.dernière_visite {
+                writeln!(f, "\x1b[94mDernière visite\x1b[39m : {dernière_visite}")?;
+            }
+            writeln!(f, "\x1b[94mLongitude\x1b[39m : {}", point.longitude)?;
+            writeln!(f, "\x1b[94mLatitude\x1b[39m : {}", point.latitude)?;
+            if let Some(hauteur) = point.hauteur_ellipsoïdale {
+                writeln!(f, "\x1b[94mHauteur ellipsoïdale\x1b[39m : {hauteur}m")?;
+            }
+            if let Some(altitude) = point.altitude {
+                match &point.système_altimétrique {
+                    Some(système) => {
+                        writeln!(f, "\x1b[94mAltitude\x1b[39m : {altitude}m ({système})")?
+                    }
+                    None => writeln!(f, "\x1b[94mAltitude\x1b[39m : {altitude}m")?,
+                }
+            }
+            if let Some(support) = &point.support {
+                writeln!(f, "\x1b[94mSupport\x1b[39m : {support}")?;
+            }
+            if let Some(jumeau) = &point.jumeau {
+                writeln!(f, "\x1b[94mRepère de nivellement\x1b[39m : {jumeau}")?;
+            }
+            if let Some(remarques) = &point.remarques {
+                writeln!(f, "\x1b[94mRemarques\x1b[39m : {remarques}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,13 @@
+pub mod cli_interface;
 pub mod common_traits;
+pub mod crue;
+pub mod custom_types;
+pub mod export;
+pub mod fiches;
+pub mod geod;
+pub mod network;
 pub mod ngf;
+pub mod osm;
 pub mod save;
-pub mod cli_interface;
+pub mod site;
+pub mod trace;
@@ -1,25 +1,1714 @@
-use clap::{arg, command, value_parser};
+use chrono::NaiveDate;
+use clap::ArgMatches;
+use cli_interface::{Format, Sortie};
+use common_traits::{Identified, ObjectId};
+use crue::{RepèreDeCrue, RéférenceNiveau};
+use geodesie_de_bureau::network::{distance, Area, ChoiceError, Chooser, Registry};
 use geodesie_de_bureau::*;
-use ngf::*;
+use ngf::json_mappings::bbox::{NivfReaCode, RnTypeCode, RnÉtatCode};
+use ngf::{Matricule, RepèreNivellement, Territoire};
+use save::{Backend, Config, Objet, Store};
+use serde_json::json;
+use std::{path::PathBuf, process::exit};
 
-fn main() {
-    let matches: clap::ArgMatches = command!()
-        .arg(
-            arg!(
-                -m --matricule <matricule> "Matricule du repère à visualiser"
+/// Opens the store, or exits
+fn ouvre_store(backend: Backend) -> Box<dyn Store> {
+    save::open_store(backend).unwrap_or_else(|err| {
+        eprintln!("Could not open the store: {err}");
+        exit(1)
+    })
+}
+
+/// The id of a point given on the command line: an id (type/identifiant), or the matricule of an
+/// NGF benchmark. A matricule, or an NGF id without its cid, is the saved benchmark with this
+/// matricule (see Store::id_of_matricule)
+fn id_du_point(store: &dyn Store, point: &str) -> ObjectId {
+    let matricule = match point.parse::<ObjectId>() {
+        Ok(id) if id.network != "ngf" || id.key.contains('@') => return id,
+        Ok(id) => id.key,
+        Err(_) => point.to_string(),
+    };
+    let Ok(matricule) = matricule.parse::<Matricule>() else {
+        return ObjectId::new("ngf", &Matricule::normalise(&matricule));
+    };
+    store.id_of_matricule(&matricule).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(1)
+    })
+}
+
+/// Fetches the point given on the command line from its network: by its IGN cid (--cid), by its
+/// id, or else by a search of the matricule on the network chosen with -n
+fn récupère(
+    matches: &ArgMatches,
+    sortie: Sortie,
+    point: Option<&str>,
+    registry: &Registry,
+    territoire: Territoire,
+) -> Objet {
+    if let Some(cid) = matches.get_one::<String>("cid") {
+        let cid = ngf::cid::cid_from_input(cid).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(1)
+        });
+        return ngf::Ngf { territoire }
+            .fetch_by_cid(cid)
+            .unwrap_or_else(|err| {
+                eprintln!("Could not fetch the benchmark with cid {cid}: {err}");
+                exit(1)
+            });
+    }
+    let query = point.unwrap();
+    if let Ok(id) = query.parse::<ObjectId>() {
+        return registry.fetch(&id).unwrap_or_else(|err| {
+            eprintln!("Could not fetch '{id}': {err}");
+            exit(1)
+        });
+    }
+    let network_name = matches.get_one::<String>("network").unwrap();
+    let Some(network) = registry.get(network_name) else {
+        eprintln!("Unknown network: '{network_name}'");
+        exit(1);
+    };
+    let results = network.search(query).unwrap_or_else(|err| {
+        eprintln!("Search failed: {err}");
+        exit(1)
+    });
+    let chooser: Chooser = matches
+        .get_one::<String>("choix")
+        .unwrap()
+        .parse::<Chooser>()
+        .unwrap()
+        .avec_couleurs(sortie.couleurs);
+    let choice = match chooser.choose(query, &results) {
+        Ok(choice) => choice,
+        Err(ChoiceError::Ambiguous(candidates)) => {
+            eprintln!("No benchmark matches '{query}' exactly, the candidates are:");
+            match sortie.format {
+                Format::Json => sortie.json(&candidates),
+                Format::Texte => {
+                    for candidate in candidates {
+                        match candidate.cid {
+                            Some(cid) => sortie.texte(format!("{} (cid {cid})", candidate.key)),
+                            None => sortie.texte(candidate.key),
+                        }
+                    }
+                }
+            }
+            exit(1);
+        }
+        Err(ChoiceError::Cancelled) => {
+            eprintln!("No benchmark chosen");
+            exit(1);
+        }
+        Err(err @ ChoiceError::UnknownChoice(_)) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+        Err(ChoiceError::NoResult) => {
+            eprintln!("Nothing found for '{query}'");
+            match network.suggest(query) {
+                Ok(suggestions) if !suggestions.is_empty() => {
+                    eprintln!("Did you mean:");
+                    for suggestion in suggestions {
+                        eprintln!("  {}", suggestion.key);
+                    }
+                }
+                Ok(_) => (),
+                Err(err) => eprintln!("Could not search for close matricules: {err}"),
+            }
+            exit(1);
+        }
+    };
+    // The matricule of an NGF benchmark is not enough to tell homonyms apart
+    let key = match choice.cid {
+        Some(cid) if network.name() == "ngf" => format!("{}@{cid}", choice.key),
+        _ => choice.key.clone(),
+    };
+    network.fetch(&key).unwrap_or_else(|err| {
+        eprintln!("Could not fetch '{}': {err}", choice.key);
+        exit(1)
+    })
+}
+
+/// Exits if the JSON output was asked to a command that has none, such as the commands that write
+/// files
+fn sans_json(sortie: Sortie, commande: &str) {
+    if sortie.format == Format::Json {
+        eprintln!("'{commande}' has no JSON output, --format json can not be used with it");
+        exit(1);
+    }
+}
+
+/// The point with, if asked with -g, the triplet and the primordial benchmark of a benchmark
+fn avec_groupe(matches: &ArgMatches, objet: Objet) -> (Vec<Objet>, Option<ngf::GroupeRepères>) {
+    match objet {
+        Objet::Ngf(repère) if matches.get_flag("groupe") => {
+            let groupe = ngf::fetch_groupe(*repère).unwrap_or_else(|err| {
+                eprintln!("Could not fetch the triplet and the primordial benchmark: {err}");
+                exit(1)
+            });
+            let objets = groupe
+                .repères()
+                .into_iter()
+                .map(|repère| Objet::Ngf(Box::new(repère.clone())))
+                .collect();
+            (objets, Some(groupe))
+        }
+        objet => (vec![objet], None),
+    }
+}
+
+/// Copies the store into another backend, and switches to it with --switch
+fn commande_migrate(migrate_matches: &ArgMatches, sortie: Sortie, config: &mut Config) {
+    sans_json(sortie, "migrate");
+    let destination: Backend = *migrate_matches.get_one::<Backend>("backend").unwrap();
+    if destination == config.backend {
+        eprintln!("The store already uses the '{destination}' backend");
+        exit(1);
+    }
+    let result = save::open_store(config.backend).and_then(|from| {
+        let mut to = save::open_store(destination)?;
+        save::migrate(from.as_ref(), to.as_mut())
+    });
+    if let Err(err) = result {
+        eprintln!("Migration failed: {err}");
+        exit(1);
+    }
+    sortie.texte(format!(
+        "Copied the '{}' store into the '{destination}' store",
+        config.backend
+    ));
+    if migrate_matches.get_flag("switch") {
+        config.backend = destination;
+        if let Err(err) = save::write_config(config) {
+            eprintln!("Could not write the configuration: {err}");
+            exit(1);
+        }
+    }
+}
+
+/// Lists the saves of the Python version, or imports one of them
+fn commande_import_legacy(import_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    sans_json(sortie, "import-legacy");
+    let Some(path) = import_matches.get_one::<PathBuf>("save") else {
+        match save::legacy::legacy_saves() {
+            Ok(saves) if saves.is_empty() => sortie.texte("No legacy save found"),
+            Ok(saves) => {
+                for save in saves {
+                    sortie.texte(save.display());
+                }
+            }
+            Err(err) => {
+                eprintln!("Could not list the legacy saves: {err}");
+                exit(1);
+            }
+        }
+        return;
+    };
+    let result = save::open_store(config.backend)
+        .and_then(|mut store| save::legacy::import_legacy_save(path, store.as_mut()));
+    match result {
+        Ok(report) => sortie.texte(report.to_string().trim_end()),
+        Err(err) => {
+            eprintln!("Import failed: {err}");
+            exit(1);
+        }
+    }
+}
+
+/// Shows or changes the configuration, and lists the profiles
+fn commande_config(config_matches: &ArgMatches, sortie: Sortie, config: &mut Config) {
+    match config_matches.subcommand() {
+        Some(("set", set_matches)) => {
+            sans_json(sortie, "config set");
+            let valeur = set_matches.get_one::<String>("valeur").unwrap();
+            // The only setting for now
+            config.territoire = valeur.parse().unwrap_or_else(|err| {
+                eprintln!("{err}");
+                exit(1)
+            });
+            if let Err(err) = save::write_config(config) {
+                eprintln!("Could not write the configuration: {err}");
+                exit(1);
+            }
+        }
+        Some(("profiles", _)) => {
+            let profils = save::profiles().unwrap_or_else(|err| {
+                eprintln!("Could not list the profiles: {err}");
+                exit(1)
+            });
+            match sortie.format {
+                Format::Json => sortie.json(&profils),
+                Format::Texte => {
+                    for profil in profils {
+                        sortie.texte(profil);
+                    }
+                }
+            }
+        }
+        _ => {
+            let dossier = save::determine_config_directory().unwrap_or_default();
+            match sortie.format {
+                Format::Json => sortie.json(&json!({
+                    "profil": save::profile(),
+                    "dossier": dossier,
+                    "backend": config.backend.to_string(),
+                    "territoire": config.territoire.to_string(),
+                })),
+                Format::Texte => sortie.texte(format!(
+                    "\x1b[94mProfil\x1b[39m : {}\n\
+                    \x1b[94mDossier\x1b[39m : {dossier}\n\
+                    \x1b[94mBackend\x1b[39m : {}\n\
+                    \x1b[94mTerritoire\x1b[39m : {}",
+                    save::profile().unwrap_or("(défaut)"),
+                    config.backend,
+                    config.territoire
+                )),
+            }
+        }
+    }
+}
+
+/// Lists the networks
+fn commande_networks(sortie: Sortie, registry: &Registry) {
+    match sortie.format {
+        Format::Json => sortie.json(
+            &registry
+                .networks()
+                .map(|network| json!({ "nom": network.name(), "description": network.description() }))
+                .collect::<Vec<_>>(),
+        ),
+        Format::Texte => {
+            for network in registry.networks() {
+                sortie.texte(format!(
+                    "\x1b[94m{}\x1b[39m : {}",
+                    network.name(),
+                    network.description()
+                ));
+            }
+        }
+    }
+}
+
+/// Lists the types of custom points
+fn commande_types(sortie: Sortie) {
+    match save::load_types() {
+        Ok(types) if sortie.format == Format::Json => sortie.json(
+            &types
+                .iter()
+                .map(|point_type| {
+                    json!({
+                        "nom": point_type.name,
+                        "champs": point_type
+                            .fields
+                            .iter()
+                            .map(|(nom, field_type)| json!({ "nom": nom, "type": field_type.to_string() }))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Ok(types) if types.is_empty() => sortie.texte(format!(
+            "No custom point type. Types are defined in {}",
+            save::types_directory().unwrap_or_default().display()
+        )),
+        Ok(types) => {
+            for point_type in types {
+                sortie.texte(point_type.to_string().trim_end());
+            }
+        }
+        Err(err) => {
+            eprintln!("Could not read the point types: {err}");
+            exit(1);
+        }
+    }
+}
+
+/// Saves a custom point
+fn commande_add_point(add_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    let type_name = add_matches.get_one::<String>("type").unwrap();
+    let types = save::load_types().unwrap_or_else(|err| {
+        eprintln!("Could not read the point types: {err}");
+        exit(1)
+    });
+    let Some(point_type) = types
+        .iter()
+        .find(|point_type| &point_type.name == type_name)
+    else {
+        eprintln!("Unknown point type: '{type_name}'");
+        exit(1);
+    };
+    let mut inputs: Vec<(String, String)> = vec![];
+    for champ in add_matches.get_many::<String>("champs").unwrap_or_default() {
+        let Some((field, value)) = champ.split_once('=') else {
+            eprintln!("'{champ}' is not of the form field=value");
+            exit(1);
+        };
+        inputs.push((field.trim().to_string(), value.to_string()));
+    }
+    let point = point_type
+        .point_from_input(&inputs)
+        .unwrap_or_else(|errors| {
+            eprintln!("Invalid point:");
+            for error in errors {
+                eprintln!("  {error}");
+            }
+            exit(1)
+        });
+    let objet = Objet::Autre(point);
+    if let Err(err) =
+        save::open_store(config.backend).and_then(|mut store| store.save_objet(&objet))
+    {
+        eprintln!("Could not save the point: {err}");
+        exit(1);
+    }
+    match sortie.format {
+        Format::Json => sortie.json(&objet),
+        Format::Texte => sortie.texte(objet),
+    }
+}
+
+/// Saves a flood mark
+fn commande_add_crue(crue_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    let text = |name: &str| -> String {
+        crue_matches
+            .get_one::<String>(name)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let crue = RepèreDeCrue {
+        identifiant: text("identifiant"),
+        rn_type: if crue_matches.get_flag("trait") {
+            RnTypeCode::TraitDeCrue
+        } else {
+            RnTypeCode::RepèreDeCrue
+        },
+        état: crue_matches.get_one::<RnÉtatCode>("etat").unwrap().clone(),
+        rivière: text("riviere"),
+        date_crue: *crue_matches.get_one::<NaiveDate>("date").unwrap(),
+        niveau: crue_matches.get_one::<f64>("niveau").copied(),
+        référence_niveau: match text("reference").as_str() {
+            "echelle" => RéférenceNiveau::Échelle,
+            "ngf-ign69" => RéférenceNiveau::Altitude(NivfReaCode::NgfIgn1969),
+            "ngf-ign78" => RéférenceNiveau::Altitude(NivfReaCode::NgfIgn1978),
+            _ => RéférenceNiveau::HauteurAuSol,
+        },
+        inscription: text("inscription"),
+        longitude: *crue_matches.get_one::<f64>("longitude").unwrap(),
+        latitude: *crue_matches.get_one::<f64>("latitude").unwrap(),
+        insee: text("insee"),
+        commune: text("commune"),
+        localisation: crue_matches.get_one::<String>("localisation").cloned(),
+        support: text("support"),
+        repère_ngf: crue_matches.get_one::<Matricule>("repere-ngf").cloned(),
+        dénivelée: crue_matches.get_one::<f64>("denivelee").copied(),
+        remarques: "".to_string(),
+    };
+    let objet = Objet::Crue(Box::new(crue));
+    if let Err(err) =
+        save::open_store(config.backend).and_then(|mut store| store.save_objet(&objet))
+    {
+        eprintln!("Could not save the flood mark: {err}");
+        exit(1);
+    }
+    match sortie.format {
+        Format::Json => sortie.json(&objet),
+        Format::Texte => sortie.texte(objet),
+    }
+}
+
+/// Builds the graph of the saved benchmarks, and the path between two of them
+fn commande_graph(
+    graph_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    territoire: Territoire,
+) {
+    if graph_matches.contains_id("export") {
+        sans_json(sortie, "graph --export");
+    }
+    let store = ouvre_store(config.backend);
+    let mut repères: Vec<RepèreNivellement> = store
+        .objets()
+        .unwrap_or_else(|err| {
+            eprintln!("Could not read the store: {err}");
+            exit(1)
+        })
+        .into_iter()
+        .filter_map(|objet| match objet {
+            Objet::Ngf(repère) => Some(*repère),
+            _ => None,
+        })
+        .collect();
+    if let Some(matricule) = graph_matches.get_one::<Matricule>("autour") {
+        let Some(centre) = repères.iter().find(|repère| &repère.matricule == matricule) else {
+            eprintln!("'{matricule}' is not saved");
+            exit(1);
+        };
+        let area = Area::around(centre.longitude, centre.latitude, 0.05);
+        match ngf::rn_in_area(&area, territoire) {
+            Ok(fetched) => repères.extend(fetched),
+            Err(err) => {
+                eprintln!("Could not fetch the benchmarks around '{matricule}': {err}");
+                exit(1);
+            }
+        }
+    }
+    let graphe =
+        ngf::GrapheRepères::new(repères, *graph_matches.get_one::<f64>("proximite").unwrap());
+    for homonyme in graphe.homonymes() {
+        eprintln!(
+            "'{}' (cid {}) was left out: another benchmark with this matricule is in the graph",
+            homonyme.matricule, homonyme.cid
+        );
+    }
+    let chemin = match (
+        graph_matches.get_one::<Matricule>("de"),
+        graph_matches.get_one::<Matricule>("vers"),
+    ) {
+        (Some(de), Some(vers)) => {
+            match graphe.chemin(de, vers, !graph_matches.get_flag("hors-ligne")) {
+                Some(chemin) => Some(chemin),
+                None => {
+                    eprintln!("No path between '{de}' and '{vers}'");
+                    exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    if sortie.format == Format::Json {
+        sortie.json(&json!({
+            "repères": graphe.nombre_de_repères(),
+            "relations": graphe.nombre_de_relations(),
+            "chemin": chemin.map(|chemin| json!({
+                "repères": chemin.repères.iter().map(|repère| &repère.matricule).collect::<Vec<_>>(),
+                "longueur": chemin.longueur,
+            })),
+        }));
+        return;
+    }
+    if let Some(chemin) = chemin {
+        for repère in &chemin.repères {
+            sortie.texte(&repère.matricule);
+        }
+        sortie.texte(format!(
+            "\x1b[94mLongueur\x1b[39m : {:.0}m",
+            chemin.longueur
+        ));
+    }
+    if let Some(format) = graph_matches.get_one::<String>("export") {
+        let export = match format.as_str() {
+            "graphml" => graphe.to_graphml(),
+            _ => graphe.to_dot(),
+        };
+        match graph_matches.get_one::<PathBuf>("output") {
+            Some(path) => {
+                if let Err(err) = std::fs::write(path, export) {
+                    eprintln!("Could not write {}: {err}", path.display());
+                    exit(1);
+                }
+            }
+            None => print!("{export}"),
+        }
+    } else if !graph_matches.contains_id("de") {
+        sortie.texte(format!(
+            "{} benchmarks, {} relations",
+            graphe.nombre_de_repères(),
+            graphe.nombre_de_relations()
+        ));
+    }
+}
+
+/// Exports the saved points
+fn commande_export(export_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    sans_json(sortie, "export");
+    let strings = |id: &str| -> Vec<String> {
+        export_matches
+            .get_many::<String>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    let store = ouvre_store(config.backend);
+    let filtre = export::Filtre {
+        ids: strings("id")
+            .iter()
+            .map(|id| id_du_point(store.as_ref(), id).to_string())
+            .collect(),
+        types: strings("type"),
+        départements: strings("departement"),
+        visité: match (
+            export_matches.get_flag("visites"),
+            export_matches.get_flag("non-visites"),
+        ) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+    };
+    let points = export::points_exportés(store.as_ref(), &filtre).unwrap_or_else(|err| {
+        eprintln!("Could not read the store: {err}");
+        exit(1)
+    });
+    let dossiers: export::kml::Dossiers = export_matches
+        .get_one::<String>("dossiers")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let délimiteur = *export_matches.get_one::<char>("separateur").unwrap() as u8;
+    let colonnes: Vec<String> = strings("colonnes");
+    let format_export = export_matches
+        .get_one::<String>("format_export")
+        .unwrap()
+        .as_str();
+    // The GPX and FlatGeobuf formats can not have points without coordinates
+    let mut exportés = points.len();
+    if matches!(format_export, "gpx" | "fgb") {
+        for point in export::sans_coordonnées(&points) {
+            eprintln!(
+                "'{}' has no coordinates, it is left out of the export",
+                point.objet.object_id()
+            );
+            exportés -= 1;
+        }
+    }
+    let export = match format_export {
+        format @ ("csv" | "csv-visites") => {
+            let mut csv: Vec<u8> = vec![];
+            let result = if format == "csv" {
+                let colonnes = if colonnes.is_empty() {
+                    export::csv::COLONNES_POINTS_PAR_DÉFAUT
+                        .map(str::to_string)
+                        .to_vec()
+                } else {
+                    colonnes
+                };
+                export::csv::exporte_points(&points, &colonnes, délimiteur, &mut csv)
+            } else {
+                let colonnes = if colonnes.is_empty() {
+                    export::csv::COLONNES_VISITES.map(str::to_string).to_vec()
+                } else {
+                    colonnes
+                };
+                let ids: Vec<String> = points.iter().map(|point| point.objet.object_id()).collect();
+                store.visites().and_then(|visites| {
+                    let visites: Vec<save::Visite> = visites
+                        .into_iter()
+                        .filter(|visite| ids.contains(&visite.objet))
+                        .collect();
+                    export::csv::exporte_visites(
+                        &visites,
+                        &store.photos()?,
+                        &colonnes,
+                        délimiteur,
+                        &mut csv,
+                    )
+                })
+            };
+            if let Err(err) = result {
+                eprintln!("Could not export the CSV: {err}");
+                exit(1);
+            }
+            String::from_utf8(csv).unwrap()
+        }
+        "kmz" => {
+            let Some(path) = export_matches.get_one::<PathBuf>("output") else {
+                eprintln!("The KMZ export needs an output file (-o)");
+                exit(1);
+            };
+            let result = std::fs::File::create(path)
+                .map_err(|err| err.into())
+                .and_then(|file| export::kml::kmz(&points, dossiers, file));
+            match result {
+                Ok(illisibles) => {
+                    for photo in illisibles {
+                        eprintln!("Could not read the photo {}", photo.fichier);
+                    }
+                    eprintln!("{} points exported to {}", points.len(), path.display());
+                }
+                Err(err) => {
+                    eprintln!("Could not write {}: {err}", path.display());
+                    exit(1);
+                }
+            }
+            return;
+        }
+        format @ ("gpkg" | "fgb") => {
+            let Some(path) = export_matches.get_one::<PathBuf>("output") else {
+                eprintln!("The GeoPackage and FlatGeobuf exports need an output file (-o)");
+                exit(1);
+            };
+            let crs: export::projection::Crs = export_matches
+                .get_one::<String>("crs")
+                .unwrap()
+                .parse()
+                .unwrap();
+            let result = if format == "gpkg" {
+                store.visites().and_then(|visites| {
+                    export::geopackage::geopackage(path, &points, &visites, crs)
+                })
+            } else {
+                std::fs::File::create(path)
+                    .map(std::io::BufWriter::new)
+                    .and_then(|file| export::flatgeobuf::flatgeobuf(&points, crs, file))
+                    .map_err(|err| err.into())
+            };
+            if let Err(err) = result {
+                eprintln!("Could not write {}: {err}", path.display());
+                exit(1);
+            }
+            eprintln!("{exportés} points exported to {}", path.display());
+            return;
+        }
+        "kml" => export::kml::kml(&points, dossiers),
+        "gpx" => export::gpx::gpx(
+            &points,
+            export_matches
+                .get_one::<String>("route")
+                .map(String::as_str),
+        ),
+        _ => serde_json::to_string_pretty(&export::geojson::feature_collection(&points)).unwrap(),
+    };
+    match export_matches.get_one::<PathBuf>("output") {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, export) {
+                eprintln!("Could not write {}: {err}", path.display());
+                exit(1);
+            }
+            eprintln!("{exportés} points exported to {}", path.display());
+        }
+        None => println!("{export}"),
+    }
+}
+
+/// Imports custom points from a CSV file
+fn commande_import_csv(
+    import_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+) {
+    let path = import_matches.get_one::<PathBuf>("fichier").unwrap();
+    let types = save::load_types().unwrap_or_else(|err| {
+        eprintln!("Could not read the point types: {err}");
+        exit(1)
+    });
+    let result = std::fs::File::open(path)
+        .map_err(|err| err.into())
+        .and_then(|file| {
+            export::csv::importe(
+                file,
+                import_matches.get_one::<String>("type").map(String::as_str),
+                &types,
+                registry,
+                *import_matches.get_one::<char>("separateur").unwrap() as u8,
             )
-            .required(true)
-            .value_parser(value_parser!(String)),
-        )
-        .get_matches();
-    if let Some(rn_matricule) = matches.get_one::<String>("matricule") {
-        let rn: RepèreNivellement =
-            ngf::get_rn_from_rn_identifications_infos(ngf::find_matricule_to_use_from_list(
-                rn_matricule,
-                &ngf::rn_from_matricule(rn_matricule),
-            ));
-        println!("{}", rn);
+        });
+    let import = result.unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {err}", path.display());
+        exit(1)
+    });
+    let result = save::open_store(config.backend).and_then(|mut store| {
+        import
+            .objets
+            .iter()
+            .try_for_each(|objet| store.save_objet(objet))
+    });
+    if let Err(err) = result {
+        eprintln!("Could not save the points: {err}");
+        exit(1);
+    }
+    if sortie.format == Format::Json {
+        sortie.json(&json!({
+            "importés": import.objets.iter().map(Objet::object_id).collect::<Vec<_>>(),
+            "erreurs": import.erreurs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        }));
+        if !import.erreurs.is_empty() {
+            exit(1);
+        }
+        return;
+    }
+    sortie.texte(format!("{} points imported", import.objets.len()));
+    if !import.erreurs.is_empty() {
+        eprintln!("{} rows could not be imported:", import.erreurs.len());
+        for erreur in import.erreurs {
+            eprintln!("  {erreur}");
+        }
+        exit(1);
+    }
+}
+
+/// Compares the saved benchmarks with OpenStreetMap
+fn commande_osm(osm_matches: &ArgMatches, sortie: Sortie, config: &Config, registry: &Registry) {
+    let extrait = osm_matches.get_one::<PathBuf>("extrait").unwrap();
+    let nœuds = osm::lis_extrait(extrait).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {err}", extrait.display());
+        exit(1)
+    });
+    eprintln!("{} survey points in {}", nœuds.len(), extrait.display());
+    let store = ouvre_store(config.backend);
+    let mut objets = store.objets().unwrap_or_else(|err| {
+        eprintln!("Could not read the store: {err}");
+        exit(1)
+    });
+    let ngf = registry.get("ngf").unwrap();
+    for matricule in osm_matches
+        .get_many::<String>("repere")
+        .into_iter()
+        .flatten()
+    {
+        match ngf.fetch(matricule) {
+            Ok(objet) => objets.push(objet),
+            Err(err) => {
+                eprintln!("Could not fetch {matricule}: {err}");
+                exit(1);
+            }
+        }
+    }
+    let tolérance = *osm_matches.get_one::<f64>("tolerance").unwrap();
+    if osm_matches.get_flag("ign") {
+        // Only the surroundings of the survey points are fetched, since an extract can cover a
+        // whole region. The margin also brings the benchmarks a little too far from their point
+        let zones: Vec<Area> = nœuds
+            .iter()
+            .map(|nœud| {
+                // About 111 km per degree of latitude, less for the longitudes
+                let degrés = (tolérance + 100.0) / (111_320.0 * nœud.latitude.to_radians().cos());
+                Area::around(nœud.longitude, nœud.latitude, degrés)
+            })
+            .collect();
+        match ngf.fetch_areas(&zones) {
+            Ok(repères) => objets.extend(repères),
+            Err(err) => {
+                eprintln!("Could not fetch the benchmarks of the area: {err}");
+                exit(1);
+            }
+        }
+    }
+    let mut repères: Vec<RepèreNivellement> = vec![];
+    for objet in objets {
+        if let Objet::Ngf(repère) = objet {
+            if repères.iter().all(|autre| autre.cid != repère.cid) {
+                repères.push(*repère);
+            }
+        }
+    }
+    let comparaisons = osm::compare(repères, &nœuds, tolérance);
+    match sortie.format {
+        Format::Json => sortie.json(
+            &comparaisons
+                .iter()
+                .map(|comparaison| {
+                    let (constat, nœud) = match &comparaison.constat {
+                        osm::Constat::Présent { nœud, distance } => {
+                            ("présent", Some((nœud, distance)))
+                        }
+                        osm::Constat::MalPlacé { nœud, distance } => {
+                            ("mal placé", Some((nœud, distance)))
+                        }
+                        osm::Constat::Discordant { nœud, distance } => {
+                            ("discordant", Some((nœud, distance)))
+                        }
+                        osm::Constat::Absent => ("absent", None),
+                    };
+                    json!({
+                        "matricule": comparaison.repère.matricule,
+                        "constat": constat,
+                        "nœud": nœud.map(|(nœud, _)| nœud.url()),
+                        "distance": nœud.map(|(_, distance)| distance),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Format::Texte => sortie.texte(osm::rapport(&comparaisons).trim_end()),
+    }
+    for (option, contenu) in [
+        ("osc", osm::osc(&comparaisons)),
+        ("taches", osm::tâches(&comparaisons)),
+    ] {
+        if let Some(path) = osm_matches.get_one::<PathBuf>(option) {
+            if let Err(err) = std::fs::write(path, contenu) {
+                eprintln!("Could not write {}: {err}", path.display());
+                exit(1);
+            }
+        }
+    }
+}
+
+/// Records, edits, removes and suggests visits
+fn commande_visit(
+    visit_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+) {
+    let mut store = ouvre_store(config.backend);
+    let visites = store.visites().unwrap_or_else(|err| {
+        eprintln!("Could not read the store: {err}");
+        exit(1)
+    });
+    let visite = |id: u64| -> save::Visite {
+        visites
+            .iter()
+            .find(|visite| visite.id == id)
+            .cloned()
+            .unwrap_or_else(|| {
+                eprintln!("No visit with id {id}");
+                exit(1)
+            })
+    };
+    match visit_matches.subcommand() {
+        Some(("add", add_matches)) => {
+            let id = id_du_point(
+                store.as_ref(),
+                add_matches.get_one::<String>("point").unwrap(),
+            );
+            match store.objet(&id.to_string()) {
+                Ok(Some(_)) => (),
+                Ok(None) => {
+                    eprintln!("'{id}' is not saved, save it first with: save \"{id}\"");
+                    exit(1);
+                }
+                Err(err) => {
+                    eprintln!("Could not read the store: {err}");
+                    exit(1);
+                }
+            }
+            let result = store.next_visite_id().and_then(|visite_id| {
+                let visite = save::Visite {
+                    id: visite_id,
+                    objet: id.to_string(),
+                    date: add_matches
+                        .get_one::<NaiveDate>("date")
+                        .copied()
+                        .unwrap_or_else(|| chrono::Local::now().date_naive()),
+                    heure: add_matches.get_one::<chrono::NaiveTime>("heure").copied(),
+                    état: add_matches.get_one::<RnÉtatCode>("etat").cloned(),
+                    remarques: add_matches
+                        .get_one::<String>("remarques")
+                        .cloned()
+                        .unwrap_or_default(),
+                    à_vérifier: false,
+                };
+                store.save_visite(&visite)?;
+                Ok(visite)
+            });
+            match result {
+                Ok(visite) if sortie.format == Format::Json => sortie.json(&visite),
+                Ok(visite) => sortie.texte(format!("Visit {} of {id} saved", visite.id)),
+                Err(err) => {
+                    eprintln!("Could not save the visit: {err}");
+                    exit(1);
+                }
+            }
+        }
+        Some(("list", list_matches)) => {
+            let objet = list_matches
+                .get_one::<String>("point")
+                .map(|point| id_du_point(store.as_ref(), point).to_string());
+            let photos = store.photos().unwrap_or_else(|err| {
+                eprintln!("Could not read the store: {err}");
+                exit(1)
+            });
+            let visites: Vec<&save::Visite> = visites
+                .iter()
+                .filter(|visite| objet.as_ref().is_none_or(|objet| &visite.objet == objet))
+                .filter(|visite| visite.à_vérifier || !list_matches.get_flag("a-verifier"))
+                .collect();
+            match sortie.format {
+                Format::Json => sortie.json(&visites),
+                Format::Texte => {
+                    for visite in visites {
+                        let nombre_de_photos = photos
+                            .iter()
+                            .filter(|photo| photo.visite == visite.id)
+                            .count();
+                        sortie.texte(format!(
+                            "\x1b[94m{}\x1b[39m : {}{} {}{}{}{}{}",
+                            visite.id,
+                            visite.date,
+                            visite
+                                .heure
+                                .map(|heure| heure.format(" %H:%M").to_string())
+                                .unwrap_or_default(),
+                            visite.objet,
+                            visite
+                                .état
+                                .as_ref()
+                                .map(|état| format!(", {état}"))
+                                .unwrap_or_default(),
+                            if visite.remarques.is_empty() {
+                                "".to_string()
+                            } else {
+                                format!(", {}", visite.remarques)
+                            },
+                            if nombre_de_photos > 0 {
+                                format!(" ({nombre_de_photos} photos)")
+                            } else {
+                                "".to_string()
+                            },
+                            if visite.à_vérifier {
+                                " \x1b[93m[to check]\x1b[39m"
+                            } else {
+                                ""
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+        Some(("edit", edit_matches)) => {
+            let mut visite = visite(*edit_matches.get_one::<u64>("id").unwrap());
+            if let Some(date) = edit_matches.get_one::<NaiveDate>("date") {
+                visite.date = *date;
+            }
+            if let Some(heure) = edit_matches.get_one::<chrono::NaiveTime>("heure") {
+                visite.heure = Some(*heure);
+            }
+            if let Some(état) = edit_matches.get_one::<RnÉtatCode>("etat") {
+                visite.état = Some(état.clone());
+            }
+            if let Some(remarques) = edit_matches.get_one::<String>("remarques") {
+                visite.remarques = remarques.clone();
+            }
+            if let Err(err) = store.save_visite(&visite) {
+                eprintln!("Could not save the visit {}: {err}", visite.id);
+                exit(1);
+            }
+            if sortie.format == Format::Json {
+                sortie.json(&visite);
+            }
+        }
+        Some(("rm", rm_matches)) => {
+            let mut supprimées: Vec<save::Visite> = vec![];
+            for id in rm_matches.get_many::<u64>("id").unwrap() {
+                supprimées.push(visite(*id));
+                let result = store.photos_of(*id).and_then(|photos| {
+                    for photo in photos {
+                        store.remove_photo(photo.id)?;
+                    }
+                    store.remove_visite(*id)
+                });
+                if let Err(err) = result {
+                    eprintln!("Could not remove the visit {id}: {err}");
+                    exit(1);
+                }
+            }
+            if sortie.format == Format::Json {
+                sortie.json(&supprimées);
+            }
+        }
+        Some(("suggest", suggest_matches)) => {
+            let path = suggest_matches.get_one::<PathBuf>("trace").unwrap();
+            let segments = std::fs::File::open(path)
+                .map_err(|err| err.into())
+                .and_then(|file| trace::lis_gpx(std::io::BufReader::new(file)))
+                .unwrap_or_else(|err| {
+                    eprintln!("Could not read {}: {err}", path.display());
+                    exit(1)
+                });
+            let sauvegardés = store.objets().unwrap_or_else(|err| {
+                eprintln!("Could not read the store: {err}");
+                exit(1)
+            });
+            let mut objets = sauvegardés.clone();
+            if suggest_matches.get_flag("ign") {
+                // A margin of about 500 m, for the benchmarks close to the ends of the track
+                let zone = trace::zone(&segments, 0.005);
+                match zone.map(|zone| registry.get("ngf").unwrap().fetch_area(&zone)) {
+                    Some(Ok(repères)) => objets.extend(repères.into_iter().filter(|repère| {
+                        !sauvegardés
+                            .iter()
+                            .any(|objet| objet.object_id() == repère.object_id())
+                    })),
+                    Some(Err(err)) => {
+                        eprintln!("Could not fetch the benchmarks along the track: {err}");
+                        exit(1);
+                    }
+                    None => {}
+                }
+            }
+            let passages = trace::passages(
+                &segments,
+                objets,
+                *suggest_matches.get_one::<f64>("corridor").unwrap(),
+            );
+            if passages.is_empty() && sortie.format == Format::Texte {
+                sortie.texte("No point along the track");
+                return;
+            }
+            let date = suggest_matches.get_one::<NaiveDate>("date");
+            let mut créées: Vec<u64> = vec![];
+            let mut suggestions: Vec<serde_json::Value> = vec![];
+            for passage in passages {
+                let heure = passage
+                    .heure
+                    .map(|heure| heure.with_timezone(&chrono::Local));
+                suggestions.push(json!({
+                    "id": passage.objet.object_id(),
+                    "distance": passage.distance,
+                    "heure": heure,
+                    "visite": null,
+                }));
+                if sortie.format == Format::Texte {
+                    sortie.texte(format!(
+                        "\x1b[94m{}\x1b[39m : {} ({:.0} m)",
+                        match heure {
+                            Some(heure) => heure.format("%Y-%m-%d %H:%M").to_string(),
+                            None => "--".to_string(),
+                        },
+                        passage.objet.object_id(),
+                        passage.distance
+                    ));
+                }
+                let Some(date_visite) = heure.map(|heure| heure.date_naive()).or(date.copied())
+                else {
+                    if sortie.format == Format::Texte {
+                        sortie.texte(
+                            "  The track has no time, give the date of the visit with --date",
+                        );
+                    }
+                    continue;
+                };
+                if !suggest_matches.get_flag("oui") {
+                    // The JSON output has no room for the questions: the visits are only created
+                    // with --oui
+                    if sortie.format == Format::Json {
+                        continue;
+                    }
+                    let réponse = sortie.demande("  Create a visit to check? [y/N] ");
+                    if !matches!(réponse.to_lowercase().as_str(), "y" | "o" | "yes" | "oui") {
+                        continue;
+                    }
+                }
+                let result = store.next_visite_id().and_then(|id| {
+                    if !sauvegardés
+                        .iter()
+                        .any(|objet| objet.object_id() == passage.objet.object_id())
+                    {
+                        store.save_objet(&passage.objet)?;
+                    }
+                    store.save_visite(&save::Visite {
+                        id,
+                        objet: passage.objet.object_id(),
+                        date: date_visite,
+                        heure: heure.map(|heure| heure.time()),
+                        état: None,
+                        remarques: format!(
+                            "Passage à {:.0} m d’après la trace {}",
+                            passage.distance,
+                            path.display()
+                        ),
+                        à_vérifier: true,
+                    })?;
+                    Ok(id)
+                });
+                match result {
+                    Ok(id) => {
+                        créées.push(id);
+                        if let Some(suggestion) = suggestions.last_mut() {
+                            suggestion["visite"] = json!(id);
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("Could not save the visit: {err}");
+                        exit(1);
+                    }
+                }
+            }
+            if sortie.format == Format::Json {
+                sortie.json(&suggestions);
+            } else if !créées.is_empty() {
+                sortie.texte(format!(
+                    "{} visits to check created, confirm them with: visit confirm {}",
+                    créées.len(),
+                    créées
+                        .iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                ));
+            }
+        }
+        Some(("confirm", confirm_matches)) => {
+            let rejette = confirm_matches.get_flag("rejette");
+            let mut traitées: Vec<save::Visite> = vec![];
+            for id in confirm_matches.get_many::<u64>("id").unwrap() {
+                let Some(visite) = visites.iter().find(|visite| visite.id == *id) else {
+                    eprintln!("No visit with id {id}");
+                    exit(1);
+                };
+                let visite = if rejette {
+                    visite.clone()
+                } else {
+                    save::Visite {
+                        à_vérifier: false,
+                        ..visite.clone()
+                    }
+                };
+                let result = if rejette {
+                    store.remove_visite(*id)
+                } else {
+                    store.save_visite(&visite)
+                };
+                if let Err(err) = result {
+                    eprintln!("Could not save the visit {id}: {err}");
+                    exit(1);
+                }
+                traitées.push(visite);
+            }
+            // The confirmed visits, or the rejected ones that were removed
+            if sortie.format == Format::Json {
+                sortie.json(&traitées);
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Adds and removes the photos of the visits
+fn commande_photo(photo_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    let mut store = ouvre_store(config.backend);
+    match photo_matches.subcommand() {
+        Some(("import", import_matches)) => {
+            let visite = *import_matches.get_one::<u64>("visite").unwrap();
+            match store.visites() {
+                Ok(visites) if visites.iter().any(|autre| autre.id == visite) => (),
+                Ok(_) => {
+                    eprintln!("No visit with id {visite}");
+                    exit(1);
+                }
+                Err(err) => {
+                    eprintln!("Could not read the store: {err}");
+                    exit(1);
+                }
+            }
+            let légende = import_matches.get_one::<String>("legende").unwrap();
+            let mut importées: Vec<save::Photo> = vec![];
+            for path in import_matches.get_many::<PathBuf>("fichier").unwrap() {
+                // Like the photos of the legacy saves, the photos stay where they are
+                let result: Result<save::Photo, Box<dyn std::error::Error>> =
+                    image::image_dimensions(path)
+                        .map_err(|err| err.into())
+                        .and_then(|_| Ok(path.canonicalize()?))
+                        .and_then(|fichier| {
+                            let photo = save::Photo {
+                                id: store.next_photo_id()?,
+                                visite,
+                                fichier: fichier.to_string_lossy().to_string(),
+                                légende: légende.clone(),
+                            };
+                            store.save_photo(&photo)?;
+                            Ok(photo)
+                        });
+                match result {
+                    Ok(photo) if sortie.format == Format::Json => importées.push(photo),
+                    Ok(photo) => {
+                        sortie.texte(format!("Photo {} added to the visit {visite}", photo.id))
+                    }
+                    Err(err) => {
+                        eprintln!("Could not import {}: {err}", path.display());
+                        exit(1);
+                    }
+                }
+            }
+            if sortie.format == Format::Json {
+                sortie.json(&importées);
+            }
+        }
+        Some(("list", list_matches)) => {
+            let photos = match list_matches.get_one::<u64>("visite") {
+                Some(visite) => store.photos_of(*visite),
+                None => store.photos(),
+            }
+            .unwrap_or_else(|err| {
+                eprintln!("Could not read the store: {err}");
+                exit(1)
+            });
+            match sortie.format {
+                Format::Json => sortie.json(&photos),
+                Format::Texte => {
+                    for photo in photos {
+                        sortie.texte(format!(
+                            "\x1b[94m{}\x1b[39m : visit {}, {}{}",
+                            photo.id,
+                            photo.visite,
+                            photo.fichier,
+                            if photo.légende.is_empty() {
+                                "".to_string()
+                            } else {
+                                format!(" ({})", photo.légende)
+                            }
+                        ));
+                    }
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Generates the static site of the saved points
+fn commande_site(site_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    sans_json(sortie, "site");
+    let dossier = site_matches.get_one::<PathBuf>("dossier").unwrap();
+    let store = ouvre_store(config.backend);
+    let (points, visites) = export::points_exportés(store.as_ref(), &export::Filtre::default())
+        .and_then(|points| Ok((points, store.visites()?)))
+        .unwrap_or_else(|err| {
+            eprintln!("Could not read the store: {err}");
+            exit(1)
+        });
+    let illisibles = site::génère(dossier, &points, &visites).unwrap_or_else(|err| {
+        eprintln!("Could not write the site in {}: {err}", dossier.display());
+        exit(1)
+    });
+    for photo in illisibles {
+        eprintln!(
+            "Could not read the photo {}, left out of the site",
+            photo.fichier
+        );
+    }
+    sortie.texte(format!(
+        "{} points written to {}",
+        points.len(),
+        dossier.join("index.html").display()
+    ));
+}
+
+/// Generates the field sheets of points
+fn commande_field_sheets(
+    sheets_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+) {
+    sans_json(sortie, "field-sheets");
+    let store = ouvre_store(config.backend);
+    let mut repères: Vec<RepèreNivellement> = vec![];
+    for matricule in sheets_matches
+        .get_many::<String>("matricule")
+        .into_iter()
+        .flatten()
+    {
+        let id = id_du_point(store.as_ref(), matricule);
+        let objet = match store.objet(&id.to_string()) {
+            Ok(Some(objet)) => objet,
+            Ok(None) => registry.fetch(&id).unwrap_or_else(|err| {
+                eprintln!("Could not fetch {matricule}: {err}");
+                exit(1)
+            }),
+            Err(err) => {
+                eprintln!("Could not read the store: {err}");
+                exit(1);
+            }
+        };
+        match objet {
+            Objet::Ngf(repère) => repères.push(*repère),
+            objet => {
+                eprintln!(
+                    "'{matricule}' is not a benchmark, but a point of type '{}'",
+                    objet.type_name()
+                );
+                exit(1);
+            }
+        }
+    }
+    if sheets_matches.get_flag("sauvegardes") {
+        let filtre = export::Filtre {
+            types: vec!["ngf".to_string()],
+            départements: sheets_matches
+                .get_many::<String>("departement")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default(),
+            visité: sheets_matches.get_flag("non-visites").then_some(false),
+            ..Default::default()
+        };
+        let points = export::points_exportés(store.as_ref(), &filtre).unwrap_or_else(|err| {
+            eprintln!("Could not read the store: {err}");
+            exit(1)
+        });
+        for point in points {
+            if let Objet::Ngf(repère) = point.objet {
+                if repères.iter().all(|autre| autre.cid != repère.cid) {
+                    repères.push(*repère);
+                }
+            }
+        }
+    }
+    if repères.is_empty() {
+        eprintln!("No benchmark to print, give matricules or --sauvegardes");
+        exit(1);
+    }
+    let path = sheets_matches.get_one::<PathBuf>("output").unwrap();
+    let result = fiches::pdf(&repères, sheets_matches.get_one::<String>("titre").unwrap())
+        .and_then(|pdf| Ok(std::fs::write(path, pdf)?));
+    if let Err(err) = result {
+        eprintln!("Could not write {}: {err}", path.display());
+        exit(1);
+    }
+    sortie.texte(format!(
+        "{} field sheets written to {}",
+        repères.len(),
+        path.display()
+    ));
+}
+
+/// Lists the saved points
+fn commande_list(list_matches: &ArgMatches, sortie: Sortie, config: &Config) {
+    let strings = |id: &str| -> Vec<String> {
+        list_matches
+            .get_many::<String>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    let filtre = export::Filtre {
+        types: strings("type"),
+        départements: strings("departement"),
+        visité: match (
+            list_matches.get_flag("visites"),
+            list_matches.get_flag("non-visites"),
+        ) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+        ..Default::default()
+    };
+    let store = ouvre_store(config.backend);
+    let points = export::points_exportés(store.as_ref(), &filtre).unwrap_or_else(|err| {
+        eprintln!("Could not read the store: {err}");
+        exit(1)
+    });
+    match sortie.format {
+        Format::Json => sortie.json(
+            &points
+                .iter()
+                .map(|point| {
+                    point
+                        .attributs()
+                        .into_iter()
+                        .map(|(nom, attribut)| (nom.to_string(), attribut.json()))
+                        .collect::<serde_json::Map<String, serde_json::Value>>()
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Format::Texte => {
+            for point in &points {
+                let mut détails: Vec<String> = vec![];
+                if let Some(commune) = point.commune() {
+                    détails.push(commune);
+                }
+                if let Some(état) = point.état() {
+                    détails.push(état.to_string());
+                }
+                détails.push(match point.dernière_visite {
+                    Some(date) => format!("{} visits, last on {date}", point.nombre_de_visites),
+                    None => "never visited".to_string(),
+                });
+                sortie.texte(format!(
+                    "\x1b[94m{}\x1b[39m : {}",
+                    point.objet.object_id(),
+                    détails.join(", ")
+                ));
+            }
+        }
+    }
+}
+
+/// Searches a network
+fn commande_search(search_matches: &ArgMatches, sortie: Sortie, registry: &Registry) {
+    let network_name = search_matches.get_one::<String>("network").unwrap();
+    let Some(network) = registry.get(network_name) else {
+        eprintln!("Unknown network: '{network_name}'");
+        exit(1);
+    };
+    let query = search_matches.get_one::<String>("requete").unwrap();
+    let results = network.search(query).unwrap_or_else(|err| {
+        eprintln!("Search failed: {err}");
+        exit(1)
+    });
+    match sortie.format {
+        Format::Json => sortie.json(&results),
+        Format::Texte if results.is_empty() => {
+            eprintln!("Nothing found for '{query}'");
+            exit(1);
+        }
+        Format::Texte => {
+            for result in results {
+                sortie.texte(match result.cid {
+                    Some(cid) => format!("{} \x1b[90m(cid {cid})\x1b[39m", result.key),
+                    None => result.key,
+                });
+            }
+        }
+    }
+}
+
+/// Lists the saved points close to a point
+fn commande_nearby(
+    nearby_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+) {
+    let store = ouvre_store(config.backend);
+    let mut centre_id: Option<String> = None;
+    let centre = match (
+        nearby_matches.get_one::<f64>("longitude"),
+        nearby_matches.get_one::<f64>("latitude"),
+    ) {
+        (Some(longitude), Some(latitude)) => (*longitude, *latitude),
+        _ => {
+            let id = id_du_point(
+                store.as_ref(),
+                nearby_matches.get_one::<String>("point").unwrap(),
+            );
+            let objet = match store.objet(&id.to_string()) {
+                Ok(Some(objet)) => objet,
+                Err(err) => {
+                    eprintln!("Could not read the store: {err}");
+                    exit(1);
+                }
+                Ok(None) => registry.fetch(&id).unwrap_or_else(|err| {
+                    eprintln!("Could not fetch '{id}': {err}");
+                    exit(1)
+                }),
+            };
+            centre_id = Some(id.to_string());
+            objet.coordonnées().unwrap_or_else(|| {
+                eprintln!("'{id}' has no coordinates");
+                exit(1)
+            })
+        }
+    };
+    let rayon = *nearby_matches.get_one::<f64>("rayon").unwrap();
+    let objets = if nearby_matches.get_flag("sauvegardes") {
+        store.objets()
     } else {
-        println!("Please specify a matricule")
+        let network_name = nearby_matches.get_one::<String>("network").unwrap();
+        let Some(network) = registry.get(network_name) else {
+            eprintln!("Unknown network: '{network_name}'");
+            exit(1);
+        };
+        // About 111 km per degree of latitude, less for the longitudes
+        let degrés = rayon / (111_320.0 * centre.1.to_radians().cos());
+        network.fetch_area(&Area::around(centre.0, centre.1, degrés))
+    }
+    .unwrap_or_else(|err| {
+        eprintln!("Could not fetch the points around: {err}");
+        exit(1)
+    });
+    let mut proches: Vec<(f64, Objet)> = objets
+        .into_iter()
+        .filter(|objet| Some(objet.object_id()) != centre_id)
+        .filter_map(|objet| Some((distance(centre, objet.coordonnées()?), objet)))
+        .filter(|(distance, _)| *distance <= rayon)
+        .collect();
+    proches.sort_by(|a, b| a.0.total_cmp(&b.0));
+    match sortie.format {
+        Format::Json => sortie.json(
+            &proches
+                .iter()
+                .map(|(distance, objet)| {
+                    json!({
+                        "id": objet.object_id(),
+                        "distance": distance,
+                        "coordonnees": objet.coordonnées(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Format::Texte => {
+            for (distance, objet) in proches {
+                sortie.texte(format!(
+                    "\x1b[94m{}\x1b[39m : {distance:.0} m",
+                    objet.object_id()
+                ));
+            }
+        }
+    }
+}
+
+/// Fetches and saves points
+fn commande_save(
+    save_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+    territoire: Territoire,
+) {
+    let points: Vec<Option<&str>> = match save_matches.get_many::<String>("point") {
+        Some(points) => points.map(|point| Some(point.as_str())).collect(),
+        None => vec![None],
+    };
+    let mut store = ouvre_store(config.backend);
+    let mut sauvegardés: Vec<String> = vec![];
+    for point in points {
+        let objet = récupère(save_matches, sortie, point, registry, territoire);
+        let (objets, _) = avec_groupe(save_matches, objet);
+        for objet in objets {
+            if let Err(err) = store.save_objet(&objet) {
+                eprintln!("Could not save '{}': {err}", objet.object_id());
+                exit(1);
+            }
+            if sortie.format == Format::Texte {
+                sortie.texte(format!("Saved {}", objet.object_id()));
+            }
+            sauvegardés.push(objet.object_id());
+        }
+    }
+    if sortie.format == Format::Json {
+        sortie.json(&sauvegardés);
+    }
+}
+
+/// Shows a point, saved or fetched
+fn commande_show(
+    show_matches: &ArgMatches,
+    sortie: Sortie,
+    config: &Config,
+    registry: &Registry,
+    territoire: Territoire,
+) {
+    let point = show_matches.get_one::<String>("point");
+    let store = ouvre_store(config.backend);
+    // The saved points are shown as they are saved, with what they are linked to
+    let sauvegardé = point
+        .filter(|point| point.parse::<ObjectId>().is_ok())
+        .map(|point| id_du_point(store.as_ref(), point))
+        .and_then(|id| {
+            store.objet(&id.to_string()).unwrap_or_else(|err| {
+                eprintln!("Could not read the store: {err}");
+                exit(1)
+            })
+        });
+    if let (Some(objet), Format::Texte) = (&sauvegardé, sortie.format) {
+        match objet {
+            Objet::Crue(crue) => {
+                let mut texte = crue.to_string();
+                // The altitude of the flood comes from the NGF benchmark it is levelled from
+                let repère_ngf = match &crue.repère_ngf {
+                    Some(matricule) => match store
+                        .id_of_matricule(matricule)
+                        .and_then(|id| store.objet(id.to_string().as_str()))
+                    {
+                        Ok(Some(Objet::Ngf(repère))) => Some(repère),
+                        _ => None,
+                    },
+                    None => None,
+                };
+                if let Some(altitude) = crue.altitude(repère_ngf.as_deref()) {
+                    texte.push_str(&format!(
+                        "\x1b[94mAltitude de la crue\x1b[39m : {altitude:.3}m\n"
+                    ));
+                }
+                sortie.texte(texte);
+            }
+            Objet::Ngf(repère) => {
+                sortie.texte(repère);
+                // The geodetic site the benchmark belongs to, if it was saved too
+                if let Some(sit_no) = &repère.site_géodésique {
+                    match store.objet(ObjectId::new("geod", sit_no).to_string().as_str()) {
+                        Ok(Some(site)) => sortie.texte(site),
+                        _ => sortie.texte(format!(
+                            "The geodetic site {sit_no} is not saved (save it with: save geod/{sit_no})"
+                        )),
+                    }
+                }
+            }
+            objet => sortie.texte(objet),
+        }
+        return;
+    }
+    if let Some(objet) = sauvegardé {
+        sortie.json(&objet);
+        return;
+    }
+    let objet = récupère(
+        show_matches,
+        sortie,
+        point.map(String::as_str),
+        registry,
+        territoire,
+    );
+    let texte = match registry.get(objet.type_name()) {
+        Some(network) => network.display(&objet),
+        None => objet.to_string(),
+    };
+    let (objets, groupe) = avec_groupe(show_matches, objet);
+    match sortie.format {
+        Format::Json if groupe.is_some() => sortie.json(&objets),
+        Format::Json => sortie.json(&objets[0]),
+        Format::Texte => {
+            sortie.texte(texte);
+            if let Some(groupe) = groupe {
+                sortie.texte(groupe);
+            }
+        }
+    }
+}
+
+fn main() {
+    let matches = cli_interface::commande().get_matches();
+    let sortie = Sortie::new(&matches);
+
+    if let Some(profil) = matches.get_one::<String>("profil") {
+        if let Err(err) = save::set_profile(profil) {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
+    let mut config = save::read_config().unwrap_or_else(|err| {
+        eprintln!("Could not read the configuration: {err}");
+        exit(1)
+    });
+
+    let mut registry = Registry::default();
+    let territoire = matches
+        .get_one::<Territoire>("territoire")
+        .copied()
+        .unwrap_or(config.territoire);
+    registry.register(Box::new(ngf::Ngf { territoire }));
+
+    match matches.subcommand() {
+        Some(("migrate", migrate_matches)) => {
+            commande_migrate(migrate_matches, sortie, &mut config)
+        }
+        Some(("import-legacy", import_matches)) => {
+            commande_import_legacy(import_matches, sortie, &config)
+        }
+        Some(("config", config_matches)) => commande_config(config_matches, sortie, &mut config),
+        Some(("networks", _)) => commande_networks(sortie, &registry),
+        Some(("types", _)) => commande_types(sortie),
+        Some(("add-point", add_matches)) => commande_add_point(add_matches, sortie, &config),
+        Some(("add-crue", crue_matches)) => commande_add_crue(crue_matches, sortie, &config),
+        Some(("graph", graph_matches)) => {
+            commande_graph(graph_matches, sortie, &config, territoire)
+        }
+        Some(("export", export_matches)) => commande_export(export_matches, sortie, &config),
+        Some(("import-csv", import_matches)) => {
+            commande_import_csv(import_matches, sortie, &config, &registry)
+        }
+        Some(("osm", osm_matches)) => commande_osm(osm_matches, sortie, &config, &registry),
+        Some(("visit", visit_matches)) => commande_visit(visit_matches, sortie, &config, &registry),
+        Some(("photo", photo_matches)) => commande_photo(photo_matches, sortie, &config),
+        Some(("site", site_matches)) => commande_site(site_matches, sortie, &config),
+        Some(("field-sheets", sheets_matches)) => {
+            commande_field_sheets(sheets_matches, sortie, &config, &registry)
+        }
+        Some(("list", list_matches)) => commande_list(list_matches, sortie, &config),
+        Some(("search", search_matches)) => commande_search(search_matches, sortie, &registry),
+        Some(("nearby", nearby_matches)) => {
+            commande_nearby(nearby_matches, sortie, &config, &registry)
+        }
+        Some(("save", save_matches)) => {
+            commande_save(save_matches, sortie, &config, &registry, territoire)
+        }
+        Some(("show", show_matches)) => {
+            commande_show(show_matches, sortie, &config, &registry, territoire)
+        }
+        _ => unreachable!(),
     }
 }
@@ -0,0 +1,431 @@
+//! Networks of points that can be searched and fetched (the NGF levelling network, geodetic
+//! sites…). Each network implements Network, and the Registry lists the networks the CLI can
+//! dispatch to
+use crate::{
+    cli_interface::sans_couleurs,
+    common_traits::{Identified, ObjectId},
+    ngf::Matricule,
+    save::Objet,
+};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt,
+    io::{self, IsTerminal, Write},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// A point found by Network::search
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    /// The key of the point in its network (the matricule for NGF benchmarks)
+    pub key: String,
+    /// The id used by the network API, if any
+    pub cid: Option<u32>,
+}
+
+/// An area in WGS84 coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Area {
+    pub longitude_min: f64,
+    pub latitude_min: f64,
+    pub longitude_max: f64,
+    pub latitude_max: f64,
+}
+
+impl Area {
+    /// The area within `radius` degrees of the point
+    pub fn around(longitude: f64, latitude: f64, radius: f64) -> Area {
+        Area {
+            longitude_min: longitude - radius,
+            latitude_min: latitude - radius,
+            longitude_max: longitude + radius,
+            latitude_max: latitude + radius,
+        }
+    }
+
+    pub fn contains(&self, longitude: f64, latitude: f64) -> bool {
+        (self.longitude_min..=self.longitude_max).contains(&longitude)
+            && (self.latitude_min..=self.latitude_max).contains(&latitude)
+    }
+}
+
+/// Mean radius of the Earth, in metres
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// The distance between two (longitude, latitude) points along the great circle, in metres
+pub fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (longitude_a, latitude_a) = (a.0.to_radians(), a.1.to_radians());
+    let (longitude_b, latitude_b) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((latitude_b - latitude_a) / 2.0).sin().powi(2)
+        + latitude_a.cos() * latitude_b.cos() * ((longitude_b - longitude_a) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+/// A network of points
+pub trait Network {
+    /// The name of the network, used as the first part of the object ids (see Identified)
+    fn name(&self) -> &str;
+
+    /// A short description shown to the user
+    fn description(&self) -> &str;
+
+    /// Returns the points whose key contains `query`
+    fn search(&self, query: &str) -> Result<Vec<SearchResult>, Box<dyn Error>>;
+
+    /// Returns the points whose key is close to `query`, closest first, when search finds
+    /// nothing. Networks without suggestions return an empty list
+    fn suggest(&self, _query: &str) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+        Ok(vec![])
+    }
+
+    /// Fetches the point identified by `key` in this network
+    fn fetch(&self, key: &str) -> Result<Objet, Box<dyn Error>>;
+
+    /// Fetches all the points of the area
+    fn fetch_area(&self, area: &Area) -> Result<Vec<Objet>, Box<dyn Error>>;
+
+    /// Fetches all the points of the areas, once even if they are in several areas
+    fn fetch_areas(&self, areas: &[Area]) -> Result<Vec<Objet>, Box<dyn Error>> {
+        let mut ids: HashSet<String> = HashSet::new();
+        let mut objets: Vec<Objet> = vec![];
+        for area in areas {
+            for objet in self.fetch_area(area)? {
+                if ids.insert(objet.object_id()) {
+                    objets.push(objet);
+                }
+            }
+        }
+        Ok(objets)
+    }
+
+    /// The fiche of the point shown to the user
+    fn display(&self, objet: &Objet) -> String {
+        objet.to_string()
+    }
+}
+
+/// The networks the CLI can use
+pub struct Registry {
+    networks: Vec<Box<dyn Network>>,
+}
+
+impl Registry {
+    /// A registry without any network
+    pub fn empty() -> Registry {
+        Registry { networks: vec![] }
+    }
+
+    /// Adds a network, replacing the network with the same name if any
+    pub fn register(&mut self, network: Box<dyn Network>) {
+        self.networks
+            .retain(|registered| registered.name() != network.name());
+        self.networks.push(network);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Network> {
+        self.networks
+            .iter()
+            .find(|network| network.name() == name)
+            .map(|network| network.as_ref())
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = &dyn Network> {
+        self.networks.iter().map(|network| network.as_ref())
+    }
+
+    /// Fetches a point from the network given by its id
+    pub fn fetch(&self, id: &ObjectId) -> Result<Objet, Box<dyn Error>> {
+        self.get(&id.network)
+            .ok_or(format!("Unknown network: '{}'", id.network))?
+            .fetch(&id.key)
+    }
+}
+
+impl Default for Registry {
+    /// The registry with all the networks of this crate
+    fn default() -> Registry {
+        let mut registry = Registry::empty();
+        registry.register(Box::new(crate::ngf::Ngf::default()));
+        registry.register(Box::new(crate::geod::Geod));
+        registry
+    }
+}
+
+/// A function choosing among the results, for frontends that are not terminals. It returns None
+/// if the user cancels
+pub type ChooserCallback = Arc<dyn Fn(&str, &[SearchResult]) -> Option<SearchResult> + Send + Sync>;
+
+/// How to pick a result when a search returns several ones. The result matching the query exactly
+/// is always picked directly, and so is a single result, except with ExactOnly and FailWithList
+#[derive(Clone)]
+pub enum Chooser {
+    /// Prompts the user in the terminal, with colours or not
+    Interactive { couleurs: bool },
+    /// Picks the first result
+    First,
+    /// Only accepts the result matching the query exactly, even if there is a single result
+    ExactOnly,
+    /// Fails with the list of the results, even if there is a single inexact one
+    FailWithList,
+    /// Lets a callback choose
+    Callback(ChooserCallback),
+}
+
+/// Why Chooser::choose did not pick a result
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChoiceError {
+    NoResult,
+    /// Several results (or a single inexact one with ExactOnly or FailWithList), and the chooser
+    /// did not pick one
+    Ambiguous(Vec<SearchResult>),
+    /// The user or the callback did not choose
+    Cancelled,
+    /// The callback returned a result that is not one of the results it was given
+    UnknownChoice(SearchResult),
+}
+
+impl fmt::Display for ChoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChoiceError::NoResult => write!(f, "No result"),
+            ChoiceError::Ambiguous(results) => write!(
+                f,
+                "No exact match among {} results: {}",
+                results.len(),
+                results
+                    .iter()
+                    .map(|result| result.key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ChoiceError::Cancelled => write!(f, "No result chosen"),
+            ChoiceError::UnknownChoice(result) => {
+                write!(f, "'{}' is not one of the results", result.key)
+            }
+        }
+    }
+}
+
+impl Error for ChoiceError {}
+
+impl Chooser {
+    /// The same chooser, prompting with colours or not if it is interactive
+    pub fn avec_couleurs(self, couleurs: bool) -> Chooser {
+        match self {
+            Chooser::Interactive { .. } => Chooser::Interactive { couleurs },
+            chooser => chooser,
+        }
+    }
+
+    /// Picks the result to use for `query`. The quotes and whitespace of the query are normalised
+    /// as in the matricules
+    pub fn choose(
+        &self,
+        query: &str,
+        results: &[SearchResult],
+    ) -> Result<SearchResult, ChoiceError> {
+        if results.is_empty() {
+            return Err(ChoiceError::NoResult);
+        }
+        // If there is one result in the list that matches exactly the query, we return it directly
+        let query = Matricule::normalise(query);
+        if let Some(result) = results.iter().find(|result| result.key == query) {
+            return Ok(result.clone());
+        }
+        // If there is only one result in the list, we return it directly
+        if results.len() == 1 && !matches!(self, Chooser::ExactOnly | Chooser::FailWithList) {
+            return Ok(results[0].clone());
+        }
+        match self {
+            Chooser::Interactive { couleurs } => {
+                prompt_search_result(results, *couleurs).ok_or(ChoiceError::Cancelled)
+            }
+            Chooser::First => Ok(results[0].clone()),
+            Chooser::ExactOnly | Chooser::FailWithList => {
+                Err(ChoiceError::Ambiguous(results.to_vec()))
+            }
+            Chooser::Callback(callback) => match callback(&query, results) {
+                Some(choice) if results.contains(&choice) => Ok(choice),
+                Some(choice) => Err(ChoiceError::UnknownChoice(choice)),
+                None => Err(ChoiceError::Cancelled),
+            },
+        }
+    }
+}
+
+impl FromStr for Chooser {
+    type Err = String;
+
+    /// The strategies the CLI offers (callbacks can not be given as text). The interactive chooser
+    /// uses colours, see avec_couleurs
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "interactif" => Ok(Chooser::Interactive { couleurs: true }),
+            "premier" => Ok(Chooser::First),
+            "exact" => Ok(Chooser::ExactOnly),
+            "liste" => Ok(Chooser::FailWithList),
+            _ => Err(format!(
+                "Unknown choice strategy: '{s}' (expected interactif, premier, exact or liste)"
+            )),
+        }
+    }
+}
+
+/// Picks the result to use for `query`: the only result, the result matching the query exactly, or
+/// the one the user chooses. Returns None if there is no result. The prompt has colours only in a
+/// terminal, and if NO_COLOR is not set
+pub fn choose_search_result(query: &str, results: &[SearchResult]) -> Option<SearchResult> {
+    let couleurs = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    Chooser::Interactive { couleurs }
+        .choose(query, results)
+        .ok()
+}
+
+/// Prompts the user to choose one of the results. Returns None if stdin is closed
+fn prompt_search_result(results: &[SearchResult], couleurs: bool) -> Option<SearchResult> {
+    let colorie = |texte: String| {
+        if couleurs {
+            texte
+        } else {
+            sans_couleurs(&texte)
+        }
+    };
+    let mut string_found_results: String = String::new();
+    // Get some sizes to align the text (we therefore need to parse the list twice)
+    let max_size_indexes: usize = (results.len() - 1).to_string().len();
+    let max_size_key: usize = results
+        .iter()
+        .map(|result| result.key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max_size_cid: usize = results
+        .iter()
+        .map(|result| result.cid.map(|cid| cid.to_string().len()).unwrap_or(0))
+        .max()
+        .unwrap_or(0);
+    for (index, result) in results.iter().enumerate() {
+        string_found_results += format!(
+            "\x1b[92;1m{index:<max_size_indexes$}\x1b[39;22m : \x1b[94;1m{:<max_size_key$}\x1b[39;22m{}\n",
+            result.key,
+            match result.cid {
+                Some(cid) => format!(" (id \x1b[95;1m{cid:>max_size_cid$}\x1b[39;22m)"),
+                None => "".to_string(),
+            },
+        )
+        .as_str();
+    }
+    println!(
+        "\
+    Repères found :\n\
+    {}\
+    ",
+        colorie(string_found_results)
+    );
+    loop {
+        print!(
+            "{}",
+            colorie(format!(
+                "Your choice (\x1b[92;1m0\x1b[22m-\x1b[1m{number_of_results}\x1b[39;22m) : ",
+                number_of_results = results.len() - 1
+            ))
+        );
+        let mut input: String = String::new();
+        io::stdout().flush().unwrap();
+        if io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            println!();
+            break None;
+        }
+        if let Some(result) = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|choice| results.get(choice))
+        {
+            break Some(result.clone());
+        };
+        println!(
+            "{}",
+            colorie(format!(
+                "Please enter a valid choice from \x1b[92;1m0\x1b[22m to \x1b[1m{number_of_results}\x1b[39;22m",
+                number_of_results = results.len() - 1
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_registry() {
+    let registry = Registry::default();
+    assert!(registry.get("ngf").is_some());
+    assert!(registry.get("crue").is_none());
+    assert!(registry.fetch(&ObjectId::new("inconnu", "1")).is_err());
+
+    let results = vec![
+        SearchResult {
+            key: "T'.D.S3 - 5".to_string(),
+            cid: Some(1),
+        },
+        SearchResult {
+            key: "T'.D.S3 - 50".to_string(),
+            cid: Some(452592),
+        },
+    ];
+    assert_eq!(choose_search_result("T'.D.S3 - 5", &[]), None);
+    assert_eq!(
+        choose_search_result("T'.D.S3 - 50", &results),
+        Some(results[1].clone())
+    );
+    let query = "T'.D.S3";
+    assert_eq!(
+        Chooser::First.choose(query, &results),
+        Ok(results[0].clone())
+    );
+    assert_eq!(
+        Chooser::FailWithList.choose(query, &results),
+        Err(ChoiceError::Ambiguous(results.clone()))
+    );
+    assert_eq!(
+        Chooser::FailWithList.choose(query, &results[1..]),
+        Err(ChoiceError::Ambiguous(results[1..].to_vec()))
+    );
+    assert!(Chooser::ExactOnly.choose(query, &results[1..]).is_err());
+    // The query is normalised before looking for the exact match
+    assert_eq!(
+        Chooser::ExactOnly.choose(" T’.D.S3  - 5", &results),
+        Ok(results[0].clone())
+    );
+    assert_eq!(
+        Chooser::FailWithList.choose("T'.D.S3 - 50 ", &results[1..]),
+        Ok(results[1].clone())
+    );
+    let callback = Chooser::Callback(Arc::new(|_, results| results.last().cloned()));
+    assert_eq!(callback.choose(query, &results), Ok(results[1].clone()));
+    assert_eq!(
+        Chooser::Callback(Arc::new(|_, _| None)).choose(query, &results),
+        Err(ChoiceError::Cancelled)
+    );
+    let unknown = SearchResult {
+        key: "T'.D.S3 - 51".to_string(),
+        cid: Some(452593),
+    };
+    let returned = unknown.clone();
+    assert_eq!(
+        Chooser::Callback(Arc::new(move |_, _| Some(returned.clone()))).choose(query, &results),
+        Err(ChoiceError::UnknownChoice(unknown))
+    );
+    assert_eq!(
+        Chooser::First.choose(query, &[]),
+        Err(ChoiceError::NoResult)
+    );
+}
+
+#[test]
+fn test_area() {
+    assert!(Area::around(1.4, 43.6, 0.1).contains(1.409197, 43.649276));
+    assert!(!Area::around(1.4, 43.6, 0.1).contains(1.6, 43.6));
+    // Toulouse to Paris
+    assert!((distance((1.4442, 43.6047), (2.3522, 48.8566)) - 588_000.0).abs() < 2_000.0);
+}
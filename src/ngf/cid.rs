@@ -0,0 +1,121 @@
+//! Lookup of benchmarks by their IGN id (the `rn_cid` of the URLs of the fiches), for instance from
+//! a link someone shared. The matricule is read from the HTML fiche of the benchmark, then the
+//! benchmark is fetched as usual
+use super::*;
+
+const FICHE_HTML_URL: &str =
+    "https://geodesie.ign.fr/fiches/index.php?module=e&action=fichehtml&source=gp";
+
+/// The URL of the PDF fiche of the benchmark
+pub fn fiche_url(cid: i64) -> String {
+    format!("https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid={cid}&geo_cid=0")
+}
+
+/// Reads a cid given as a number or as the URL of a fiche. 0 is not a cid: it is what the URLs of
+/// the fiches use for the missing one
+///
+/// # Examples
+/// ```
+/// use geodesie_de_bureau::ngf::cid::cid_from_input;
+/// assert_eq!(cid_from_input("452592"), Ok(452592));
+/// assert_eq!(
+///     cid_from_input("https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=452592&geo_cid=0"),
+///     Ok(452592)
+/// );
+/// ```
+pub fn cid_from_input(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    if let Ok(cid) = input.parse::<u32>() {
+        return match cid {
+            0 => Err("Invalid cid: 0".to_string()),
+            cid => Ok(cid),
+        };
+    }
+    let paramètre = |nom: &str| {
+        input
+            .split(['?', '&', '#'])
+            .filter_map(|paramètre| paramètre.split_once('='))
+            .find(|(clé, _)| *clé == nom)
+            .and_then(|(_, valeur)| valeur.parse::<u32>().ok())
+    };
+    match (paramètre("rn_cid"), paramètre("geo_cid")) {
+        (Some(cid), _) if cid != 0 => Ok(cid),
+        (_, Some(geo_cid)) if geo_cid != 0 => Err(format!(
+            "'{input}' is the fiche of a geodetic point (geo_cid={geo_cid}), not of a benchmark"
+        )),
+        _ => Err(format!(
+            "Invalid cid: '{input}' (expected a number or the URL of a fiche)"
+        )),
+    }
+}
+
+/// Reads the matricule of the benchmark from its HTML fiche (FICHE_HTML_URL), where it follows the
+/// `Matricule` label, in the same cell or in the next one. The tests only use hand-written HTML:
+/// the parsing was not checked against a capture of a real fiche
+fn matricule_from_fiche(html: &str) -> Option<Matricule> {
+    let textes: Vec<String> = html
+        .split(['<', '>'])
+        .enumerate()
+        // Odd parts are the tags
+        .filter(|(index, _)| index % 2 == 0)
+        .map(|(_, texte)| {
+            texte
+                .replace("&#039;", "'")
+                .replace("&#39;", "'")
+                .replace("&quot;", "\"")
+                .replace("&nbsp;", " ")
+                .replace("&amp;", "&")
+                .trim()
+                .to_string()
+        })
+        .filter(|texte| !texte.is_empty())
+        .collect();
+    let index = textes
+        .iter()
+        .position(|texte| texte.to_lowercase().starts_with("matricule"))?;
+    // The colon after the label can be alone between two tags
+    std::iter::once(&textes[index]["matricule".len()..])
+        .chain(textes[index + 1..].iter().map(String::as_str))
+        .map(|texte| texte.trim_start_matches([' ', ':']).trim())
+        .find(|texte| !texte.is_empty())?
+        .parse()
+        .ok()
+}
+
+/// Fetches the benchmark with this cid
+pub fn fetch_rn_by_cid(
+    cid: u32,
+    territoire: Territoire,
+) -> Result<RepèreNivellement, Box<dyn Error>> {
+    let html: String =
+        reqwest::blocking::get(format!("{FICHE_HTML_URL}&rn_cid={cid}&geo_cid=0"))?.text()?;
+    let matricule = matricule_from_fiche(&html).ok_or(format!("No benchmark with cid {cid}"))?;
+    // Among the homonyms, fetch_rn picks the benchmark with the cid
+    fetch_rn(&RNIdentificationInfos { cid, matricule }, territoire)
+}
+
+#[test]
+fn test_cid() {
+    assert_eq!(cid_from_input(" 452592 "), Ok(452592));
+    assert_eq!(cid_from_input(&fiche_url(481679)), Ok(481679));
+    assert!(cid_from_input(
+        "https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=0&geo_cid=1234"
+    )
+    .unwrap_err()
+    .contains("geodetic point"));
+    assert!(cid_from_input("T'.D.S3 - 50").is_err());
+    assert!(cid_from_input("0").is_err());
+    assert!(cid_from_input(&fiche_url(0)).is_err());
+
+    assert_eq!(
+        matricule_from_fiche(
+            "<html><table><tr><td><b>Matricule</b></td><td>T&#039;.D.S3 - 50</td></tr></table></html>"
+        ),
+        Some("T'.D.S3 - 50".parse().unwrap())
+    );
+    assert_eq!(
+        matricule_from_fiche("<p>Matricule : M&quot;.A.K3L3 - 15-I</p>"),
+        Some("M\".A.K3L3 - 15-I".parse().unwrap())
+    );
+    assert_eq!(matricule_from_fiche("<p>Pas de résultat</p>"), None);
+}
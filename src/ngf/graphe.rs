@@ -0,0 +1,429 @@
+//! The neighbourhood graph of benchmarks. Benchmarks are linked when one is described from the
+//! other (du_repère and distance), when they follow each other along the same voie (by PK), and
+//! when they are close to each other
+use super::*;
+use crate::network::distance;
+use petgraph::{
+    algo::astar,
+    graph::{NodeIndex, UnGraph},
+    visit::{EdgeFiltered, EdgeRef},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+/// Why two benchmarks are linked
+#[derive(Clone, PartialEq, Debug)]
+pub enum Relation {
+    /// One of the benchmarks is described from the other (du_repère), `distance` metres away
+    RepèreProche { distance: f64 },
+    /// The benchmarks follow each other along the voie, `écart` metres apart
+    Voie { voie: String, écart: f64 },
+    /// The benchmarks are `distance` metres apart
+    Proximité { distance: f64 },
+}
+
+impl Relation {
+    /// The length of the edge, in metres
+    pub fn longueur(&self) -> f64 {
+        match self {
+            Relation::RepèreProche { distance } => *distance,
+            Relation::Voie { écart, .. } => *écart,
+            Relation::Proximité { distance } => *distance,
+        }
+    }
+
+    /// If the relation follows a levelling line, rather than only geographic proximity
+    pub fn le_long_de_la_ligne(&self) -> bool {
+        !matches!(self, Relation::Proximité { .. })
+    }
+
+    fn type_name(&self) -> &str {
+        match self {
+            Relation::RepèreProche { .. } => "repère proche",
+            Relation::Voie { .. } => "voie",
+            Relation::Proximité { .. } => "proximité",
+        }
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Relation::Voie { voie, écart } => write!(f, "{voie} ({écart:.0}m)"),
+            relation => write!(f, "{} ({:.0}m)", relation.type_name(), relation.longueur()),
+        }
+    }
+}
+
+/// A path between two benchmarks
+#[derive(Clone, PartialEq, Debug)]
+pub struct Chemin<'a> {
+    /// In metres
+    pub longueur: f64,
+    pub repères: Vec<&'a RepèreNivellement>,
+}
+
+/// The graph of a set of benchmarks
+pub struct GrapheRepères {
+    graphe: UnGraph<RepèreNivellement, Relation>,
+    index: HashMap<Matricule, NodeIndex>,
+    /// The benchmarks left out because another benchmark of the graph has their matricule
+    homonymes: Vec<RepèreNivellement>,
+}
+
+/// Parses a number written with a decimal comma, such as the PKs and distances of the API
+fn nombre(valeur: &str) -> Option<f64> {
+    valeur.trim().replace(',', ".").parse::<f64>().ok()
+}
+
+impl GrapheRepères {
+    /// Builds the graph. Benchmarks closer than `proximité_max` metres are linked by a Proximité
+    /// edge (0 for none). If several benchmarks have the same matricule, the first one is kept,
+    /// and the ones with another cid are listed by homonymes
+    pub fn new(repères: Vec<RepèreNivellement>, proximité_max: f64) -> GrapheRepères {
+        let mut graphe: UnGraph<RepèreNivellement, Relation> = UnGraph::new_undirected();
+        let mut index: HashMap<Matricule, NodeIndex> = HashMap::new();
+        let mut homonymes: Vec<RepèreNivellement> = vec![];
+        for repère in repères {
+            match index.get(&repère.matricule) {
+                Some(&node) if graphe[node].cid != repère.cid => homonymes.push(repère),
+                Some(_) => (),
+                None => {
+                    index.insert(
+                        repère.matricule.clone(),
+                        NodeIndex::new(graphe.node_count()),
+                    );
+                    graphe.add_node(repère);
+                }
+            }
+        }
+        let nodes: Vec<NodeIndex> = graphe.node_indices().collect();
+        let coordonnées = |node: NodeIndex| (graphe[node].longitude, graphe[node].latitude);
+
+        let mut edges: Vec<(NodeIndex, NodeIndex, Relation)> = vec![];
+        // du_repère
+        for &node in &nodes {
+            let Some(&proche) = Matricule::from_str(&graphe[node].du_repère)
+                .ok()
+                .and_then(|du_repère| index.get(&du_repère))
+            else {
+                continue;
+            };
+            if proche != node {
+                let distance = match graphe[node].distance.as_deref().and_then(nombre) {
+                    Some(kilomètres) => kilomètres * 1000.0,
+                    None => distance(coordonnées(node), coordonnées(proche)),
+                };
+                edges.push((node, proche, Relation::RepèreProche { distance }));
+            }
+        }
+        // Consecutive PKs along the voies. Voies with the same name are told apart by their ends
+        // and their département
+        type Voie<'a> = (&'a str, Option<&'a str>, Option<&'a str>, &'a str);
+        let mut voies: BTreeMap<Voie, Vec<(f64, NodeIndex)>> = BTreeMap::new();
+        for &node in &nodes {
+            let repère = &graphe[node];
+            if let Some(pk) = repère.voie_pk.as_deref().and_then(nombre) {
+                if !repère.voie_suivie.is_empty() {
+                    voies
+                        .entry((
+                            repère.voie_suivie.as_str(),
+                            repère.voie_de.as_deref(),
+                            repère.voie_vers.as_deref(),
+                            repère.département.as_str(),
+                        ))
+                        .or_default()
+                        .push((pk, node));
+                }
+            }
+        }
+        for ((voie, ..), mut pks) in voies {
+            pks.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in pks.windows(2) {
+                edges.push((
+                    pair[0].1,
+                    pair[1].1,
+                    Relation::Voie {
+                        voie: voie.to_string(),
+                        écart: (pair[1].0 - pair[0].0) * 1000.0,
+                    },
+                ));
+            }
+        }
+        // Geodesic proximity
+        if proximité_max > 0.0 {
+            for (i, &a) in nodes.iter().enumerate() {
+                for &b in &nodes[i + 1..] {
+                    let distance = distance(coordonnées(a), coordonnées(b));
+                    if distance <= proximité_max {
+                        edges.push((a, b, Relation::Proximité { distance }));
+                    }
+                }
+            }
+        }
+        for (a, b, relation) in edges {
+            graphe.add_edge(a, b, relation);
+        }
+        GrapheRepères {
+            graphe,
+            index,
+            homonymes,
+        }
+    }
+
+    /// The benchmarks left out because another benchmark with the same matricule, but another
+    /// cid, is in the graph
+    pub fn homonymes(&self) -> &[RepèreNivellement] {
+        &self.homonymes
+    }
+
+    pub fn repère(&self, matricule: &Matricule) -> Option<&RepèreNivellement> {
+        self.index.get(matricule).map(|&node| &self.graphe[node])
+    }
+
+    pub fn nombre_de_repères(&self) -> usize {
+        self.graphe.node_count()
+    }
+
+    pub fn nombre_de_relations(&self) -> usize {
+        self.graphe.edge_count()
+    }
+
+    /// The benchmarks linked to a benchmark, with the relations
+    pub fn voisins(&self, matricule: &Matricule) -> Vec<(&RepèreNivellement, &Relation)> {
+        let Some(&node) = self.index.get(matricule) else {
+            return vec![];
+        };
+        self.graphe
+            .edges(node)
+            .map(|edge| {
+                let voisin = if edge.source() == node {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                (&self.graphe[voisin], edge.weight())
+            })
+            .collect()
+    }
+
+    /// The shortest path between two benchmarks. If `le_long_de_la_ligne`, only the relations
+    /// following the levelling lines are used
+    pub fn chemin(
+        &self,
+        de: &Matricule,
+        vers: &Matricule,
+        le_long_de_la_ligne: bool,
+    ) -> Option<Chemin<'_>> {
+        let (&de, &vers) = (self.index.get(de)?, self.index.get(vers)?);
+        let filtré = EdgeFiltered::from_fn(&self.graphe, |edge| {
+            !le_long_de_la_ligne || edge.weight().le_long_de_la_ligne()
+        });
+        let (longueur, nodes) = astar(
+            &filtré,
+            de,
+            |node| node == vers,
+            |edge| edge.weight().longueur(),
+            |_| 0.0,
+        )?;
+        Some(Chemin {
+            longueur,
+            repères: nodes.into_iter().map(|node| &self.graphe[node]).collect(),
+        })
+    }
+
+    /// Exports the graph in the Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let échappe = |texte: &str| texte.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("graph repères {\n");
+        for node in self.graphe.node_indices() {
+            let repère = &self.graphe[node];
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\", pos=\"{},{}\"];",
+                node.index(),
+                échappe(repère.matricule.as_str()),
+                repère.longitude,
+                repère.latitude
+            )
+            .unwrap();
+        }
+        for edge in self.graphe.edge_references() {
+            writeln!(
+                dot,
+                "    n{} -- n{} [label=\"{}\", relation=\"{}\", longueur={:.1}{}];",
+                edge.source().index(),
+                edge.target().index(),
+                échappe(&edge.weight().to_string()),
+                edge.weight().type_name(),
+                edge.weight().longueur(),
+                if edge.weight().le_long_de_la_ligne() {
+                    ""
+                } else {
+                    ", style=dashed"
+                }
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the graph in the GraphML format
+    pub fn to_graphml(&self) -> String {
+        let échappe = |texte: &str| {
+            texte
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        let mut graphml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+            \x20   <key id=\"matricule\" for=\"node\" attr.name=\"matricule\" attr.type=\"string\"/>\n\
+            \x20   <key id=\"longitude\" for=\"node\" attr.name=\"longitude\" attr.type=\"double\"/>\n\
+            \x20   <key id=\"latitude\" for=\"node\" attr.name=\"latitude\" attr.type=\"double\"/>\n\
+            \x20   <key id=\"altitude\" for=\"node\" attr.name=\"altitude\" attr.type=\"double\"/>\n\
+            \x20   <key id=\"etat\" for=\"node\" attr.name=\"etat\" attr.type=\"string\"/>\n\
+            \x20   <key id=\"voie\" for=\"node\" attr.name=\"voie\" attr.type=\"string\"/>\n\
+            \x20   <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n\
+            \x20   <key id=\"longueur\" for=\"edge\" attr.name=\"longueur\" attr.type=\"double\"/>\n\
+            \x20   <graph id=\"repères\" edgedefault=\"undirected\">\n",
+        );
+        for node in self.graphe.node_indices() {
+            let repère = &self.graphe[node];
+            writeln!(graphml, "        <node id=\"n{}\">", node.index()).unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"matricule\">{}</data>",
+                échappe(repère.matricule.as_str())
+            )
+            .unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"longitude\">{}</data>",
+                repère.longitude
+            )
+            .unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"latitude\">{}</data>",
+                repère.latitude
+            )
+            .unwrap();
+            if let Some(altitude) = repère.altitude_m() {
+                writeln!(
+                    graphml,
+                    "            <data key=\"altitude\">{altitude}</data>"
+                )
+                .unwrap();
+            }
+            writeln!(
+                graphml,
+                "            <data key=\"etat\">{}</data>",
+                échappe(&repère.état.to_string())
+            )
+            .unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"voie\">{}</data>",
+                échappe(&repère.voie_suivie)
+            )
+            .unwrap();
+            writeln!(graphml, "        </node>").unwrap();
+        }
+        for (i, edge) in self.graphe.edge_references().enumerate() {
+            writeln!(
+                graphml,
+                "        <edge id=\"e{i}\" source=\"n{}\" target=\"n{}\">",
+                edge.source().index(),
+                edge.target().index()
+            )
+            .unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"relation\">{}</data>",
+                échappe(&edge.weight().to_string())
+            )
+            .unwrap();
+            writeln!(
+                graphml,
+                "            <data key=\"longueur\">{:.1}</data>",
+                edge.weight().longueur()
+            )
+            .unwrap();
+            writeln!(graphml, "        </edge>").unwrap();
+        }
+        graphml.push_str("    </graph>\n</graphml>\n");
+        graphml
+    }
+}
+
+#[test]
+fn test_graphe() {
+    let repère = |matricule: &str, longitude: f64, pk: Option<&str>, du_repère: &str| {
+        let mut repère = crate::save::repère_test(matricule);
+        repère.longitude = longitude;
+        repère.voie_pk = pk.map(str::to_string);
+        repère.du_repère = du_repère.to_string();
+        repère.distance = None;
+        repère
+    };
+    let mut repère_d = repère("D", 1.45, None, "C");
+    repère_d.distance = Some("0,5".to_string());
+    // Another voie with the same name, in another département
+    let mut repère_f = repère("F", 1.60, Some("121,5"), "");
+    repère_f.département = "82".to_string();
+    let mut homonyme = repère("B", 1.70, None, "");
+    homonyme.cid += 1;
+    let graphe = GrapheRepères::new(
+        vec![
+            repère("A", 1.40, Some("120,3"), ""),
+            repère("C", 1.42, Some("122,1"), ""),
+            repère("B", 1.41, Some("121"), ""),
+            repère_d,
+            repère("E", 1.4501, None, ""),
+            repère_f,
+            repère("A", 1.40, Some("120,3"), ""),
+            homonyme,
+        ],
+        100.0,
+    );
+    let m = |matricule: &str| Matricule::from_str(matricule).unwrap();
+    assert_eq!(graphe.nombre_de_repères(), 6);
+    assert_eq!(graphe.homonymes().len(), 1);
+    assert!(graphe.voisins(&m("F")).is_empty());
+    // A-B and B-C along the voie, D-C as du_repère, D-E by proximity
+    assert_eq!(graphe.nombre_de_relations(), 4);
+    let chemin = graphe.chemin(&m("A"), &m("D"), true).unwrap();
+    assert_eq!(
+        chemin
+            .repères
+            .iter()
+            .map(|repère| repère.matricule.as_str())
+            .collect::<Vec<_>>(),
+        vec!["A", "B", "C", "D"]
+    );
+    assert!((chemin.longueur - 2300.0).abs() < 0.01);
+    assert!(graphe.chemin(&m("A"), &m("E"), true).is_none());
+    assert_eq!(
+        graphe
+            .chemin(&m("A"), &m("E"), false)
+            .unwrap()
+            .repères
+            .len(),
+        5
+    );
+    assert_eq!(graphe.voisins(&m("D")).len(), 2);
+
+    let dot = graphe.to_dot();
+    assert!(dot.starts_with("graph repères {"));
+    assert_eq!(dot.matches(" -- ").count(), 4);
+    let graphml = graphe.to_graphml();
+    assert_eq!(graphml.matches("<edge ").count(), 4);
+    assert!(graphml.contains("<data key=\"matricule\">D</data>"));
+}
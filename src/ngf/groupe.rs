@@ -0,0 +1,152 @@
+//! Triplets and primordial benchmarks. The benchmarks of a triplet are levelled together, and
+//! most benchmarks depend on a primordial benchmark. As all these benchmarks are close to each
+//! other, they are found in the tiles around the benchmark
+use super::*;
+use crate::network::distance;
+use std::fmt;
+
+/// How far around a benchmark its triplet and primordial benchmark are searched, in degrees
+const GROUPE_RADIUS: f64 = 0.1;
+
+/// A benchmark with its triplet and its primordial benchmark
+#[derive(Clone, PartialEq, Debug)]
+pub struct GroupeRepères {
+    pub repère: RepèreNivellement,
+    /// The other benchmarks of the triplet, by distance
+    pub triplet: Vec<RepèreNivellement>,
+    pub primordial: Option<RepèreNivellement>,
+}
+
+impl GroupeRepères {
+    /// Finds the triplet and the primordial benchmark of `repère` among `candidates`
+    pub fn from_candidates(
+        repère: RepèreNivellement,
+        candidates: Vec<RepèreNivellement>,
+    ) -> GroupeRepères {
+        let mut triplet: Vec<RepèreNivellement> = vec![];
+        let mut primordial: Option<RepèreNivellement> = None;
+        for candidate in candidates {
+            if candidate.cid == repère.cid {
+                continue;
+            }
+            if repère.primordial_cid == Some(candidate.cid) {
+                primordial = Some(candidate.clone());
+            }
+            if repère.triplet_cid.is_some()
+                && candidate.triplet_cid == repère.triplet_cid
+                && triplet.iter().all(|sibling| sibling.cid != candidate.cid)
+            {
+                triplet.push(candidate);
+            }
+        }
+        let groupe = GroupeRepères {
+            repère,
+            triplet: vec![],
+            primordial,
+        };
+        triplet.sort_by(|a, b| groupe.distance(a).total_cmp(&groupe.distance(b)));
+        GroupeRepères { triplet, ..groupe }
+    }
+
+    /// The distance between the benchmark and another one, in metres
+    pub fn distance(&self, other: &RepèreNivellement) -> f64 {
+        distance(
+            (self.repère.longitude, self.repère.latitude),
+            (other.longitude, other.latitude),
+        )
+    }
+
+    /// All the benchmarks of the group, starting with the benchmark itself
+    pub fn repères(&self) -> Vec<&RepèreNivellement> {
+        let mut repères: Vec<&RepèreNivellement> = vec![&self.repère];
+        repères.extend(self.triplet.iter());
+        if let Some(primordial) = &self.primordial {
+            if repères.iter().all(|repère| repère.cid != primordial.cid) {
+                repères.push(primordial);
+            }
+        }
+        repères
+    }
+}
+
+/// Fetches the triplet and the primordial benchmark of `repère`
+pub fn fetch_groupe(repère: RepèreNivellement) -> Result<GroupeRepères, Box<dyn Error>> {
+    if repère.triplet_cid.is_none() && repère.primordial_cid.is_none() {
+        return Ok(GroupeRepères {
+            repère,
+            triplet: vec![],
+            primordial: None,
+        });
+    }
+    let candidates = rn_in_area(
+        &Area::around(repère.longitude, repère.latitude, GROUPE_RADIUS),
+        Territoire::from_département(&repère.département),
+    )?;
+    Ok(GroupeRepères::from_candidates(repère, candidates))
+}
+
+impl fmt::Display for GroupeRepères {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "\x1b[91m=== Triplet ===\x1b[39m")?;
+        if self.triplet.is_empty() {
+            writeln!(f, "Aucun autre repère connu")?;
+        }
+        for sibling in &self.triplet {
+            writeln!(
+                f,
+                "\x1b[94m{}\x1b[39m : {:.0}m ({})",
+                sibling.matricule,
+                self.distance(sibling),
+                sibling.état
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "\x1b[91m=== Repère primordial ===\x1b[39m")?;
+        match (&self.primordial, self.repère.primordial_cid) {
+            (Some(primordial), _) => writeln!(
+                f,
+                "\x1b[94m{}\x1b[39m : {:.0}m ({})",
+                primordial.matricule,
+                self.distance(primordial),
+                primordial.état
+            )?,
+            (None, Some(cid)) => writeln!(f, "Introuvable autour du repère (id {cid})")?,
+            (None, None) => writeln!(f, "Aucun")?,
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_groupe() {
+    let repère = |matricule: &str, cid: i64, longitude: f64, triplet: Option<&str>| {
+        let mut repère = crate::save::repère_test(matricule);
+        repère.cid = cid;
+        repère.longitude = longitude;
+        repère.triplet_cid = triplet.map(str::to_string);
+        repère
+    };
+    let mut repère_50 = repère("T'.D.S3 - 50", 50, 1.409197, Some("T1"));
+    repère_50.primordial_cid = Some(1);
+    let groupe = GroupeRepères::from_candidates(
+        repère_50.clone(),
+        vec![
+            repère("T'.D.S3 - 50", 50, 1.409197, Some("T1")),
+            repère("T'.D.S3 - 52", 52, 1.419197, Some("T1")),
+            repère("T'.D.S3 - 51", 51, 1.410197, Some("T1")),
+            repère("T'.D.S3 - 54", 54, 1.410197, Some("T2")),
+            repère("T'.D.S3 - 0", 1, 1.5, None),
+        ],
+    );
+    assert_eq!(
+        groupe
+            .triplet
+            .iter()
+            .map(|sibling| sibling.matricule.as_str())
+            .collect::<Vec<_>>(),
+        vec!["T'.D.S3 - 51", "T'.D.S3 - 52"]
+    );
+    assert_eq!(groupe.primordial.as_ref().unwrap().cid, 1);
+    assert_eq!(groupe.repères().len(), 4);
+    assert!((groupe.distance(&groupe.triplet[0]) - 80.5).abs() < 1.0);
+}
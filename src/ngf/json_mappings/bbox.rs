@@ -1,7 +1,7 @@
 //! Can serialize / deserialize the output of the « https://geodesie.ign.fr/ripgeo/fr/api/nivrn/bbox/{long}/{lat}/json/ » API call
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct BBox {
@@ -42,6 +42,7 @@ pub struct Properties {
     pub nivf_ref_en_code: NivfRefEnCode,
     pub nivf_rea_code: NivfReaCode,
     pub nivf_ref_lp_code: i64,
+    #[serde(deserialize_with = "HTypeCode::from_api_code")]
     pub h_type_code: HTypeCode,
     pub rn_etat_code: RnÉtatCode,
     pub rn_action_code: RnActionCode,
@@ -216,23 +217,59 @@ impl fmt::Display for RnTypeCode {
     }
 }
 
-#[derive(Serialize_repr, Deserialize_repr, Clone, PartialEq, Debug)]
+/// Only the metropolitan code was seen in the answers of the API. The overseas codes are not
+/// known, so any other code is read as Inconnu instead of failing
+#[derive(Serialize_repr, Clone, PartialEq, Debug, Default)]
 #[repr(u64)]
 pub enum NivfRefEnCode {
+    /// Metropolitan France, including Corsica
+    #[default]
     SystèmeRGF93v1ETRS89ProjectionLAMBERT93 = 702400037010140,
+    Inconnu = 0,
+}
+
+impl<'de> Deserialize<'de> for NivfRefEnCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match u64::deserialize(deserializer)? {
+            702400037010140 => NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93,
+            _ => NivfRefEnCode::Inconnu,
+        })
+    }
 }
 
 impl fmt::Display for NivfRefEnCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Système : RGF93 v1 (ETRS89) - Projection : LAMBERT-93")
+        write!(
+            f,
+            "{}",
+            match self {
+                NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93 =>
+                    "Système : RGF93 v1 (ETRS89) - Projection : LAMBERT-93",
+                NivfRefEnCode::Inconnu => "Système inconnu",
+            }
+        )
     }
 }
 
-#[derive(Serialize_repr, Deserialize_repr, Clone, PartialEq, Debug)]
+/// Only the metropolitan codes (2 and 3) were seen in the answers of the API. The overseas codes
+/// are not known, so any other code is read as Inconnu instead of failing
+#[derive(Serialize_repr, Clone, PartialEq, Debug)]
 #[repr(u8)]
 pub enum NivfReaCode {
     NgfIgn1969 = 2,
+    /// Corsica
     NgfIgn1978 = 3,
+    Inconnu = 0,
+}
+
+impl<'de> Deserialize<'de> for NivfReaCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match u8::deserialize(deserializer)? {
+            2 => NivfReaCode::NgfIgn1969,
+            3 => NivfReaCode::NgfIgn1978,
+            _ => NivfReaCode::Inconnu,
+        })
+    }
 }
 
 impl fmt::Display for NivfReaCode {
@@ -243,20 +280,38 @@ impl fmt::Display for NivfReaCode {
             match self {
                 NivfReaCode::NgfIgn1969 => "NGF-IGN 1969",
                 NivfReaCode::NgfIgn1978 => "NGF-IGN 1978",
+                NivfReaCode::Inconnu => "Système altimétrique inconnu",
             }
         )
     }
 }
 
+/// The saves hold the values below, which they have always been written with. The API uses other
+/// codes too, see from_api_code
 #[derive(Serialize_repr, Deserialize_repr, Clone, PartialEq, Debug)]
 #[repr(u8)]
 pub enum HTypeCode {
-    AltitudeNormale = 2 | 3,
-    AltitudeOrthométrique =
-        10 | 11 | 13 | 14 | 15 | 16 | 17 | 18 | 21 | 23 | 26 | 29 | 35 | 37 | 41 | 44,
+    AltitudeNormale = 3,
+    AltitudeOrthométrique = 63,
     AltitudeProvisoire = 169,
 }
 
+impl HTypeCode {
+    /// Reads a code of the bbox API, where several codes mean the same type of altitude
+    pub fn from_api_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match u8::deserialize(deserializer)? {
+            2 | 3 => Ok(HTypeCode::AltitudeNormale),
+            10 | 11 | 13 | 14 | 15 | 16 | 17 | 18 | 21 | 23 | 26 | 29 | 35 | 37 | 41 | 44 | 63 => {
+                Ok(HTypeCode::AltitudeOrthométrique)
+            }
+            169 => Ok(HTypeCode::AltitudeProvisoire),
+            code => Err(serde::de::Error::custom(format!(
+                "unknown altitude type code: {code}"
+            ))),
+        }
+    }
+}
+
 impl fmt::Display for HTypeCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -308,6 +363,44 @@ impl fmt::Display for RnÉtatCode {
     }
 }
 
+impl FromStr for RnÉtatCode {
+    type Err = String;
+
+    /// Parses a state as displayed, or typed without accents and with dashes
+    ///
+    /// # Examples
+    /// ```
+    /// use geodesie_de_bureau::ngf::json_mappings::bbox::RnÉtatCode;
+    /// assert_eq!("Non retrouvé".parse(), Ok(RnÉtatCode::NonRetrouvé));
+    /// assert_eq!("non-retrouve".parse(), Ok(RnÉtatCode::NonRetrouvé));
+    /// assert_eq!("bon".parse(), Ok(RnÉtatCode::BonÉtat));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalisé: String = s
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'é' | 'è' => 'e',
+                ' ' | '_' => '-',
+                c => c,
+            })
+            .collect();
+        match normalisé.as_str() {
+            "bon" | "bon-etat" => Ok(RnÉtatCode::BonÉtat),
+            "mauvais" | "mauvais-etat" => Ok(RnÉtatCode::MauvaisÉtat),
+            "detruit" => Ok(RnÉtatCode::Détruit),
+            "imprenable" => Ok(RnÉtatCode::Imprenable),
+            "non-retrouve" => Ok(RnÉtatCode::NonRetrouvé),
+            "deplace" | "presume-deplace" => Ok(RnÉtatCode::PresuméDéplacé),
+            "detruit-apres-observation" => Ok(RnÉtatCode::DétruitAprèsObservation),
+            _ => Err(format!(
+                "Unknown state: '{s}' (expected bon, mauvais, detruit, imprenable, non-retrouve, deplace or detruit-apres-observation)"
+            )),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum RnActionCode {
@@ -395,3 +488,60 @@ impl fmt::Display for RnGPSExploitCode {
 //    #[serde(rename = "")]
 //    Empty,
 // }
+
+#[test]
+fn test_h_type_code() {
+    let api_code = |json: &str| {
+        HTypeCode::from_api_code(&mut serde_json::Deserializer::from_str(json)).unwrap()
+    };
+    assert_eq!(api_code("2"), HTypeCode::AltitudeNormale);
+    assert_eq!(api_code("3"), HTypeCode::AltitudeNormale);
+    assert_eq!(api_code("26"), HTypeCode::AltitudeOrthométrique);
+    assert_eq!(api_code("169"), HTypeCode::AltitudeProvisoire);
+    assert!(HTypeCode::from_api_code(&mut serde_json::Deserializer::from_str("12")).is_err());
+    // The saves only accept the values they are written with
+    assert!(serde_json::from_str::<HTypeCode>("2").is_err());
+    assert!(serde_json::from_str::<HTypeCode>("26").is_err());
+
+    // save → load → save does not change anything
+    let mut repère = crate::save::repère_test("T'.D.S3 - 50");
+    for altitude_type in [
+        HTypeCode::AltitudeNormale,
+        HTypeCode::AltitudeOrthométrique,
+        HTypeCode::AltitudeProvisoire,
+    ] {
+        repère.altitude_type = altitude_type;
+        let sauvegarde = serde_json::to_string(&repère).unwrap();
+        let chargé: super::repere::RepèreNivellement = serde_json::from_str(&sauvegarde).unwrap();
+        assert_eq!(chargé, repère);
+        assert_eq!(serde_json::to_string(&chargé).unwrap(), sauvegarde);
+    }
+}
+
+#[test]
+fn test_codes_inconnus() {
+    assert_eq!(
+        serde_json::from_str::<NivfReaCode>("3").unwrap(),
+        NivfReaCode::NgfIgn1978
+    );
+    assert_eq!(
+        serde_json::from_str::<NivfReaCode>("7").unwrap(),
+        NivfReaCode::Inconnu
+    );
+    assert_eq!(
+        serde_json::from_str::<NivfReaCode>("42").unwrap(),
+        NivfReaCode::Inconnu
+    );
+    assert_eq!(
+        serde_json::from_str::<NivfRefEnCode>("702400037010140").unwrap(),
+        NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93
+    );
+    assert_eq!(
+        serde_json::from_str::<NivfRefEnCode>("1").unwrap(),
+        NivfRefEnCode::Inconnu
+    );
+    assert_eq!(
+        serde_json::from_str::<NivfRefEnCode>("702400042010320").unwrap(),
+        NivfRefEnCode::Inconnu
+    );
+}
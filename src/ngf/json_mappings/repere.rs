@@ -1,11 +1,13 @@
 use std::fmt::Display;
 
 use super::bbox::*;
+use crate::common_traits::Identified;
+use crate::ngf::Matricule;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct RepèreNivellement {
-    pub matricule: String,
+    pub matricule: Matricule,
     pub cid: i64,
     pub fiche_url: String,
     pub système_altimétrique: NivfReaCode,
@@ -27,6 +29,9 @@ pub struct RepèreNivellement {
     pub latitude: f64,
     pub e: String,
     pub n: String,
+    /// The reference frame and projection of e and n
+    #[serde(default)]
+    pub référentiel: NivfRefEnCode,
 
     pub département: String,
     pub insee: String,
@@ -49,6 +54,34 @@ pub struct RepèreNivellement {
     pub remarques: String,
     pub exploitabilité_gps: RnGPSExploitCode,
     pub géod_info: String,
+    /// Number of the geodetic site the benchmark belongs to (see geod)
+    #[serde(default)]
+    pub site_géodésique: Option<String>,
+    /// Identifies the triplet of the benchmark, shared by the benchmarks of the triplet
+    #[serde(default)]
+    pub triplet_cid: Option<String>,
+    /// cid of the primordial benchmark the benchmark depends on
+    #[serde(default)]
+    pub primordial_cid: Option<i64>,
+}
+
+impl RepèreNivellement {
+    /// The altitude in metres (the API writes it with a decimal comma)
+    pub fn altitude_m(&self) -> Option<f64> {
+        self.altitude.replace(',', ".").parse::<f64>().ok()
+    }
+}
+
+/// Several benchmarks can have the same matricule (homonyms), so the id is made of the matricule
+/// and the cid: `ngf/(matricule)@(cid)`. The benchmarks whose cid is unknown (0, such as some
+/// imported ones) are identified by their matricule only
+impl Identified for RepèreNivellement {
+    fn object_id(&self) -> String {
+        match self.cid {
+            0 => format!("ngf/{}", self.matricule),
+            cid => format!("ngf/{}@{cid}", self.matricule),
+        }
+    }
 }
 
 impl Display for RepèreNivellement {
@@ -79,6 +112,7 @@ impl Display for RepèreNivellement {
             \x1b[94mLatitude (dms)\x1b[39m : {latitude}\n\
             \n\
             \x1b[91m=== Coordonnées en kilomètres ===\x1b[39m\n\
+            {référentiel}\n\
             \x1b[94mE (km)\x1b[39m : {e}\n\
             \x1b[94mN (km)\x1b[39m : {n}\n\
             \n\
@@ -97,6 +131,7 @@ impl Display for RepèreNivellement {
             \x1b[91m=== Support ===\x1b[39m\n\
             \x1b[94mSupport\x1b[39m : {support}{géod_info}\n\
             {partie_support}\
+            {site_géodésique}\
             \x1b[94mRepèrements\x1b[39m :\n\
             {repèrement_horizontal}\
             {repèrement_vertical}\
@@ -139,6 +174,7 @@ impl Display for RepèreNivellement {
             latitude = self.latitude,
             e = self.e,
             n = self.n,
+            référentiel = self.référentiel,
             département = self.département,
             insee = self.insee,
             commune = self.commune,
@@ -217,14 +253,19 @@ impl Display for RepèreNivellement {
             } else {
                 "".to_string()
             },
+            site_géodésique = if let Some(site_géodésique) = &self.site_géodésique {
+                format!("\x1b[94mSite géodésique\x1b[39m : geod/{site_géodésique}\n")
+            } else {
+                "".to_string()
+            },
             repèrement_horizontal = if let Some(repèrement_horizontal) = &self.repèrement_horizontal
             {
-                if repèrement_horizontal != "" {
+                if !repèrement_horizontal.is_empty() {
                     format!(
                         "{first_chars} \x1b[94mhorizontal\x1b[39m : {repèrement_horizontal}\n",
                         first_chars = if let Some(repèrement_vertical) = &self.repèrement_vertical
                         {
-                            if repèrement_vertical != "" {
+                            if !repèrement_vertical.is_empty() {
                                 "├╴"
                             } else {
                                 "└╴"
@@ -240,7 +281,7 @@ impl Display for RepèreNivellement {
                 "".to_string()
             },
             repèrement_vertical = if let Some(repèrement_vertical) = &self.repèrement_vertical {
-                if repèrement_vertical != "" {
+                if !repèrement_vertical.is_empty() {
                     format!("└╴ \x1b[94mvertical\x1b[39m : {repèrement_vertical}\n")
                 } else {
                     "".to_string()
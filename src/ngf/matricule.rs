@@ -0,0 +1,325 @@
+//! Matricules of the benchmarks, such as `T'.D.S3 - 57 BIS` or `M".A.K3L3 - 15-I`: the
+//! designation of the levelling line, then the number of the benchmark on the line, with an
+//! optional letter, an optional BIS/TER/QUATER suffix and an optional Roman sub-number
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// The suffix of a benchmark placed next to another one
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Suffixe {
+    Bis,
+    Ter,
+    Quater,
+}
+
+impl Suffixe {
+    fn from_word(word: &str) -> Option<Suffixe> {
+        match word {
+            "BIS" => Some(Suffixe::Bis),
+            "TER" => Some(Suffixe::Ter),
+            "QUATER" => Some(Suffixe::Quater),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a Roman number, such as the sub-numbers of the matricules
+fn roman_value(roman: &str) -> Option<u32> {
+    if roman.is_empty() {
+        return None;
+    }
+    let mut value: u32 = 0;
+    let mut previous: u32 = 0;
+    for c in roman.chars().rev() {
+        let digit = match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            _ => return None,
+        };
+        if digit < previous {
+            value = value.checked_sub(digit)?;
+        } else {
+            value += digit;
+            previous = digit;
+        }
+    }
+    Some(value)
+}
+
+/// The matricule of a benchmark. It keeps the normalised text, which is what the IGN API uses,
+/// and the parts parsed from it, which are used to order the matricules naturally (`5`, `5 BIS`,
+/// `50`). Matricules that do not follow the grammar only have a line designation
+#[derive(Clone, Debug)]
+pub struct Matricule {
+    texte: String,
+    ligne: String,
+    numéro: Option<u32>,
+    lettre: String,
+    suffixe: Option<Suffixe>,
+    sous_numéro: Option<u32>,
+}
+
+impl Matricule {
+    /// Normalises the quotes and whitespace of a matricule typed by a user
+    ///
+    /// # Examples
+    /// ```
+    /// use geodesie_de_bureau::ngf::Matricule;
+    /// assert_eq!(Matricule::normalise("  T’.D.S3  -  50 "), "T'.D.S3 - 50");
+    /// assert_eq!(Matricule::normalise("M''.A.K3L3 - 15-I"), "M\".A.K3L3 - 15-I");
+    /// assert_eq!(Matricule::normalise("FM″ - 3-VIII"), "FM\" - 3-VIII");
+    /// ```
+    pub fn normalise(texte: &str) -> String {
+        texte
+            .replace(['’', '‘', '′', '`'], "'")
+            .replace(['″', '“', '”'], "\"")
+            .replace("''", "\"")
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.texte.as_str()
+    }
+
+    /// The designation of the levelling line, for instance `T'.D.S3`
+    pub fn ligne(&self) -> &str {
+        &self.ligne
+    }
+
+    pub fn numéro(&self) -> Option<u32> {
+        self.numéro
+    }
+
+    /// The lowercase letters after the number, as in `102a`
+    pub fn lettre(&self) -> &str {
+        &self.lettre
+    }
+
+    pub fn suffixe(&self) -> Option<Suffixe> {
+        self.suffixe
+    }
+
+    /// The Roman sub-number, as in `15-I`
+    pub fn sous_numéro(&self) -> Option<u32> {
+        self.sous_numéro
+    }
+
+    /// The order of the levelling line (1 to 4), when the designation of the line encodes it:
+    /// one capital letter for the first order, two for the second, a prime (`'`) for the third
+    /// and a double prime (`"`) for the fourth
+    pub fn ordre(&self) -> Option<u8> {
+        let tête = self.ligne.split('.').next().unwrap_or_default();
+        if tête.contains('"') {
+            Some(4)
+        } else if tête.contains('\'') {
+            Some(3)
+        } else if !tête.is_empty() && tête.chars().all(|c| c.is_ascii_uppercase()) {
+            match tête.len() {
+                1 => Some(1),
+                2 => Some(2),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Parses the part after the line designation, such as `57 BIS` or `15-I`
+    fn parse_numéro(numéro: &str) -> Option<(u32, String, Option<Suffixe>, Option<u32>)> {
+        let (principal, sous_numéro) = match numéro.rsplit_once('-') {
+            Some((principal, romain)) => (principal.trim(), Some(roman_value(romain.trim())?)),
+            None => (numéro, None),
+        };
+        let (principal, suffixe) = match principal.rsplit_once(' ') {
+            Some((principal, mot)) => (principal.trim(), Some(Suffixe::from_word(mot)?)),
+            None => (principal, None),
+        };
+        let chiffres: String = principal.chars().take_while(char::is_ascii_digit).collect();
+        let lettre: String = principal[chiffres.len()..].to_string();
+        if chiffres.is_empty() || !lettre.chars().all(|c| c.is_ascii_lowercase()) {
+            return None;
+        }
+        Some((chiffres.parse().ok()?, lettre, suffixe, sous_numéro))
+    }
+}
+
+impl FromStr for Matricule {
+    type Err = String;
+
+    /// Fails only if the matricule is empty or contains a `|`, which the API can not handle
+    ///
+    /// # Examples
+    /// ```
+    /// use geodesie_de_bureau::ngf::{matricule::Suffixe, Matricule};
+    /// let matricule: Matricule = "T'.D.S3 - 57 BIS".parse().unwrap();
+    /// assert_eq!(matricule.ligne(), "T'.D.S3");
+    /// assert_eq!(matricule.numéro(), Some(57));
+    /// assert_eq!(matricule.suffixe(), Some(Suffixe::Bis));
+    /// let matricule: Matricule = "M\".A.K3L3 - 15-I".parse().unwrap();
+    /// assert_eq!(matricule.sous_numéro(), Some(1));
+    /// assert_eq!(matricule.ordre(), Some(4));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let texte = Matricule::normalise(s);
+        if texte.is_empty() || texte.contains('|') {
+            return Err(format!("Invalid matricule: '{s}'"));
+        }
+        let parsed = texte
+            .split_once(" - ")
+            .and_then(|(ligne, numéro)| Some((ligne, Matricule::parse_numéro(numéro)?)));
+        Ok(match parsed {
+            Some((ligne, (numéro, lettre, suffixe, sous_numéro))) => Matricule {
+                ligne: ligne.to_string(),
+                numéro: Some(numéro),
+                lettre,
+                suffixe,
+                sous_numéro,
+                texte,
+            },
+            None => Matricule {
+                ligne: texte.clone(),
+                numéro: None,
+                lettre: "".to_string(),
+                suffixe: None,
+                sous_numéro: None,
+                texte,
+            },
+        })
+    }
+}
+
+impl fmt::Display for Matricule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.texte)
+    }
+}
+
+impl PartialEq for Matricule {
+    fn eq(&self, other: &Self) -> bool {
+        self.texte == other.texte
+    }
+}
+
+impl Eq for Matricule {}
+
+impl Hash for Matricule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.texte.hash(state);
+    }
+}
+
+impl PartialEq<str> for Matricule {
+    fn eq(&self, other: &str) -> bool {
+        self.texte == other
+    }
+}
+
+impl PartialEq<&str> for Matricule {
+    fn eq(&self, other: &&str) -> bool {
+        self.texte == *other
+    }
+}
+
+impl PartialEq<String> for Matricule {
+    fn eq(&self, other: &String) -> bool {
+        &self.texte == other
+    }
+}
+
+impl Ord for Matricule {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ligne
+            .cmp(&other.ligne)
+            .then(self.numéro.cmp(&other.numéro))
+            .then(self.lettre.cmp(&other.lettre))
+            .then(self.suffixe.cmp(&other.suffixe))
+            .then(self.sous_numéro.cmp(&other.sous_numéro))
+            .then(self.texte.cmp(&other.texte))
+    }
+}
+
+impl PartialOrd for Matricule {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Matricules are serialized as their text, as before this type existed
+impl Serialize for Matricule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.texte)
+    }
+}
+
+impl<'de> Deserialize<'de> for Matricule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let texte = String::deserialize(deserializer)?;
+        Matricule::from_str(&texte).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn test_matricule() {
+    let matricule = |texte: &str| Matricule::from_str(texte).unwrap();
+    let mut matricules: Vec<Matricule> = [
+        "T'.D.S3 - 59",
+        "T'.D.S3 - 5 BIS",
+        "T'.D.S3 - 57 BIS",
+        "T'.D.S3 - 50",
+        "T'.D.S3 - 57",
+        "T'.D.S3 - 5",
+        "T'.D.S3 - 102a",
+        "T'.D.S3 - 102",
+        "T'.D.S3 - 5-II",
+        "T'.D.S3 - 5-I",
+    ]
+    .into_iter()
+    .map(matricule)
+    .collect();
+    matricules.sort();
+    assert_eq!(
+        matricules.iter().map(Matricule::as_str).collect::<Vec<_>>(),
+        vec![
+            "T'.D.S3 - 5",
+            "T'.D.S3 - 5-I",
+            "T'.D.S3 - 5-II",
+            "T'.D.S3 - 5 BIS",
+            "T'.D.S3 - 50",
+            "T'.D.S3 - 57",
+            "T'.D.S3 - 57 BIS",
+            "T'.D.S3 - 59",
+            "T'.D.S3 - 102",
+            "T'.D.S3 - 102a",
+        ]
+    );
+
+    let fm = matricule("FM″ - 3-VIII");
+    assert_eq!(fm, "FM\" - 3-VIII");
+    assert_eq!((fm.numéro(), fm.sous_numéro()), (Some(3), Some(8)));
+    assert_eq!(matricule("T'.D.S3 - 102a").lettre(), "a");
+    assert_eq!(matricule("M.AC - 0-VIII").ordre(), Some(1));
+    assert_eq!(matricule("NP - 12").ordre(), Some(2));
+    assert_eq!(matricule("T'.D.S3 - 50").ordre(), Some(3));
+    // Matricules outside of the grammar are kept as they are
+    let autre = matricule("REPERE SANS NUMERO");
+    assert_eq!(
+        (autre.ligne(), autre.numéro()),
+        ("REPERE SANS NUMERO", None)
+    );
+    assert!(Matricule::from_str(" ").is_err());
+    assert!(Matricule::from_str("A|B").is_err());
+    assert_eq!(
+        serde_json::to_string(&matricule("T’.D.S3 - 50")).unwrap(),
+        r#""T'.D.S3 - 50""#
+    );
+}
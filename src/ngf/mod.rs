@@ -1,21 +1,39 @@
+pub mod cid;
+pub mod graphe;
+pub mod groupe;
 pub mod json_mappings;
+pub mod matricule;
+pub mod network;
+pub mod suggestions;
+pub mod territoire;
+use crate::geod::sit_no_from_géod_info;
+use crate::network::{Area, ChoiceError, Chooser, SearchResult};
+pub use cid::fetch_rn_by_cid;
+pub use graphe::GrapheRepères;
+pub use groupe::{fetch_groupe, GroupeRepères};
 pub use json_mappings::{
     bbox::{BBox, Feature, Properties},
     repere::RepèreNivellement,
 };
+pub use matricule::Matricule;
+pub use network::Ngf;
 use reqwest;
-use std::{
-    io::{self, Write},
-    process::exit,
-};
+use std::{collections::BTreeSet, error::Error};
+pub use suggestions::suggest_rn;
+pub use territoire::Territoire;
 
 const SEARCH_RN_URL: &str = "https://geodesie.ign.fr/fiches/index.php?module=e&action=visugeod";
+const BBOX_URL: &str = "https://geodesie.ign.fr/ripgeo/fr/api/nivrn/bbox";
+/// The bbox API returns the benchmarks of a tile of this size, in degrees
+const TILE_SIZE: f64 = 0.1;
+/// How many matricules are suggested when a search finds nothing
+pub const MAX_SUGGESTIONS: usize = 5;
 
 /// This is output from the API call of searching for RNs
 #[derive(Clone, Debug, PartialEq)]
 pub struct RNIdentificationInfos {
     pub cid: u32,
-    pub matricule: String,
+    pub matricule: Matricule,
 }
 
 /// Returns the names and IDs of the benchmarks that contain the provided string
@@ -24,41 +42,45 @@ pub struct RNIdentificationInfos {
 /// ```
 /// use geodesie_de_bureau::ngf::*;
 /// assert_eq!(
-///    rn_from_matricule("T'.D.S3 - 50"),
+///    rn_from_matricule(&"T'.D.S3 - 50".parse().unwrap()),
 ///    vec![RNIdentificationInfos {
 ///        cid: 452592,
-///        matricule: "T'.D.S3 - 50".to_string(),
+///        matricule: "T'.D.S3 - 50".parse().unwrap(),
 ///    }]
 /// );
 /// assert_eq!(
-///     rn_from_matricule("PeuDeChancesQueCeSoitUnRN"),
+///     rn_from_matricule(&"PeuDeChancesQueCeSoitUnRN".parse().unwrap()),
 ///     vec![]
 /// )
 /// ```
-pub fn rn_from_matricule(matricule: &str) -> Vec<RNIdentificationInfos> {
-    assert_ne!(matricule, "");
-    assert!(!matricule.contains("|"));
-    // Let’s own the string, we have a lot to do onto it
-    let mut matricule: String = String::from(matricule.trim());
-    matricule = matricule.replace("’", "'");
+pub fn rn_from_matricule(matricule: &Matricule) -> Vec<RNIdentificationInfos> {
+    search_rn(matricule, Territoire::France).unwrap()
+}
+
+/// Same as rn_from_matricule, but for any territory, and returns an error instead of panicking
+/// when the request fails. The benchmarks are sorted by matricule
+pub fn search_rn(
+    matricule: &Matricule,
+    territoire: Territoire,
+) -> Result<Vec<RNIdentificationInfos>, Box<dyn Error>> {
     let mut headers: reqwest::header::HeaderMap = reqwest::header::HeaderMap::new();
     headers.insert(
         "content-type",
         "application/x-www-form-urlencoded".parse().unwrap(),
     );
     let client: reqwest::blocking::Client = reqwest::blocking::Client::new();
-    let body: String =
-        format!("repere_ajax={matricule}&identifiant_visugeod=identificateur_repere");
+    let body: String = format!(
+        "repere_ajax={matricule}&identifiant_visugeod=identificateur_repere&t={}",
+        territoire.code()
+    );
     let mut resp: String = client
         .post(SEARCH_RN_URL)
         .body(body)
         .headers(headers)
-        .send()
-        .unwrap()
-        .text()
-        .unwrap();
+        .send()?
+        .text()?;
     if resp.contains("Pas de résultat") {
-        return vec![];
+        return Ok(vec![]);
     }
     resp = resp.replace("<ul>", "");
     resp = resp.replace("</ul>", "");
@@ -69,87 +91,37 @@ pub fn rn_from_matricule(matricule: &str) -> Vec<RNIdentificationInfos> {
     resp = resp.trim().to_string();
     let mut result_vec: Vec<RNIdentificationInfos> = vec![];
     for rn in resp.split("\n") {
-        let id_and_name: Vec<&str> = rn.split("\x00").into_iter().collect();
+        let Some((cid, name)) = rn.split_once('\x00') else {
+            return Err(format!("Unexpected search result: '{rn}'").into());
+        };
         result_vec.push(RNIdentificationInfos {
-            cid: id_and_name[0].parse::<u32>().unwrap(),
-            matricule: id_and_name[1].to_string(),
+            cid: cid.parse::<u32>()?,
+            matricule: name.parse()?,
         });
     }
-    result_vec
+    result_vec.sort_by(|a, b| a.matricule.cmp(&b.matricule));
+    Ok(result_vec)
 }
 
-/// Prompts the user to select a repère in the provided repères_found
+/// Selects a repère in the provided repères_found, using `chooser` when several repères match
 pub fn find_matricule_to_use_from_list(
-    matricule_input: &str,
-    repères_found: &Vec<RNIdentificationInfos>,
-) -> RNIdentificationInfos {
-    // If there is only one repère in the list. we return it directly
-    if repères_found.len() == 1 {
-        return repères_found[0].clone();
-    }
-    // If there are no repères in the list. we can call exit(1)
-    if repères_found.len() == 0 {
-        exit(1);
-    }
-    // If there is one repère in the list that matches exactly the provided matricule, we return it directly
-    if let Some(repère) = repères_found
+    matricule_input: &Matricule,
+    repères_found: &[RNIdentificationInfos],
+    chooser: &Chooser,
+) -> Result<RNIdentificationInfos, ChoiceError> {
+    let results: Vec<SearchResult> = repères_found
         .iter()
-        .find(|infos_repère| infos_repère.matricule == matricule_input)
-    {
-        return repère.clone();
-    }
-    let mut string_found_repères: String = String::new();
-    // Get some sizes to align the text (we therefore need to parse the list twice)
-    let mut max_size_indexes: usize = 0;
-    let mut max_size_matricule: usize = 0;
-    let mut max_size_cid: usize = 0;
-    for (index, repère) in repères_found.iter().enumerate() {
-        if index.to_string().len() > max_size_indexes {
-            max_size_indexes = index.to_string().len();
-        };
-        if repère.matricule.to_string().len() > max_size_matricule {
-            max_size_matricule = repère.matricule.to_string().len();
-        };
-        if repère.cid.to_string().len() > max_size_cid {
-            max_size_cid = repère.cid.to_string().len();
-        };
-    }
-    for (index, repère) in repères_found.iter().enumerate() {
-        string_found_repères += format!(
-            "\x1b[92;1m{index:<2$}\x1b[39;22m : \x1b[94;1m{:<3$}\x1b[39;22m (id \x1b[95;1m{:>4$}\x1b[39;22m)\n",
-            repère.matricule,
-            repère.cid,
-            max_size_indexes,
-            max_size_matricule,
-            max_size_cid
-        )
-        .as_str();
-    }
-    println!(
-        "\
-    Repères found :\n\
-    {string_found_repères}\
-    "
-    );
-    loop {
-        print!(
-            "Your choice (\x1b[92;1m0\x1b[22m-\x1b[1m{number_of_repères}\x1b[39;22m) : ",
-            number_of_repères = repères_found.len() - 1
-        );
-        let mut input: String = String::new();
-        io::stdout().flush().unwrap();
-        io::stdin().read_line(&mut input).unwrap();
-        input = input.trim().to_string();
-        let choice: i16 = input.parse().unwrap_or(-1);
-        let repères_found_len_i16: i16 = repères_found.len() as i16;
-        if (0..repères_found_len_i16).contains(&choice) {
-            break repères_found[choice as usize].clone();
-        };
-        println!(
-            "Please enter a valid choice from \x1b[92;1m0\x1b[22m to \x1b[1m{number_of_repères}\x1b[39;22m",
-            number_of_repères = repères_found.len() - 1
-        );
-    }
+        .map(|repère| SearchResult {
+            key: repère.matricule.to_string(),
+            cid: Some(repère.cid),
+        })
+        .collect();
+    let choice = chooser.choose(matricule_input.as_str(), &results)?;
+    repères_found
+        .iter()
+        .find(|repère| repère.matricule == choice.key && Some(repère.cid) == choice.cid)
+        .cloned()
+        .ok_or(ChoiceError::UnknownChoice(choice))
 }
 
 /// Takes the identification of a RN as parameter and returns a RepèreNivellement. As simple as that !
@@ -165,12 +137,12 @@ pub fn find_matricule_to_use_from_list(
 /// assert_eq!(
 ///     get_rn_from_rn_identifications_infos(
 ///         RNIdentificationInfos{
-///             matricule: "T'.D.S3 - 50".to_string(),
+///             matricule: "T'.D.S3 - 50".parse().unwrap(),
 ///             cid: 452592
 ///         }
 ///     ),
 ///     RepèreNivellement {
-///         matricule: "T'.D.S3 - 50".to_string(),
+///         matricule: "T'.D.S3 - 50".parse().unwrap(),
 ///         cid: 452592,
 ///         fiche_url: "https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=452592&geo_cid=0".to_string(),
 ///         système_altimétrique: NivfReaCode::NgfIgn1969,
@@ -189,6 +161,7 @@ pub fn find_matricule_to_use_from_list(
 ///         latitude: 43.649276,
 ///         e: "571.63".to_string(),
 ///         n: "6284.65".to_string(),
+///         référentiel: NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93,
 ///         département: "31".to_string(),
 ///         insee: "31555".to_string(),
 ///         commune: "Toulouse".to_string(),
@@ -207,69 +180,156 @@ pub fn find_matricule_to_use_from_list(
 ///         hors_ign: "100063".to_string(),
 ///         remarques: "".to_string(),
 ///         exploitabilité_gps: RnGPSExploitCode::Empty,
-///         géod_info: "".to_string()
+///         géod_info: "".to_string(),
+///         site_géodésique: None,
+///         triplet_cid: None,
+///         primordial_cid: None,
 ///     }
 /// )
 /// ```
 pub fn get_rn_from_rn_identifications_infos(
     rn_id_infos: RNIdentificationInfos,
 ) -> RepèreNivellement {
-    let matricule_with_double_primes: String = rn_id_infos.matricule.replace("'", "''");
-    let body: String = format!("h_recherche=repere|{matricule_with_double_primes}&t=france");
+    fetch_rn(&rn_id_infos, Territoire::France).unwrap()
+}
+
+/// Same as get_rn_from_rn_identifications_infos, but for any territory, and returns an error
+/// instead of panicking
+pub fn fetch_rn(
+    rn_id_infos: &RNIdentificationInfos,
+    territoire: Territoire,
+) -> Result<RepèreNivellement, Box<dyn Error>> {
+    let matricule_with_double_primes: String = rn_id_infos.matricule.as_str().replace("'", "''");
+    let body: String = format!(
+        "h_recherche=repere|{matricule_with_double_primes}&t={}",
+        territoire.code()
+    );
     let mut headers: reqwest::header::HeaderMap = reqwest::header::HeaderMap::new();
     headers.insert(
         "content-type",
         "application/x-www-form-urlencoded".parse().unwrap(),
     );
-    let resp = reqwest::blocking::Client::new()
-        .post(format!(
-            "https://geodesie.ign.fr/ripgeo/fr/api/nivrn/bbox/{}/json/",
-            reqwest::blocking::Client::new()
-                .post(SEARCH_RN_URL)
-                .body(body)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap()
-                .split("\n")
-                .into_iter()
-                .collect::<Vec<&str>>()[0]
-                .split("|")
-                .into_iter()
-                .collect::<Vec<&str>>()[0]
-                .split(" ")
+    let resp: String = reqwest::blocking::Client::new()
+        .post(SEARCH_RN_URL)
+        .body(body)
+        .headers(headers)
+        .send()?
+        .text()?;
+    // The lines start with the coordinates of the benchmarks with this matricule, there are
+    // several of them for homonyms: the benchmark is the one with the cid
+    let mut tiles: Vec<(i64, i64)> = vec![];
+    for line in resp.lines() {
+        let coordinates: Result<Vec<f64>, _> = line
+            .split('|')
+            .next()
+            .unwrap_or_default()
+            .split(' ')
+            .map(|coord| coord.parse::<f64>())
+            .collect();
+        if let Ok([longitude, latitude, ..]) = coordinates.as_deref() {
+            let tile = (tile_index(*longitude), tile_index(*latitude));
+            if !tiles.contains(&tile) {
+                tiles.push(tile);
+            }
+        }
+    }
+    if tiles.is_empty() {
+        return Err(format!("No coordinates found for '{}'", rn_id_infos.matricule).into());
+    }
+    for (x, y) in tiles {
+        if let Some(feature) = fetch_tile(x, y, territoire)?
+            .features
+            .into_iter()
+            .find(|feature| feature.properties.rn_cid == rn_id_infos.cid as i64)
+        {
+            return repère_from_feature(feature);
+        }
+    }
+    Err(format!(
+        "'{}' (cid {}) was not found around its coordinates",
+        rn_id_infos.matricule, rn_id_infos.cid
+    )
+    .into())
+}
+
+/// Fetches all the benchmarks of the territory in the area
+pub fn rn_in_area(
+    area: &Area,
+    territoire: Territoire,
+) -> Result<Vec<RepèreNivellement>, Box<dyn Error>> {
+    rn_in_areas(std::slice::from_ref(area), territoire)
+}
+
+/// Fetches all the benchmarks of the territory in the areas. The tiles covered by several areas
+/// are only fetched once
+pub fn rn_in_areas(
+    areas: &[Area],
+    territoire: Territoire,
+) -> Result<Vec<RepèreNivellement>, Box<dyn Error>> {
+    let mut repères: Vec<RepèreNivellement> = vec![];
+    for (x, y) in tiles(areas) {
+        repères.extend(
+            fetch_tile(x, y, territoire)?
+                .features
                 .into_iter()
-                .map(|coord| {
-                    format!(
-                        "{:.1}",
-                        (coord.parse::<f64>().unwrap() * 10f64).floor() / 10f64
-                    )
+                .filter(|feature| {
+                    areas.iter().any(|area| {
+                        area.contains(
+                            feature.geometry.coordinates[0],
+                            feature.geometry.coordinates[1],
+                        )
+                    })
                 })
-                .collect::<Vec<String>>()
-                .join("/")
+                .map(repère_from_feature)
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+    }
+    Ok(repères)
+}
+
+/// The tiles (x, y) covering the areas, see tile_index
+fn tiles(areas: &[Area]) -> BTreeSet<(i64, i64)> {
+    let mut tiles: BTreeSet<(i64, i64)> = BTreeSet::new();
+    for area in areas {
+        for x in tile_index(area.longitude_min)..=tile_index(area.longitude_max) {
+            for y in tile_index(area.latitude_min)..=tile_index(area.latitude_max) {
+                tiles.insert((x, y));
+            }
+        }
+    }
+    tiles
+}
+
+/// The index of the tile containing this coordinate
+fn tile_index(coordinate: f64) -> i64 {
+    (coordinate / TILE_SIZE).floor() as i64
+}
+
+/// Fetches the benchmarks of the territory in the tile (x, y), see tile_index. The API has no
+/// territory parameter: the benchmarks are filtered by département
+fn fetch_tile(x: i64, y: i64, territoire: Territoire) -> Result<BBox, Box<dyn Error>> {
+    let resp: String = reqwest::blocking::Client::new()
+        .post(format!(
+            "{BBOX_URL}/{:.1}/{:.1}/json/",
+            x as f64 * TILE_SIZE,
+            y as f64 * TILE_SIZE
         ))
-        .send()
-        .unwrap()
-        .text()
-        .unwrap();
+        .send()?
+        .text()?;
     // To debug issues related to deserialization (this deserialization fails very often)
-    if let Err(wrong_col) = serde_json::from_str::<BBox>(resp.as_str()) {
-        let wrong_col = wrong_col.column();
-        println!(
-            "{}\x1b[91;1m{}\x1b[0m{}",
-            resp[wrong_col - 100..wrong_col].to_string(),
-            resp[wrong_col..=wrong_col].to_string(),
-            resp[wrong_col + 1..wrong_col + 100].to_string()
-        );
-    };
-    let bbox_data: BBox = serde_json::from_str::<BBox>(resp.as_str()).unwrap();
-    let rn: Feature = bbox_data
-        .features
-        .iter()
-        .find(|feature| feature.properties.rn_nom == rn_id_infos.matricule)
-        .unwrap()
-        .clone();
+    let mut bbox = serde_json::from_str::<BBox>(resp.as_str()).map_err(|err| {
+        let wrong_col = err.column().min(resp.len().saturating_sub(1));
+        let start = resp.floor_char_boundary(wrong_col.saturating_sub(100));
+        let end = resp.ceil_char_boundary((wrong_col + 100).min(resp.len()));
+        format!("{err}: {}", &resp[start..end])
+    })?;
+    bbox.features.retain(|feature| {
+        Territoire::from_département(&feature.properties.departement_code) == territoire
+    });
+    Ok(bbox)
+}
+
+fn repère_from_feature(rn: Feature) -> Result<RepèreNivellement, Box<dyn Error>> {
     let prop: Properties = rn.properties;
     // type_complément_avec_canex
     let type_complement = prop.rn_type_compl.clone().unwrap_or("".to_string());
@@ -284,10 +344,10 @@ pub fn get_rn_from_rn_identifications_infos(
     } else {
         type_complément_avec_canex = type_complement.to_string() + ", " + canex_info.as_str()
     }
-    RepèreNivellement {
-        matricule: prop.rn_nom,
+    Ok(RepèreNivellement {
+        matricule: prop.rn_nom.parse()?,
         cid: prop.rn_cid,
-        fiche_url: format!("https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid={cid}&geo_cid=0", cid= prop.rn_cid),
+        fiche_url: cid::fiche_url(prop.rn_cid),
         système_altimétrique: prop.nivf_rea_code,
         altitude: prop.altitude,
         altitude_complémentaire: prop.altitude_complementaire,
@@ -304,6 +364,7 @@ pub fn get_rn_from_rn_identifications_infos(
         latitude: rn.geometry.coordinates[1],
         e: prop.e,
         n: prop.n,
+        référentiel: prop.nivf_ref_en_code,
         département: prop.departement_code,
         insee: prop.insee,
         commune: prop.commune_nom,
@@ -322,8 +383,16 @@ pub fn get_rn_from_rn_identifications_infos(
         hors_ign: prop.hors_ign,
         remarques: prop.remarque,
         exploitabilité_gps: prop.rn_gps_eploit_code,
-        géod_info: prop.geod_info
-    }
+        site_géodésique: prop
+            .sit_no
+            .filter(|sit_no| !sit_no.is_empty())
+            .or_else(|| sit_no_from_géod_info(&prop.geod_info)),
+        géod_info: prop.geod_info,
+        triplet_cid: prop
+            .triplet_cid
+            .filter(|triplet_cid| !triplet_cid.is_empty()),
+        primordial_cid: prop.rn_primordial_cid.filter(|cid| *cid != 0),
+    })
 }
 
 #[test]
@@ -337,7 +406,7 @@ fn tests_rn_from_matricule() {
         "FM\" - 3-VIII",
     ] {
         assert_eq!(
-            rn_from_matricule(repère),
+            rn_from_matricule(&repère.parse().unwrap()),
             vec![RNIdentificationInfos {
                 cid: match repère {
                     "M.AC - 0-VIII" => 303869,
@@ -350,61 +419,101 @@ fn tests_rn_from_matricule() {
                         "The provided repère name has no associated value in the match expression"
                     ),
                 },
-                matricule: repère.to_string(),
+                matricule: repère.parse().unwrap(),
             }]
         );
     }
     assert_eq!(
-        rn_from_matricule("T'.D.S3 - 5"),
+        rn_from_matricule(&"T'.D.S3 - 5".parse().unwrap()),
         vec![
+            RNIdentificationInfos {
+                cid: 481574,
+                matricule: "T'.D.S3 - 5 BIS".parse().unwrap(),
+            },
             RNIdentificationInfos {
                 cid: 452592,
-                matricule: "T'.D.S3 - 50".to_string(),
+                matricule: "T'.D.S3 - 50".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 429495,
-                matricule: "T'.D.S3 - 52".to_string(),
+                matricule: "T'.D.S3 - 52".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 108049,
-                matricule: "T'.D.S3 - 54".to_string(),
+                matricule: "T'.D.S3 - 54".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 108050,
-                matricule: "T'.D.S3 - 55".to_string(),
+                matricule: "T'.D.S3 - 55".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 452593,
-                matricule: "T'.D.S3 - 56".to_string(),
+                matricule: "T'.D.S3 - 56".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 338593,
-                matricule: "T'.D.S3 - 57 BIS".to_string(),
+                matricule: "T'.D.S3 - 57 BIS".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 429496,
-                matricule: "T'.D.S3 - 58".to_string(),
+                matricule: "T'.D.S3 - 58".parse().unwrap(),
             },
             RNIdentificationInfos {
                 cid: 521727,
-                matricule: "T'.D.S3 - 59".to_string(),
-            },
-            RNIdentificationInfos {
-                cid: 481574,
-                matricule: "T'.D.S3 - 5 BIS".to_string(),
+                matricule: "T'.D.S3 - 59".parse().unwrap(),
             },
         ]
     );
 }
+#[test]
+fn test_tiles() {
+    // Two survey points of Toulouse and one of Paris: the tiles in between are not fetched
+    let areas = [
+        Area::around(1.4092, 43.6493, 0.001),
+        Area::around(1.4421, 43.6047, 0.001),
+        Area::around(2.3488, 48.8534, 0.001),
+    ];
+    assert_eq!(
+        tiles(&areas).into_iter().collect::<Vec<_>>(),
+        vec![(14, 436), (23, 488)]
+    );
+    // Across the edge of a tile
+    assert_eq!(tiles(&[Area::around(1.4, 43.65, 0.001)]).len(), 2);
+}
+
+#[test]
+fn test_find_matricule_to_use_from_list() {
+    let repères = [
+        RNIdentificationInfos {
+            cid: 481574,
+            matricule: "T'.D.S3 - 5 BIS".parse().unwrap(),
+        },
+        RNIdentificationInfos {
+            cid: 452592,
+            matricule: "T'.D.S3 - 50".parse().unwrap(),
+        },
+    ];
+    let chooser = Chooser::Callback(std::sync::Arc::new(|_, _| {
+        Some(SearchResult {
+            key: "T'.D.S3 - 51".to_string(),
+            cid: Some(452593),
+        })
+    }));
+    assert!(matches!(
+        find_matricule_to_use_from_list(&"T'.D.S3".parse().unwrap(), &repères, &chooser),
+        Err(ChoiceError::UnknownChoice(_))
+    ));
+}
+
 #[test]
 fn test_get_rn_from_rn_identifications_infos() {
     assert_eq!(get_rn_from_rn_identifications_infos(
         RNIdentificationInfos {
             cid: 452592,
-            matricule: "T'.D.S3 - 50".to_string(),
+            matricule: "T'.D.S3 - 50".parse().unwrap(),
         }
     ), RepèreNivellement {
-        matricule: "T'.D.S3 - 50".to_string(),
+        matricule: "T'.D.S3 - 50".parse().unwrap(),
         cid: 452592,
         fiche_url: "https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=452592&geo_cid=0".to_string(),
         système_altimétrique: json_mappings::bbox::NivfReaCode::NgfIgn1969,
@@ -423,6 +532,7 @@ fn test_get_rn_from_rn_identifications_infos() {
         latitude: 43.649276,
         e: "571.63".to_string(),
         n: "6284.65".to_string(),
+        référentiel: json_mappings::bbox::NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93,
         département: "31".to_string(),
         insee: "31555".to_string(),
         commune: "Toulouse".to_string(),
@@ -441,6 +551,9 @@ fn test_get_rn_from_rn_identifications_infos() {
         hors_ign: "100063".to_string(),
         remarques: "".to_string(),
         exploitabilité_gps: json_mappings::bbox::RnGPSExploitCode::Empty,
-        géod_info: "".to_string()
+        géod_info: "".to_string(),
+        site_géodésique: None,
+        triplet_cid: None,
+        primordial_cid: None,
     })
 }
@@ -0,0 +1,79 @@
+//! The NGF levelling network, as a Network
+use super::*;
+use crate::{
+    network::{Area, Network, SearchResult},
+    save::Objet,
+};
+use std::error::Error;
+
+/// The benchmarks of the levelling network of a territory, fetched from the IGN API
+#[derive(Default)]
+pub struct Ngf {
+    pub territoire: Territoire,
+}
+
+impl Ngf {
+    /// Fetches the benchmark with this IGN id (see cid::cid_from_input to read it from a fiche URL)
+    pub fn fetch_by_cid(&self, cid: u32) -> Result<Objet, Box<dyn Error>> {
+        Ok(Objet::Ngf(Box::new(fetch_rn_by_cid(cid, self.territoire)?)))
+    }
+}
+
+impl Network for Ngf {
+    fn name(&self) -> &str {
+        "ngf"
+    }
+
+    fn description(&self) -> &str {
+        "Repères du nivellement général de la France"
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+        Ok(search_rn(&query.parse()?, self.territoire)?
+            .into_iter()
+            .map(|infos| SearchResult {
+                key: infos.matricule.to_string(),
+                cid: Some(infos.cid),
+            })
+            .collect())
+    }
+
+    fn suggest(&self, query: &str) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+        Ok(suggest_rn(query, MAX_SUGGESTIONS, self.territoire)?
+            .into_iter()
+            .map(|infos| SearchResult {
+                key: infos.matricule.to_string(),
+                cid: Some(infos.cid),
+            })
+            .collect())
+    }
+
+    /// The key is a matricule, or the `(matricule)@(cid)` of the ids of the benchmarks
+    fn fetch(&self, key: &str) -> Result<Objet, Box<dyn Error>> {
+        if let Some((matricule, cid)) = key.rsplit_once('@') {
+            let infos = RNIdentificationInfos {
+                cid: cid.parse()?,
+                matricule: matricule.parse()?,
+            };
+            return Ok(Objet::Ngf(Box::new(fetch_rn(&infos, self.territoire)?)));
+        }
+        let matricule: Matricule = key.parse()?;
+        let infos: RNIdentificationInfos = search_rn(&matricule, self.territoire)?
+            .into_iter()
+            .find(|infos| infos.matricule == matricule)
+            .ok_or(format!("No benchmark with matricule '{key}'"))?;
+        Ok(Objet::Ngf(Box::new(fetch_rn(&infos, self.territoire)?)))
+    }
+
+    fn fetch_area(&self, area: &Area) -> Result<Vec<Objet>, Box<dyn Error>> {
+        self.fetch_areas(std::slice::from_ref(area))
+    }
+
+    /// The tiles shared by the areas are only fetched once
+    fn fetch_areas(&self, areas: &[Area]) -> Result<Vec<Objet>, Box<dyn Error>> {
+        Ok(rn_in_areas(areas, self.territoire)?
+            .into_iter()
+            .map(|repère| Objet::Ngf(Box::new(repère)))
+            .collect())
+    }
+}
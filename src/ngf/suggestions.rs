@@ -0,0 +1,146 @@
+//! Suggestions of matricules when a search finds nothing. Most failed searches come from a wrong
+//! prime, a missing space around the dash or a typo, so the search is retried with variants of the
+//! matricule, then with shorter prefixes, and the benchmarks found are ranked by edit distance
+use super::*;
+use strsim::levenshtein;
+
+/// Variants of a matricule typed by a user, most likely first. The query itself is not included
+///
+/// # Examples
+/// ```
+/// use geodesie_de_bureau::ngf::suggestions::variantes;
+/// assert_eq!(variantes("t'.d.s3-50"), vec!["t'.d.s3 - 50", "T'.D.S3 - 50", "T\".D.S3 - 50"]);
+/// ```
+pub fn variantes(query: &str) -> Vec<String> {
+    let normalisé = Matricule::normalise(query);
+    // Spaces around the dash between the line and the number, but not before a Roman sub-number
+    let tiret = match normalisé.split_once(" - ") {
+        Some(_) => normalisé.clone(),
+        None => match normalisé.split_once('-') {
+            Some((ligne, numéro)) => format!("{} - {}", ligne.trim(), numéro.trim()),
+            None => normalisé.clone(),
+        },
+    };
+    // Only the line is uppercased, as the letter after the number is lowercase (`102a`)
+    let majuscules = match tiret.split_once(" - ") {
+        Some((ligne, numéro)) => format!("{} - {numéro}", ligne.to_uppercase()),
+        None => tiret.to_uppercase(),
+    };
+    let primes = majuscules
+        .chars()
+        .map(|c| match c {
+            '\'' => '"',
+            '"' => '\'',
+            c => c,
+        })
+        .collect::<String>();
+    let mut variantes: Vec<String> = vec![];
+    for variante in [normalisé, tiret, majuscules, primes] {
+        if variante != query && !variante.is_empty() && !variantes.contains(&variante) {
+            variantes.push(variante);
+        }
+    }
+    variantes
+}
+
+/// Shorter prefixes of a matricule, longest first: the line, then the line without its last
+/// segments (`T'.D.S3 - 50` gives `T'.D.S3` then `T'.D`)
+///
+/// # Examples
+/// ```
+/// use geodesie_de_bureau::ngf::suggestions::préfixes;
+/// assert_eq!(préfixes("T'.D.S3 - 50"), vec!["T'.D.S3", "T'.D"]);
+/// ```
+pub fn préfixes(query: &str) -> Vec<String> {
+    let normalisé = Matricule::normalise(query);
+    let mut ligne = match normalisé.split_once('-') {
+        Some((ligne, _)) => ligne.trim(),
+        None => normalisé.as_str(),
+    };
+    let mut préfixes: Vec<String> = vec![];
+    if !ligne.is_empty() && ligne != normalisé {
+        préfixes.push(ligne.to_string());
+    }
+    // The first segment alone (`T'`) would match far too many benchmarks
+    while let Some((tête, _)) = ligne.rsplit_once('.') {
+        if !tête.contains('.') {
+            break;
+        }
+        préfixes.push(tête.to_string());
+        ligne = tête;
+    }
+    préfixes
+}
+
+/// Sorts the candidates by edit distance to the query, then by matricule, without duplicates
+pub fn classe_suggestions(
+    query: &str,
+    mut candidates: Vec<RNIdentificationInfos>,
+) -> Vec<RNIdentificationInfos> {
+    let query = Matricule::normalise(query);
+    candidates.sort_by(|a, b| {
+        levenshtein(&query, a.matricule.as_str())
+            .cmp(&levenshtein(&query, b.matricule.as_str()))
+            .then(a.matricule.cmp(&b.matricule))
+    });
+    candidates.dedup_by(|a, b| a.cid == b.cid);
+    candidates
+}
+
+/// Searches the benchmarks close to a matricule that was not found, and returns the `max` closest
+/// ones. The variants of the matricule are tried first, then its prefixes
+pub fn suggest_rn(
+    query: &str,
+    max: usize,
+    territoire: Territoire,
+) -> Result<Vec<RNIdentificationInfos>, Box<dyn Error>> {
+    let mut candidates: Vec<RNIdentificationInfos> = vec![];
+    for variante in variantes(query) {
+        candidates.extend(search_rn(&variante.parse()?, territoire)?);
+    }
+    if candidates.is_empty() {
+        for préfixe in préfixes(query) {
+            candidates.extend(search_rn(&préfixe.parse()?, territoire)?);
+            if !candidates.is_empty() {
+                break;
+            }
+        }
+    }
+    let mut suggestions = classe_suggestions(query, candidates);
+    suggestions.truncate(max);
+    Ok(suggestions)
+}
+
+#[test]
+fn test_suggestions() {
+    assert_eq!(
+        variantes("T’.D.S3 -50"),
+        vec!["T'.D.S3 -50", "T'.D.S3 - 50", "T\".D.S3 - 50"]
+    );
+    assert!(variantes("M.AC - 0-VIII").is_empty());
+    assert_eq!(variantes("t.d - 102a"), vec!["T.D - 102a"]);
+    assert_eq!(préfixes("M\".A.K3L3 - 15-I"), vec!["M\".A.K3L3", "M\".A"]);
+    assert_eq!(préfixes("NP"), Vec::<String>::new());
+
+    let infos = |matricule: &str, cid: u32| RNIdentificationInfos {
+        cid,
+        matricule: matricule.parse().unwrap(),
+    };
+    let suggestions = classe_suggestions(
+        "T'.D.S3 - 05",
+        vec![
+            infos("T'.D.S3 - 50", 50),
+            infos("T'.D.S3 - 5", 5),
+            infos("T'.D.S3 - 102", 102),
+            infos("T'.D.S3 - 5", 5),
+            infos("T'.D.S3 - 5 BIS", 6),
+        ],
+    );
+    assert_eq!(
+        suggestions
+            .iter()
+            .map(|infos| infos.cid)
+            .collect::<Vec<_>>(),
+        vec![5, 50, 102, 6]
+    );
+}
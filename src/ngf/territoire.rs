@@ -0,0 +1,98 @@
+//! The territories covered by the levelling network of the IGN. Each territory has its own
+//! altitude system, reference frame and projection, only the metropolitan ones are known
+use super::json_mappings::bbox::NivfRefEnCode;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Territoire {
+    /// Metropolitan France, including Corsica
+    #[default]
+    France,
+    Guadeloupe,
+    Martinique,
+    Guyane,
+    Réunion,
+    Mayotte,
+}
+
+pub const TERRITOIRES: [Territoire; 6] = [
+    Territoire::France,
+    Territoire::Guadeloupe,
+    Territoire::Martinique,
+    Territoire::Guyane,
+    Territoire::Réunion,
+    Territoire::Mayotte,
+];
+
+impl Territoire {
+    /// The value of the `t` parameter of the search API
+    pub fn code(&self) -> &'static str {
+        match self {
+            Territoire::France => "france",
+            Territoire::Guadeloupe => "guadeloupe",
+            Territoire::Martinique => "martinique",
+            Territoire::Guyane => "guyane",
+            Territoire::Réunion => "reunion",
+            Territoire::Mayotte => "mayotte",
+        }
+    }
+
+    /// The territory of a département (Corsica, 2A and 2B, is part of France)
+    pub fn from_département(département: &str) -> Territoire {
+        match département {
+            "971" => Territoire::Guadeloupe,
+            "972" => Territoire::Martinique,
+            "973" => Territoire::Guyane,
+            "974" => Territoire::Réunion,
+            "976" => Territoire::Mayotte,
+            _ => Territoire::France,
+        }
+    }
+
+    /// The reference frame and projection of the E and N coordinates. The codes of the overseas
+    /// territories are not known
+    pub fn référentiel(&self) -> NivfRefEnCode {
+        match self {
+            Territoire::France => NivfRefEnCode::SystèmeRGF93v1ETRS89ProjectionLAMBERT93,
+            _ => NivfRefEnCode::Inconnu,
+        }
+    }
+}
+
+impl fmt::Display for Territoire {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Territoire {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase().replace('é', "e");
+        match s.as_str() {
+            "corse" => Ok(Territoire::France),
+            _ => TERRITOIRES
+                .into_iter()
+                .find(|territoire| territoire.code() == s)
+                .ok_or(format!(
+                    "Unknown territory: '{s}' (expected one of {})",
+                    TERRITOIRES.map(|territoire| territoire.code()).join(", ")
+                )),
+        }
+    }
+}
+
+#[test]
+fn test_territoire() {
+    assert_eq!(Territoire::from_str("Réunion"), Ok(Territoire::Réunion));
+    assert_eq!(Territoire::from_str("corse"), Ok(Territoire::France));
+    assert!(Territoire::from_str("Groenland").is_err());
+    assert_eq!(Territoire::from_département("2A"), Territoire::France);
+    assert_eq!(
+        Territoire::from_département("971").référentiel(),
+        NivfRefEnCode::Inconnu
+    );
+}
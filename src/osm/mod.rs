@@ -0,0 +1,332 @@
+//! Cross-check of the NGF benchmarks against the survey points of OpenStreetMap
+//! (`man_made=survey_point`), read from a local extract. The benchmarks are matched to the
+//! survey points by their `ref` tag and by distance, and the missing ones can be exported as an
+//! osmChange file or as a task list for the mappers
+pub mod pbf;
+pub mod xml;
+
+use crate::export::échappe_xml;
+use crate::network::distance;
+use crate::ngf::{Matricule, RepèreNivellement};
+use std::{
+    collections::BTreeMap, error::Error, fmt::Write as _, fs::File, io::BufReader, path::Path,
+};
+
+/// The default distance under which a survey point is at the place of a benchmark, in metres
+pub const TOLÉRANCE: f64 = 30.0;
+
+/// A survey point of OpenStreetMap
+#[derive(Clone, PartialEq, Debug)]
+pub struct NœudOsm {
+    pub id: i64,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub étiquettes: BTreeMap<String, String>,
+}
+
+impl NœudOsm {
+    pub fn est_point_géodésique(&self) -> bool {
+        self.étiquettes.get("man_made").map(String::as_str) == Some("survey_point")
+    }
+
+    /// The `ref` tag, the matricule of the NGF benchmarks
+    pub fn référence(&self) -> Option<&str> {
+        self.étiquettes.get("ref").map(String::as_str)
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://www.openstreetmap.org/node/{}", self.id)
+    }
+}
+
+/// Reads the survey points of an extract, `.osm.pbf` or `.osm`
+pub fn lis_extrait(chemin: &Path) -> Result<Vec<NœudOsm>, Box<dyn Error>> {
+    let fichier = BufReader::new(File::open(chemin)?);
+    if chemin.to_string_lossy().ends_with(".pbf") {
+        pbf::lis_pbf(fichier)
+    } else {
+        xml::lis_xml(fichier)
+    }
+}
+
+/// The matricule or `ref` without the variations of typing: quotes and spaces
+fn clé(référence: &str) -> String {
+    Matricule::normalise(référence).replace(' ', "")
+}
+
+/// How a benchmark is mapped in OpenStreetMap
+#[derive(Clone, PartialEq, Debug)]
+pub enum Constat {
+    /// A survey point has its matricule, at its place
+    Présent {
+        nœud: NœudOsm,
+        distance: f64,
+    },
+    /// The closest survey point with its matricule is too far from it
+    MalPlacé {
+        nœud: NœudOsm,
+        distance: f64,
+    },
+    /// No survey point has its matricule, but one is at its place with another `ref` or none
+    Discordant {
+        nœud: NœudOsm,
+        distance: f64,
+    },
+    Absent,
+}
+
+/// A benchmark and how it is mapped
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comparaison {
+    pub repère: RepèreNivellement,
+    pub constat: Constat,
+}
+
+/// Matches the benchmarks to the survey points. `tolérance` is the distance under which a survey
+/// point is at the place of a benchmark, in metres
+pub fn compare(
+    repères: Vec<RepèreNivellement>,
+    nœuds: &[NœudOsm],
+    tolérance: f64,
+) -> Vec<Comparaison> {
+    let mut par_référence: BTreeMap<String, Vec<&NœudOsm>> = BTreeMap::new();
+    for nœud in nœuds {
+        if let Some(référence) = nœud.référence() {
+            par_référence.entry(clé(référence)).or_default().push(nœud);
+        }
+    }
+    let plus_proche = |repère: &RepèreNivellement,
+                       candidats: &mut dyn Iterator<Item = &NœudOsm>| {
+        candidats
+            .map(|nœud| {
+                let distance = distance(
+                    (repère.longitude, repère.latitude),
+                    (nœud.longitude, nœud.latitude),
+                );
+                (nœud.clone(), distance)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    };
+    repères
+        .into_iter()
+        .map(|repère| {
+            let homonymes = par_référence.get(&clé(repère.matricule.as_str()));
+            let constat = match homonymes
+                .and_then(|homonymes| plus_proche(&repère, &mut homonymes.iter().copied()))
+            {
+                Some((nœud, distance)) if distance <= tolérance => {
+                    Constat::Présent { nœud, distance }
+                }
+                Some((nœud, distance)) => Constat::MalPlacé { nœud, distance },
+                None => match plus_proche(&repère, &mut nœuds.iter()) {
+                    Some((nœud, distance)) if distance <= tolérance => {
+                        Constat::Discordant { nœud, distance }
+                    }
+                    _ => Constat::Absent,
+                },
+            };
+            Comparaison { repère, constat }
+        })
+        .collect()
+}
+
+/// The report of the cross-check: the benchmarks that are not correctly mapped, by kind
+pub fn rapport(comparaisons: &[Comparaison]) -> String {
+    let mut rapport = String::new();
+    let présents = comparaisons
+        .iter()
+        .filter(|comparaison| matches!(comparaison.constat, Constat::Présent { .. }))
+        .count();
+    writeln!(
+        rapport,
+        "{présents}/{} benchmarks are mapped at their place",
+        comparaisons.len()
+    )
+    .unwrap();
+    let mut section = |titre: &str, lignes: Vec<String>| {
+        if !lignes.is_empty() {
+            writeln!(rapport, "\n{titre} ({}):", lignes.len()).unwrap();
+            for ligne in lignes {
+                writeln!(rapport, "  {ligne}").unwrap();
+            }
+        }
+    };
+    section(
+        "Missing",
+        comparaisons
+            .iter()
+            .filter(|comparaison| comparaison.constat == Constat::Absent)
+            .map(|comparaison| {
+                format!(
+                    "{} ({}, {})",
+                    comparaison.repère.matricule,
+                    comparaison.repère.latitude,
+                    comparaison.repère.longitude
+                )
+            })
+            .collect(),
+    );
+    section(
+        "Mislocated",
+        comparaisons
+            .iter()
+            .filter_map(|comparaison| match &comparaison.constat {
+                Constat::MalPlacé { nœud, distance } => Some(format!(
+                    "{}: {} is {distance:.0} m away",
+                    comparaison.repère.matricule,
+                    nœud.url()
+                )),
+                _ => None,
+            })
+            .collect(),
+    );
+    section(
+        "Mismatched",
+        comparaisons
+            .iter()
+            .filter_map(|comparaison| match &comparaison.constat {
+                Constat::Discordant { nœud, distance } => Some(format!(
+                    "{}: {} ({distance:.0} m away) has {}",
+                    comparaison.repère.matricule,
+                    nœud.url(),
+                    match nœud.référence() {
+                        Some(référence) => format!("ref={référence}"),
+                        None => "no ref".to_string(),
+                    }
+                )),
+                _ => None,
+            })
+            .collect(),
+    );
+    rapport
+}
+
+/// The tags of the survey point of a benchmark
+fn étiquettes(repère: &RepèreNivellement) -> Vec<(&'static str, String)> {
+    let mut étiquettes = vec![
+        ("man_made", "survey_point".to_string()),
+        ("ref", repère.matricule.to_string()),
+        ("operator", "IGN".to_string()),
+    ];
+    if let Some(altitude) = repère.altitude_m() {
+        étiquettes.push(("ele", altitude.to_string()));
+    }
+    étiquettes
+}
+
+/// An osmChange file creating the survey points of the missing benchmarks, to review in an
+/// editor such as JOSM before uploading
+pub fn osc(comparaisons: &[Comparaison]) -> String {
+    let mut osc = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <osmChange version=\"0.6\" generator=\"Géodésie de Bureau\">\n\
+        \x20 <create>\n",
+    );
+    let absents = comparaisons
+        .iter()
+        .filter(|comparaison| comparaison.constat == Constat::Absent);
+    for (index, comparaison) in absents.enumerate() {
+        let repère = &comparaison.repère;
+        writeln!(
+            osc,
+            "    <node id=\"-{}\" version=\"0\" lat=\"{}\" lon=\"{}\">",
+            index + 1,
+            repère.latitude,
+            repère.longitude
+        )
+        .unwrap();
+        for (clé, valeur) in étiquettes(repère) {
+            writeln!(
+                osc,
+                "      <tag k=\"{clé}\" v=\"{}\"/>",
+                échappe_xml(&valeur)
+            )
+            .unwrap();
+        }
+        writeln!(osc, "    </node>").unwrap();
+    }
+    osc.push_str("  </create>\n</osmChange>\n");
+    osc
+}
+
+/// A Markdown task list of the missing benchmarks, with where to find them
+pub fn tâches(comparaisons: &[Comparaison]) -> String {
+    let mut tâches = String::new();
+    for comparaison in comparaisons
+        .iter()
+        .filter(|comparaison| comparaison.constat == Constat::Absent)
+    {
+        let repère = &comparaison.repère;
+        let mut description = vec![format!("{} ({})", repère.commune, repère.département)];
+        description.extend(repère.localisation.clone());
+        description.push(repère.support.clone());
+        writeln!(
+            tâches,
+            "- [ ] {} : {} — https://www.openstreetmap.org/#map=19/{}/{} — {}",
+            repère.matricule,
+            description.join(", "),
+            repère.latitude,
+            repère.longitude,
+            repère.fiche_url
+        )
+        .unwrap();
+    }
+    tâches
+}
+
+#[test]
+fn test_compare() {
+    use crate::save::repère_test;
+    let repère = |matricule: &str, longitude: f64| {
+        let mut repère = repère_test(matricule);
+        repère.longitude = longitude;
+        repère.latitude = 43.6;
+        repère
+    };
+    let nœud = |id: i64, longitude: f64, référence: Option<&str>| NœudOsm {
+        id,
+        longitude,
+        latitude: 43.6,
+        étiquettes: [("man_made", Some("survey_point")), ("ref", référence)]
+            .into_iter()
+            .filter_map(|(clé, valeur)| Some((clé.to_string(), valeur?.to_string())))
+            .collect(),
+    };
+    let repères = vec![
+        repère("T'.D.S3 - 50", 1.4),
+        repère("T'.D.S3 - 51", 1.5),
+        repère("T'.D.S3 - 52", 1.6),
+        repère("T'.D.S3 - 53", 1.7),
+    ];
+    let nœuds = vec![
+        // Typed differently, at 8 m
+        nœud(1, 1.4001, Some("T’.D.S3-50")),
+        // At 800 m
+        nœud(2, 1.51, Some("T'.D.S3 - 51")),
+        nœud(3, 1.6, None),
+    ];
+    let comparaisons = compare(repères, &nœuds, TOLÉRANCE);
+    assert!(matches!(
+        &comparaisons[0].constat,
+        Constat::Présent { nœud, distance } if nœud.id == 1 && *distance < 10.0
+    ));
+    assert!(matches!(
+        &comparaisons[1].constat,
+        Constat::MalPlacé { nœud, distance } if nœud.id == 2 && *distance > 700.0
+    ));
+    assert!(matches!(
+        &comparaisons[2].constat,
+        Constat::Discordant { nœud, .. } if nœud.id == 3
+    ));
+    assert_eq!(comparaisons[3].constat, Constat::Absent);
+
+    let rapport = rapport(&comparaisons);
+    assert!(rapport.starts_with("1/4 benchmarks"));
+    assert!(rapport.contains("Missing (1):\n  T'.D.S3 - 53"));
+    assert!(rapport.contains("https://www.openstreetmap.org/node/3 (0 m away) has no ref"));
+    let osc = osc(&comparaisons);
+    assert_eq!(osc.matches("<node ").count(), 1);
+    assert!(osc.contains("<node id=\"-1\" version=\"0\" lat=\"43.6\" lon=\"1.7\">"));
+    assert!(osc.contains("<tag k=\"ref\" v=\"T'.D.S3 - 53\"/>"));
+    assert!(tâches(&comparaisons).starts_with("- [ ] T'.D.S3 - 53 : "));
+}
@@ -0,0 +1,346 @@
+//! Reader of the survey points of `.osm.pbf` extracts
+//! (https://wiki.openstreetmap.org/wiki/PBF_Format). Only the nodes are decoded: the file is a
+//! sequence of zlib-compressed blobs of protocol buffers, whose few messages are read by hand
+use super::*;
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// A value of a protocol buffers message
+#[derive(Clone, Copy, Debug)]
+enum Valeur<'a> {
+    Varint(u64),
+    Octets(&'a [u8]),
+    Fixe,
+}
+
+fn varint(données: &[u8], position: &mut usize) -> Result<u64, Box<dyn Error>> {
+    let mut valeur: u64 = 0;
+    for décalage in (0..64).step_by(7) {
+        let octet = *données.get(*position).ok_or("Truncated varint")?;
+        *position += 1;
+        valeur |= ((octet & 0x7f) as u64) << décalage;
+        if octet & 0x80 == 0 {
+            return Ok(valeur);
+        }
+    }
+    Err("Varint too long".into())
+}
+
+fn zigzag(valeur: u64) -> i64 {
+    (valeur >> 1) as i64 ^ -((valeur & 1) as i64)
+}
+
+/// The fields of a message, with their numbers
+fn champs(données: &[u8]) -> Result<Vec<(u64, Valeur<'_>)>, Box<dyn Error>> {
+    let mut champs = vec![];
+    let mut position = 0;
+    while position < données.len() {
+        let clé = varint(données, &mut position)?;
+        let valeur = match clé & 7 {
+            0 => Valeur::Varint(varint(données, &mut position)?),
+            1 => {
+                position += 8;
+                Valeur::Fixe
+            }
+            2 => {
+                let longueur = varint(données, &mut position)? as usize;
+                let octets = données
+                    .get(position..position + longueur)
+                    .ok_or("Truncated message")?;
+                position += longueur;
+                Valeur::Octets(octets)
+            }
+            5 => {
+                position += 4;
+                Valeur::Fixe
+            }
+            type_ => return Err(format!("Unsupported wire type {type_}").into()),
+        };
+        champs.push((clé >> 3, valeur));
+    }
+    Ok(champs)
+}
+
+/// The integers of a repeated field, packed or not
+fn entiers(champs: &[(u64, Valeur)], numéro: u64) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut entiers = vec![];
+    for (_, valeur) in champs.iter().filter(|(n, _)| *n == numéro) {
+        match valeur {
+            Valeur::Varint(entier) => entiers.push(*entier),
+            Valeur::Octets(octets) => {
+                let mut position = 0;
+                while position < octets.len() {
+                    entiers.push(varint(octets, &mut position)?);
+                }
+            }
+            Valeur::Fixe => return Err(format!("Field {numéro} is not an integer").into()),
+        }
+    }
+    Ok(entiers)
+}
+
+fn entier(champs: &[(u64, Valeur)], numéro: u64) -> Option<u64> {
+    champs.iter().rev().find_map(|(n, valeur)| match valeur {
+        Valeur::Varint(entier) if *n == numéro => Some(*entier),
+        _ => None,
+    })
+}
+
+fn octets<'a>(champs: &[(u64, Valeur<'a>)], numéro: u64) -> Option<&'a [u8]> {
+    champs.iter().rev().find_map(|(n, valeur)| match valeur {
+        Valeur::Octets(octets) if *n == numéro => Some(*octets),
+        _ => None,
+    })
+}
+
+/// The data of a Blob message, uncompressed
+fn contenu_blob(blob: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let champs = champs(blob)?;
+    if let Some(brut) = octets(&champs, 1) {
+        return Ok(brut.to_vec());
+    }
+    let compressé = octets(&champs, 3).ok_or("Unsupported blob compression (only zlib is)")?;
+    let mut contenu = Vec::with_capacity(entier(&champs, 2).unwrap_or(0) as usize);
+    ZlibDecoder::new(compressé).read_to_end(&mut contenu)?;
+    Ok(contenu)
+}
+
+/// How the coordinates of a PrimitiveBlock are stored
+struct Grille {
+    granularité: i64,
+    décalage_latitude: i64,
+    décalage_longitude: i64,
+}
+
+impl Grille {
+    fn degrés(&self, décalage: i64, valeur: i64) -> f64 {
+        (décalage + self.granularité * valeur) as f64 * 1e-9
+    }
+}
+
+/// The survey points of a PrimitiveBlock
+fn points_du_bloc(bloc: &[u8]) -> Result<Vec<NœudOsm>, Box<dyn Error>> {
+    let champs_bloc = champs(bloc)?;
+    let chaînes: Vec<String> = champs(octets(&champs_bloc, 1).unwrap_or_default())?
+        .into_iter()
+        .filter_map(|(numéro, valeur)| match valeur {
+            Valeur::Octets(chaîne) if numéro == 1 => {
+                Some(String::from_utf8_lossy(chaîne).into_owned())
+            }
+            _ => None,
+        })
+        .collect();
+    // Most blocks have no survey point, and their nodes are skipped without reading their tags
+    let (Some(man_made), Some(survey_point)) = (
+        chaînes.iter().position(|chaîne| chaîne == "man_made"),
+        chaînes.iter().position(|chaîne| chaîne == "survey_point"),
+    ) else {
+        return Ok(vec![]);
+    };
+    let grille = Grille {
+        granularité: entier(&champs_bloc, 17).unwrap_or(100) as i64,
+        décalage_latitude: entier(&champs_bloc, 19).unwrap_or(0) as i64,
+        décalage_longitude: entier(&champs_bloc, 20).unwrap_or(0) as i64,
+    };
+    let nœud = |id: i64, latitude: i64, longitude: i64, étiquettes: Vec<(usize, usize)>| {
+        if !étiquettes.contains(&(man_made, survey_point)) {
+            return None;
+        }
+        let chaîne = |index: usize| chaînes.get(index).cloned().unwrap_or_default();
+        Some(NœudOsm {
+            id,
+            longitude: grille.degrés(grille.décalage_longitude, longitude),
+            latitude: grille.degrés(grille.décalage_latitude, latitude),
+            étiquettes: étiquettes
+                .into_iter()
+                .map(|(clé, valeur)| (chaîne(clé), chaîne(valeur)))
+                .collect(),
+        })
+    };
+
+    let mut points = vec![];
+    for (_, groupe) in champs_bloc.iter().filter(|(numéro, _)| *numéro == 2) {
+        let Valeur::Octets(groupe) = groupe else {
+            continue;
+        };
+        let champs_groupe = champs(groupe)?;
+        for (numéro, valeur) in &champs_groupe {
+            match (numéro, valeur) {
+                // Node
+                (1, Valeur::Octets(message)) => {
+                    let champs = champs(message)?;
+                    let étiquettes = entiers(&champs, 2)?
+                        .into_iter()
+                        .zip(entiers(&champs, 3)?)
+                        .map(|(clé, valeur)| (clé as usize, valeur as usize))
+                        .collect();
+                    points.extend(nœud(
+                        zigzag(entier(&champs, 1).unwrap_or(0)),
+                        zigzag(entier(&champs, 8).unwrap_or(0)),
+                        zigzag(entier(&champs, 9).unwrap_or(0)),
+                        étiquettes,
+                    ));
+                }
+                // DenseNodes: the ids and coordinates are delta-coded, and the tags of all the
+                // nodes are in one list, each node ending with 0
+                (2, Valeur::Octets(message)) => {
+                    let champs = champs(message)?;
+                    let ids = entiers(&champs, 1)?;
+                    let latitudes = entiers(&champs, 8)?;
+                    let longitudes = entiers(&champs, 9)?;
+                    let mut étiquettes = entiers(&champs, 10)?.into_iter();
+                    let (mut id, mut latitude, mut longitude) = (0i64, 0i64, 0i64);
+                    for (index, delta) in ids.into_iter().enumerate() {
+                        id += zigzag(delta);
+                        latitude += zigzag(*latitudes.get(index).ok_or("Missing latitude")?);
+                        longitude += zigzag(*longitudes.get(index).ok_or("Missing longitude")?);
+                        let mut étiquettes_nœud = vec![];
+                        while let Some(clé) = étiquettes.next().filter(|clé| *clé != 0) {
+                            let valeur = étiquettes.next().ok_or("Missing tag value")?;
+                            étiquettes_nœud.push((clé as usize, valeur as usize));
+                        }
+                        points.extend(nœud(id, latitude, longitude, étiquettes_nœud));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(points)
+}
+
+/// The survey points of a PBF extract
+pub fn lis_pbf<R: Read>(mut lecteur: R) -> Result<Vec<NœudOsm>, Box<dyn Error>> {
+    let mut points = vec![];
+    loop {
+        let mut longueur = [0u8; 4];
+        match lecteur.read_exact(&mut longueur) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        let mut entête = vec![0u8; u32::from_be_bytes(longueur) as usize];
+        lecteur.read_exact(&mut entête)?;
+        let champs_entête = champs(&entête)?;
+        let type_blob = octets(&champs_entête, 1)
+            .ok_or("Blob without type")?
+            .to_vec();
+        let taille = entier(&champs_entête, 3).ok_or("Blob without size")? as usize;
+        let mut blob = vec![0u8; taille];
+        lecteur.read_exact(&mut blob)?;
+        if type_blob == b"OSMData" {
+            points.extend(points_du_bloc(&contenu_blob(&blob)?)?);
+        }
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+fn écris_varint(mut valeur: u64, sortie: &mut Vec<u8>) {
+    while valeur >= 0x80 {
+        sortie.push((valeur as u8) | 0x80);
+        valeur >>= 7;
+    }
+    sortie.push(valeur as u8);
+}
+
+#[cfg(test)]
+fn écris_champ(numéro: u64, valeur: Valeur, sortie: &mut Vec<u8>) {
+    match valeur {
+        Valeur::Varint(entier) => {
+            écris_varint(numéro << 3, sortie);
+            écris_varint(entier, sortie);
+        }
+        Valeur::Octets(octets) => {
+            écris_varint((numéro << 3) | 2, sortie);
+            écris_varint(octets.len() as u64, sortie);
+            sortie.extend_from_slice(octets);
+        }
+        Valeur::Fixe => unimplemented!(),
+    }
+}
+
+/// A blob of this type with its header, as in a PBF file
+#[cfg(test)]
+fn écris_blob(type_blob: &str, contenu: &[u8], sortie: &mut Vec<u8>) {
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+    let mut zlib = ZlibEncoder::new(vec![], Compression::default());
+    zlib.write_all(contenu).unwrap();
+    let mut blob = vec![];
+    écris_champ(2, Valeur::Varint(contenu.len() as u64), &mut blob);
+    écris_champ(3, Valeur::Octets(&zlib.finish().unwrap()), &mut blob);
+    let mut entête = vec![];
+    écris_champ(1, Valeur::Octets(type_blob.as_bytes()), &mut entête);
+    écris_champ(3, Valeur::Varint(blob.len() as u64), &mut entête);
+    sortie.extend_from_slice(&(entête.len() as u32).to_be_bytes());
+    sortie.extend_from_slice(&entête);
+    sortie.extend_from_slice(&blob);
+}
+
+#[test]
+fn test_pbf() {
+    let zigzag = |valeur: i64| ((valeur << 1) ^ (valeur >> 63)) as u64;
+    let compactés = |entiers: &[u64]| {
+        let mut octets = vec![];
+        for entier in entiers {
+            écris_varint(*entier, &mut octets);
+        }
+        octets
+    };
+    let chaînes = [
+        "",
+        "man_made",
+        "survey_point",
+        "ref",
+        "T'.D.S3 - 50",
+        "amenity",
+        "bench",
+    ];
+    let mut table = vec![];
+    for chaîne in chaînes {
+        écris_champ(1, Valeur::Octets(chaîne.as_bytes()), &mut table);
+    }
+    // Two dense nodes, only the first one is a survey point, then a plain survey point
+    let (latitudes, longitudes) = ([436_492_760i64, 436_500_000], [14_091_970i64, 14_100_000]);
+    let mut denses = vec![];
+    écris_champ(
+        1,
+        Valeur::Octets(&compactés(&[zigzag(10), zigzag(2)])),
+        &mut denses,
+    );
+    let latitudes = compactés(&[zigzag(latitudes[0]), zigzag(latitudes[1] - latitudes[0])]);
+    écris_champ(8, Valeur::Octets(&latitudes), &mut denses);
+    let longitudes = compactés(&[zigzag(longitudes[0]), zigzag(longitudes[1] - longitudes[0])]);
+    écris_champ(9, Valeur::Octets(&longitudes), &mut denses);
+    écris_champ(
+        10,
+        Valeur::Octets(&compactés(&[1, 2, 3, 4, 0, 5, 6, 0])),
+        &mut denses,
+    );
+    let mut nœud = vec![];
+    écris_champ(1, Valeur::Varint(zigzag(-5)), &mut nœud);
+    écris_champ(2, Valeur::Varint(1), &mut nœud);
+    écris_champ(3, Valeur::Varint(2), &mut nœud);
+    écris_champ(8, Valeur::Varint(zigzag(-10)), &mut nœud);
+    écris_champ(9, Valeur::Varint(zigzag(20)), &mut nœud);
+    let mut groupe = vec![];
+    écris_champ(2, Valeur::Octets(&denses), &mut groupe);
+    écris_champ(1, Valeur::Octets(&nœud), &mut groupe);
+    let mut bloc = vec![];
+    écris_champ(1, Valeur::Octets(&table), &mut bloc);
+    écris_champ(2, Valeur::Octets(&groupe), &mut bloc);
+
+    let mut fichier = vec![];
+    écris_blob("OSMHeader", b"", &mut fichier);
+    écris_blob("OSMData", &bloc, &mut fichier);
+    let points = lis_pbf(fichier.as_slice()).unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].id, 10);
+    assert!((points[0].latitude - 43.649276).abs() < 1e-9);
+    assert!((points[0].longitude - 1.409197).abs() < 1e-9);
+    assert_eq!(points[0].référence(), Some("T'.D.S3 - 50"));
+    assert_eq!(points[1].id, -5);
+    assert!((points[1].latitude + 0.000001).abs() < 1e-12);
+    assert_eq!(points[1].référence(), None);
+}
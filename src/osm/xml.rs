@@ -0,0 +1,85 @@
+//! Reader of the survey points of `.osm` XML extracts
+use super::*;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::{collections::BTreeMap, io::BufRead};
+
+/// The attributes of an element
+fn attributs(élément: &BytesStart) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut attributs = BTreeMap::new();
+    for attribut in élément.attributes() {
+        let attribut = attribut?;
+        attributs.insert(
+            String::from_utf8_lossy(attribut.key.as_ref()).into_owned(),
+            attribut.unescape_value()?.into_owned(),
+        );
+    }
+    Ok(attributs)
+}
+
+/// The survey points of an XML extract
+pub fn lis_xml<R: BufRead>(lecteur: R) -> Result<Vec<NœudOsm>, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(lecteur);
+    let mut buffer = vec![];
+    let mut points = vec![];
+    // The node being read, whose tags follow
+    let mut nœud: Option<NœudOsm> = None;
+    loop {
+        match reader.read_event_into(&mut buffer)? {
+            Event::Start(élément) if élément.name().as_ref() == b"node" => {
+                let attributs = attributs(&élément)?;
+                let nombre = |nom: &str| -> Result<f64, Box<dyn Error>> {
+                    Ok(attributs
+                        .get(nom)
+                        .ok_or(format!("Node without {nom}"))?
+                        .parse()?)
+                };
+                nœud = Some(NœudOsm {
+                    id: attributs.get("id").ok_or("Node without id")?.parse()?,
+                    longitude: nombre("lon")?,
+                    latitude: nombre("lat")?,
+                    étiquettes: BTreeMap::new(),
+                });
+            }
+            Event::Empty(élément) if élément.name().as_ref() == b"tag" => {
+                if let Some(nœud) = nœud.as_mut() {
+                    let mut attributs = attributs(&élément)?;
+                    if let (Some(clé), Some(valeur)) =
+                        (attributs.remove("k"), attributs.remove("v"))
+                    {
+                        nœud.étiquettes.insert(clé, valeur);
+                    }
+                }
+            }
+            Event::End(élément) if élément.name().as_ref() == b"node" => {
+                points.extend(nœud.take().filter(NœudOsm::est_point_géodésique));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+    Ok(points)
+}
+
+#[test]
+fn test_xml() {
+    let extrait = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="43.649276" lon="1.409197">
+    <tag k="man_made" v="survey_point"/>
+    <tag k="ref" v="T&apos;.D.S3 - 50"/>
+  </node>
+  <node id="2" lat="43.65" lon="1.41"/>
+  <node id="3" lat="43.65" lon="1.41">
+    <tag k="amenity" v="bench"/>
+  </node>
+</osm>"#;
+    let points = lis_xml(extrait.as_bytes()).unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].id, 1);
+    assert_eq!(points[0].référence(), Some("T'.D.S3 - 50"));
+    assert_eq!(points[0].latitude, 43.649276);
+}
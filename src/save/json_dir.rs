@@ -0,0 +1,358 @@
+//! A store that follows the JSON directory layout described in database.txt
+use super::*;
+use crate::common_traits::ObjectId;
+use std::path::{Path, PathBuf};
+
+pub struct JsonDirStore {
+    root: PathBuf,
+}
+
+/// Turns a key into something that can safely be used as a file name on every OS
+fn file_name_from_key(key: &str) -> String {
+    let mut file_name = String::new();
+    for character in key.chars() {
+        if character.is_alphanumeric() || " .-_'()".contains(character) {
+            file_name.push(character);
+        } else {
+            let mut buffer = [0u8; 4];
+            for byte in character.encode_utf8(&mut buffer).bytes() {
+                file_name += format!("%{byte:02X}").as_str();
+            }
+        }
+    }
+    file_name + ".json"
+}
+
+/// Reads all the files named `file_name` in `directory` and its subdirectories
+fn find_files(directory: &Path, file_name: &str) -> StoreResult<Vec<PathBuf>> {
+    let mut found: Vec<PathBuf> = vec![];
+    if !directory.is_dir() {
+        return Ok(found);
+    }
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            found.append(&mut find_files(&path, file_name)?);
+        } else if path.file_name().and_then(|name| name.to_str()) == Some(file_name) {
+            found.push(path);
+        }
+    }
+    Ok(found)
+}
+
+/// Copies `from` and all its content into `to`
+fn copy_directory(from: &Path, to: &Path) -> StoreResult<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        let destination = to.join(path.file_name().ok_or("Invalid path")?);
+        if path.is_dir() {
+            copy_directory(&path, &destination)?;
+        } else {
+            fs::copy(&path, &destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the empty directories between `directory` and `stop` (excluded)
+fn remove_empty_parents(directory: &Path, stop: &Path) -> StoreResult<()> {
+    let mut current = directory.to_path_buf();
+    while current != stop && current.starts_with(stop) {
+        if fs::read_dir(&current)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(&current)?;
+        current = match current.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => break,
+        };
+    }
+    Ok(())
+}
+
+impl JsonDirStore {
+    /// Opens (and creates if needed) the store located at `root`
+    pub fn open<P: AsRef<Path>>(root: P) -> StoreResult<JsonDirStore> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join("data").join("points"))?;
+        fs::create_dir_all(root.join("data").join("visites"))?;
+        fs::create_dir_all(root.join("options"))?;
+        Ok(JsonDirStore { root })
+    }
+
+    /// Paths of the files of all the points
+    fn objet_paths(&self) -> StoreResult<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for type_entry in fs::read_dir(self.points_directory())? {
+            let type_path = type_entry?.path();
+            if !type_path.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(type_path)? {
+                paths.push(entry?.path());
+            }
+        }
+        Ok(paths)
+    }
+
+    fn points_directory(&self) -> PathBuf {
+        self.root.join("data").join("points")
+    }
+
+    fn visites_directory(&self) -> PathBuf {
+        self.root.join("data").join("visites")
+    }
+
+    fn objet_path(&self, id: &str) -> StoreResult<PathBuf> {
+        let id = ObjectId::from_str(id)?;
+        Ok(self
+            .points_directory()
+            .join(id.network)
+            .join(file_name_from_key(&id.key)))
+    }
+
+    fn visite_directory(&self, visite: &Visite) -> PathBuf {
+        self.visites_directory()
+            .join(visite.date.format("%Y").to_string())
+            .join(visite.date.format("%m").to_string())
+            .join(visite.date.format("%d").to_string())
+            .join(visite.id.to_string())
+    }
+
+    /// Returns the directory in which the visit with this id is currently stored
+    fn find_visite_directory(&self, id: u64) -> StoreResult<Option<PathBuf>> {
+        for path in find_files(&self.visites_directory(), "visite.json")? {
+            let directory = path.parent().ok_or("Invalid visit path")?;
+            if directory.file_name().and_then(|name| name.to_str()) == Some(id.to_string().as_str())
+            {
+                return Ok(Some(directory.to_path_buf()));
+            }
+        }
+        Ok(None)
+    }
+
+    fn photo_paths(&self) -> StoreResult<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for path in find_files(&self.visites_directory(), "visite.json")? {
+            for entry in fs::read_dir(path.parent().ok_or("Invalid visit path")?)? {
+                let photo_path = entry?.path();
+                let file_name = photo_path.file_name().and_then(|name| name.to_str());
+                if file_name
+                    .is_some_and(|name| name.starts_with("photo-") && name.ends_with(".json"))
+                {
+                    paths.push(photo_path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+}
+
+impl Store for JsonDirStore {
+    fn objets(&self) -> StoreResult<Vec<Objet>> {
+        let mut objets: Vec<Objet> = vec![];
+        for path in self.objet_paths()? {
+            objets.push(serde_json::from_str::<Objet>(
+                fs::read_to_string(path)?.as_str(),
+            )?);
+        }
+        Ok(objets)
+    }
+
+    fn objet(&self, id: &str) -> StoreResult<Option<Objet>> {
+        match fs::read_to_string(self.objet_path(id)?) {
+            Ok(content) => Ok(Some(serde_json::from_str::<Objet>(content.as_str())?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save_objet(&mut self, objet: &Objet) -> StoreResult<()> {
+        let path = self.objet_path(self.check_objet(objet)?.to_string().as_str())?;
+        fs::create_dir_all(path.parent().ok_or("Invalid point path")?)?;
+        fs::write(path, serde_json::to_string_pretty(objet)?)?;
+        Ok(())
+    }
+
+    fn remove_objet(&mut self, id: &str) -> StoreResult<()> {
+        let path = self.objet_path(id)?;
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    fn visites(&self) -> StoreResult<Vec<Visite>> {
+        let mut visites: Vec<Visite> = vec![];
+        for path in find_files(&self.visites_directory(), "visite.json")? {
+            visites.push(serde_json::from_str::<Visite>(
+                fs::read_to_string(path)?.as_str(),
+            )?);
+        }
+        Ok(visites)
+    }
+
+    fn save_visite(&mut self, visite: &Visite) -> StoreResult<()> {
+        let directory = self.visite_directory(visite);
+        // If the date of the visit changed, its photos move with it
+        if let Some(old_directory) = self.find_visite_directory(visite.id)? {
+            if old_directory != directory {
+                fs::create_dir_all(directory.parent().ok_or("Invalid visit path")?)?;
+                fs::rename(&old_directory, &directory)?;
+                remove_empty_parents(
+                    old_directory.parent().ok_or("Invalid visit path")?,
+                    &self.visites_directory(),
+                )?;
+            }
+        }
+        fs::create_dir_all(&directory)?;
+        fs::write(
+            directory.join("visite.json"),
+            serde_json::to_string_pretty(visite)?,
+        )?;
+        Ok(())
+    }
+
+    fn remove_visite(&mut self, id: u64) -> StoreResult<()> {
+        if let Some(directory) = self.find_visite_directory(id)? {
+            fs::remove_dir_all(&directory)?;
+            remove_empty_parents(
+                directory.parent().ok_or("Invalid visit path")?,
+                &self.visites_directory(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn photos(&self) -> StoreResult<Vec<Photo>> {
+        let mut photos: Vec<Photo> = vec![];
+        for path in self.photo_paths()? {
+            photos.push(serde_json::from_str::<Photo>(
+                fs::read_to_string(path)?.as_str(),
+            )?);
+        }
+        Ok(photos)
+    }
+
+    fn save_photo(&mut self, photo: &Photo) -> StoreResult<()> {
+        let directory = self
+            .find_visite_directory(photo.visite)?
+            .ok_or(format!("There is no visit with id {}", photo.visite))?;
+        self.remove_photo(photo.id)?;
+        fs::write(
+            directory.join(format!("photo-{}.json", photo.id)),
+            serde_json::to_string_pretty(photo)?,
+        )?;
+        Ok(())
+    }
+
+    fn remove_photo(&mut self, id: u64) -> StoreResult<()> {
+        let file_name = format!("photo-{id}.json");
+        for path in self.photo_paths()? {
+            if path.file_name().and_then(|name| name.to_str()) == Some(file_name.as_str()) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn options(&self) -> StoreResult<Options> {
+        match fs::read_to_string(self.root.join("options").join("options.json")) {
+            Ok(content) => Ok(serde_json::from_str::<Options>(content.as_str())?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Options::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save_options(&mut self, options: &Options) -> StoreResult<()> {
+        fs::write(
+            self.root.join("options").join("options.json"),
+            serde_json::to_string_pretty(options)?,
+        )?;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> StoreResult<Option<u32>> {
+        match fs::read_to_string(self.root.join("version")) {
+            Ok(content) => Ok(Some(content.trim().parse::<u32>()?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set_schema_version(&mut self, version: u32) -> StoreResult<()> {
+        fs::write(self.root.join("version"), format!("{version}\n"))?;
+        Ok(())
+    }
+
+    fn raw_save(&self) -> StoreResult<RawSave> {
+        let read_values = |paths: Vec<PathBuf>| -> StoreResult<Vec<serde_json::Value>> {
+            let mut values: Vec<serde_json::Value> = vec![];
+            for path in paths {
+                values.push(serde_json::from_str(fs::read_to_string(path)?.as_str())?);
+            }
+            Ok(values)
+        };
+        Ok(RawSave {
+            objets: read_values(self.objet_paths()?)?,
+            visites: read_values(find_files(&self.visites_directory(), "visite.json")?)?,
+            photos: read_values(self.photo_paths()?)?,
+            options: match fs::read_to_string(self.root.join("options").join("options.json")) {
+                Ok(content) => serde_json::from_str(content.as_str())?,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    serde_json::Value::Object(serde_json::Map::new())
+                }
+                Err(err) => return Err(err.into()),
+            },
+        })
+    }
+
+    fn clear(&mut self) -> StoreResult<()> {
+        // Only the JSON files are removed: the image files of the photos stay where they are
+        for path in self.photo_paths()? {
+            fs::remove_file(path)?;
+        }
+        for path in find_files(&self.visites_directory(), "visite.json")? {
+            fs::remove_file(path)?;
+        }
+        fs::remove_dir_all(self.points_directory())?;
+        fs::create_dir_all(self.points_directory())?;
+        let options_path = self.root.join("options").join("options.json");
+        if options_path.exists() {
+            fs::remove_file(options_path)?;
+        }
+        Ok(())
+    }
+
+    fn backup(&self) -> StoreResult<Option<String>> {
+        let mut backup_name = self
+            .root
+            .file_name()
+            .ok_or("The store has no name")?
+            .to_os_string();
+        backup_name.push(format!(
+            ".backup-v{}-{}",
+            self.schema_version()?.unwrap_or(1),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let backup_path = self.root.with_file_name(backup_name);
+        copy_directory(&self.root, &backup_path)?;
+        Ok(Some(backup_path.to_string_lossy().to_string()))
+    }
+
+    fn types(&self) -> StoreResult<Vec<PointType>> {
+        // The types are defined next to the points (see types_directory)
+        custom_types::load_types(&self.root.join("data").join("types"))
+    }
+}
+
+#[test]
+fn test_file_name_from_key() {
+    assert_eq!(file_name_from_key("T'.D.S3 - 50"), "T'.D.S3 - 50.json");
+    assert_eq!(
+        file_name_from_key("M\".A.K3L3 - 15-I"),
+        "M%22.A.K3L3 - 15-I.json"
+    );
+    assert_eq!(file_name_from_key("a/b"), "a%2Fb.json");
+}
@@ -0,0 +1,750 @@
+//! Import of the saves written by the Python CLI (py_src). Their points use the field names of
+//! template_db/data/types/ngf.json, and are either plain objects or GeoJSON features
+use super::*;
+use crate::geod::sit_no_from_géod_info;
+use crate::ngf::json_mappings::bbox::*;
+use crate::ngf::Territoire;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Prefix of the options that remember the legacy visits already imported
+const LEGACY_VISIT_OPTION: &str = "legacy_visite:";
+
+/// A field of a legacy save that could not be imported as is, or a file that could not be
+/// imported at all (its `champ` is then empty)
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnmappedField {
+    pub fichier: PathBuf,
+    pub champ: String,
+    pub valeur: Value,
+    pub raison: String,
+}
+
+/// What was imported from a legacy save
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ImportReport {
+    pub objets: usize,
+    pub visites: usize,
+    pub photos: usize,
+    /// Visits that an earlier import already brought into the store
+    pub déjà_importées: usize,
+    pub unmapped: Vec<UnmappedField>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} point(s), {} visit(s) and {} photo(s) imported",
+            self.objets, self.visites, self.photos
+        )?;
+        if self.déjà_importées > 0 {
+            writeln!(f, "{} visit(s) were already imported", self.déjà_importées)?;
+        }
+        if !self.unmapped.is_empty() {
+            writeln!(f, "Fields and files that could not be mapped:")?;
+        }
+        for field in &self.unmapped {
+            if field.champ.is_empty() {
+                writeln!(f, "{} ({})", field.fichier.display(), field.raison)?;
+                continue;
+            }
+            writeln!(
+                f,
+                "{} : \x1b[94m{}\x1b[39m = {} ({})",
+                field.fichier.display(),
+                field.champ,
+                field.valeur,
+                field.raison
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The fields of a legacy JSON object. Keeps track of the fields that were read, so that the
+/// other ones can be reported
+struct LegacyFields<'a> {
+    fields: Map<String, Value>,
+    fichier: &'a Path,
+    unmapped: Vec<UnmappedField>,
+}
+
+impl<'a> LegacyFields<'a> {
+    fn new(fields: Map<String, Value>, fichier: &'a Path) -> LegacyFields<'a> {
+        LegacyFields {
+            fields,
+            fichier,
+            unmapped: vec![],
+        }
+    }
+
+    fn report(&mut self, champ: &str, valeur: Value, raison: &str) {
+        self.unmapped.push(UnmappedField {
+            fichier: self.fichier.to_path_buf(),
+            champ: champ.to_string(),
+            valeur,
+            raison: raison.to_string(),
+        })
+    }
+
+    fn take(&mut self, champ: &str) -> Value {
+        self.fields.remove(champ).unwrap_or(Value::Null)
+    }
+
+    /// Strings, numbers and booleans are all turned into strings. null and missing fields are None
+    fn string(&mut self, champ: &str) -> Option<String> {
+        match self.take(champ) {
+            Value::Null => None,
+            Value::String(string) => Some(string),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// Like string, but empty strings are None too
+    fn non_empty_string(&mut self, champ: &str) -> Option<String> {
+        self.string(champ).filter(|string| !string.is_empty())
+    }
+
+    /// Numbers written with a decimal comma, as the IGN API does
+    fn comma_number(&mut self, champ: &str) -> Option<String> {
+        self.non_empty_string(champ)
+            .map(|number| number.replace('.', ","))
+    }
+
+    fn float(&mut self, champ: &str) -> Option<f64> {
+        match self.take(champ) {
+            Value::Number(number) => number.as_f64(),
+            Value::String(string) => match string.replace(',', ".").parse::<f64>() {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    self.report(champ, Value::String(string), "not a number");
+                    None
+                }
+            },
+            Value::Null => None,
+            value => {
+                self.report(champ, value, "not a number");
+                None
+            }
+        }
+    }
+
+    /// Coordinates in metres, turned into the kilometres used by RepèreNivellement
+    fn kilometres(&mut self, champ: &str) -> String {
+        match self.float(champ) {
+            Some(metres) => format!("{:.2}", metres / 1000.),
+            None => "".to_string(),
+        }
+    }
+
+    /// Reports all the fields that were not read
+    fn finish(mut self) -> Vec<UnmappedField> {
+        let remaining: Vec<(String, Value)> =
+            std::mem::take(&mut self.fields).into_iter().collect();
+        for (champ, valeur) in remaining {
+            if !valeur.is_null() && valeur != Value::String("".to_string()) {
+                self.report(&champ, valeur, "no equivalent field");
+            }
+        }
+        self.unmapped
+    }
+}
+
+/// Maps a state of ngf.json. Some of them have no exact equivalent, and are approximated (the
+/// boolean is then false)
+fn état_from_label(état: &str) -> Option<(RnÉtatCode, bool)> {
+    Some(match état {
+        "Bon état" => (RnÉtatCode::BonÉtat, true),
+        "Imprenable" => (RnÉtatCode::Imprenable, true),
+        "Mauvais état" => (RnÉtatCode::MauvaisÉtat, true),
+        "Non retrouvé" => (RnÉtatCode::NonRetrouvé, true),
+        "Présumé déplacé" => (RnÉtatCode::PresuméDéplacé, true),
+        "Détruit" => (RnÉtatCode::Détruit, true),
+        "Détruit après observation" => (RnÉtatCode::DétruitAprèsObservation, true),
+        "Exploitable, en mauvais état ou légèrement incliné" | "Incliné fortement" => {
+            (RnÉtatCode::MauvaisÉtat, false)
+        }
+        "Bon état mais douteux" => (RnÉtatCode::BonÉtat, false),
+        _ => return None,
+    })
+}
+
+fn état_from_legacy(fields: &mut LegacyFields, champ: &str) -> Option<RnÉtatCode> {
+    let état = fields.string(champ)?;
+    let Some((code, exact)) = état_from_label(&état) else {
+        fields.report(champ, Value::String(état), "unknown state");
+        return None;
+    };
+    if !exact {
+        fields.report(
+            champ,
+            Value::String(état),
+            format!("approximated as '{code}'").as_str(),
+        );
+    }
+    Some(code)
+}
+
+fn rn_type_from_legacy(fields: &mut LegacyFields) -> RnTypeCode {
+    let Some(rn_type) = fields.string("type") else {
+        return RnTypeCode::Inconnu;
+    };
+    match rn_type.as_str() {
+        "Inconnu" => RnTypeCode::Inconnu,
+        "Repère console" => RnTypeCode::RepèreConsole,
+        "Rivet" => RnTypeCode::Rivet,
+        "Repère Bourdalouë" => RnTypeCode::RepèreBourdalouë,
+        "Repère PLM (Chemin de fer Paris-Lyon-Méditerranée)" => {
+            RnTypeCode::RepèrePLMCheminDeFerParisLyonMéditerranée
+        }
+        "Repère MRU (Ministère Reconstruction Urbanisme)" => {
+            RnTypeCode::RepèreMRUMinistèreReconstructionUrbanisme
+        }
+        "Repère ponts et chaussées" => RnTypeCode::RepèrePontsEtChaussées,
+        "Repère navigation" => RnTypeCode::RepèreNavigation,
+        "Repère ville de Paris" => RnTypeCode::RepèreVilleDeParis,
+        "Repère cylindrique du Nivellement Général" => {
+            RnTypeCode::RepèreCylindriqueDuNivellementGénéral
+        }
+        "Repère local" => RnTypeCode::RepèreLocal,
+        "Repère hexagonal" => RnTypeCode::RepèreHexagonal,
+        "Repère local, repère dans un système local" => {
+            RnTypeCode::RepèreLocalRepèreDansUnSystèmeLocal
+        }
+        "Échelle hydrométrique" => RnTypeCode::ÉchelleHydrométrique,
+        "Repère boule" => RnTypeCode::RepèreBoule,
+        "Repère italien" => RnTypeCode::RepèreItalien,
+        "Repère de crue" => RnTypeCode::RepèreDeCrue,
+        "Repère octogonal" => RnTypeCode::RepèreOctogonal,
+        "Repère reconstruction" => RnTypeCode::RepèreReconstruction,
+        "Repère EDF" => RnTypeCode::RepèreEDF,
+        "Repère SNCF" => RnTypeCode::RepèreSNCF,
+        "Repère cadastre" => RnTypeCode::RepèreCadastre,
+        "Repère allemand" => RnTypeCode::RepèreAllemand,
+        "Repère belge" => RnTypeCode::RepèreBelge,
+        "Repère luxembourgeois" => RnTypeCode::RepèreLuxembourgeois,
+        "Repère suisse" => RnTypeCode::RepèreSuisse,
+        "Repère espagnol" => RnTypeCode::RepèreEspagnol,
+        "Repère ville de Marseille" => RnTypeCode::RepèreVilleDeMarseille,
+        "Trait de crue" => RnTypeCode::TraitDeCrue,
+        "Borne" => RnTypeCode::Borne,
+        "Repère SHOM (Service Hydrographique et Océanographique de la Marine)" => {
+            RnTypeCode::RepèreSHOMServiceHydrographiqueEtOcéanographiqueDeLaMarine
+        }
+        "Repère fondamental" => RnTypeCode::RepèreFondamental,
+        "Tube" => RnTypeCode::Tube,
+        "Repère IPG (Institut de Physique du Globe)" => {
+            RnTypeCode::RepèreIPGInstitutDePhysiqueDuGlobe
+        }
+        "Repère conique" => RnTypeCode::RepèreConique,
+        "Repère en fonte triangulaire" => RnTypeCode::RepèreEnFonteTriangulaire,
+        _ => {
+            fields.report("type", Value::String(rn_type), "unknown type");
+            RnTypeCode::Inconnu
+        }
+    }
+}
+
+/// Maps the fields of a point of ngf.json onto a RepèreNivellement
+fn repère_from_legacy(fields: &mut LegacyFields) -> Result<ngf::RepèreNivellement, String> {
+    let matricule: ngf::Matricule = fields
+        .non_empty_string("matricule")
+        .ok_or("The point has no matricule")?
+        .parse()?;
+    let cid = match fields.float("cid") {
+        Some(cid) => cid as i64,
+        None => {
+            fields.report("cid", Value::Null, "missing, set to 0");
+            0
+        }
+    };
+    let système_altimétrique = match fields.string("systeme_altimetrique").as_deref() {
+        Some("NGF-IGN 1978") => NivfReaCode::NgfIgn1978,
+        Some("NGF-IGN 1969") | None => NivfReaCode::NgfIgn1969,
+        Some(other) => {
+            fields.report(
+                "systeme_altimetrique",
+                Value::String(other.to_string()),
+                "unknown system, NGF-IGN 1969 used",
+            );
+            NivfReaCode::NgfIgn1969
+        }
+    };
+    let altitude_type = match fields.string("altitude_type").as_deref() {
+        Some("Altitude orthométrique") => HTypeCode::AltitudeOrthométrique,
+        Some("Altitude provisoire") => HTypeCode::AltitudeProvisoire,
+        Some("Altitude normale") | None => HTypeCode::AltitudeNormale,
+        Some(other) => {
+            fields.report(
+                "altitude_type",
+                Value::String(other.to_string()),
+                "unknown altitude type, normal altitude used",
+            );
+            HTypeCode::AltitudeNormale
+        }
+    };
+    let état = état_from_legacy(fields, "etat").unwrap_or(RnÉtatCode::BonÉtat);
+    let rn_type = rn_type_from_legacy(fields);
+    let (longitude, latitude) = (
+        fields.float("longitude").unwrap_or_default(),
+        fields.float("latitude").unwrap_or_default(),
+    );
+    let voie_côté = match fields.string("voie_cote").as_deref() {
+        Some("Droit") => VoieCôtéCode::Droit,
+        Some("Gauche") => VoieCôtéCode::Gauche,
+        Some("Milieu") => VoieCôtéCode::Milieu,
+        Some("") | None => VoieCôtéCode::TheAPIDocumentationIsWrong,
+        Some(other) => {
+            fields.report(
+                "voie_cote",
+                Value::String(other.to_string()),
+                "unknown side",
+            );
+            VoieCôtéCode::TheAPIDocumentationIsWrong
+        }
+    };
+    // The département is the entity only when the entity is a département
+    let département = match fields.string("entite_nature") {
+        Some(nature) if nature.to_lowercase().starts_with("département") => {
+            fields.take("entite");
+            fields.string("entite_no").unwrap_or_default()
+        }
+        Some(nature) => {
+            fields.report("entite_nature", Value::String(nature), "not a département");
+            "".to_string()
+        }
+        None => fields.string("entite_no").unwrap_or_default(),
+    };
+    // The legacy saves only tell whether the IGN observed the benchmark, not who did
+    match fields.take("hors_ign") {
+        Value::Bool(false) | Value::Null => (),
+        value => fields.report("hors_ign", value, "the observing organisation is unknown"),
+    }
+    let exploitabilité_gps = match fields.string("exploitabilite_gnss").as_deref() {
+        Some("Exploitable directement par GNSS") => RnGPSExploitCode::ExploitableDirectementParGPS,
+        Some("Exploitable par GNSS depuis une station excentrée") => {
+            RnGPSExploitCode::ExploitableParGPSDepuisUneStationExcentrée
+        }
+        Some("Inexploitable par GNSS") => RnGPSExploitCode::InexploitableParGPS,
+        Some("Exploitation par GNSS inconnue") | None => RnGPSExploitCode::Empty,
+        Some(other) => {
+            fields.report(
+                "exploitabilite_gnss",
+                Value::String(other.to_string()),
+                "unknown GNSS exploitability",
+            );
+            RnGPSExploitCode::Empty
+        }
+    };
+    let géod_info = fields.string("geod_info").unwrap_or_default();
+    Ok(ngf::RepèreNivellement {
+        fiche_url: fields.string("fiche_url").unwrap_or_else(|| format!("https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid={cid}&geo_cid=0")),
+        matricule,
+        cid,
+        système_altimétrique,
+        altitude: fields.comma_number("altitude").unwrap_or_default(),
+        altitude_complémentaire: "".to_string(),
+        altitude_type,
+        dernière_observation: fields.string("derniere_observation").unwrap_or_default(),
+        nouveau_calcul: fields.string("nouveau_calcul").unwrap_or_default(),
+        dernière_visite: fields.string("derniere_visite").unwrap_or_default(),
+        état,
+        rn_type,
+        type_complément: fields.string("type_complement"),
+        canex_info: fields.string("canex_info").unwrap_or_default(),
+        type_complément_avec_canex: fields.string("type_complement_avec_canex").unwrap_or_default(),
+        longitude,
+        latitude,
+        e: fields.kilometres("e"),
+        n: fields.kilometres("n"),
+        référentiel: Territoire::from_département(&département).référentiel(),
+        département,
+        insee: fields.string("insee").unwrap_or_default(),
+        commune: fields.string("commune").unwrap_or_default(),
+        voie_suivie: fields.string("voie_suivie").unwrap_or_default(),
+        voie_de: fields.non_empty_string("voie_de"),
+        voie_vers: fields.non_empty_string("voie_vers"),
+        voie_côté,
+        voie_pk: fields.comma_number("voie_pk"),
+        distance: fields.comma_number("distance"),
+        du_repère: fields.string("du_repere").unwrap_or_default(),
+        localisation: fields.non_empty_string("localisation"),
+        support: fields.string("support").unwrap_or_default(),
+        partie_support: fields.non_empty_string("partie_support"),
+        repèrement_horizontal: fields.string("reperement_horizontal"),
+        repèrement_vertical: fields.string("reperement_vertical"),
+        hors_ign: "".to_string(),
+        remarques: fields.string("remarques").unwrap_or_default(),
+        exploitabilité_gps,
+        site_géodésique: sit_no_from_géod_info(&géod_info),
+        géod_info,
+        triplet_cid: None,
+        primordial_cid: None,
+    })
+}
+
+/// Returns the ngf.json objects contained in a legacy point file: a plain object, a GeoJSON
+/// feature, or a GeoJSON feature collection (as written by `ign-nivf sauvematricule`)
+fn legacy_points(value: Value) -> Vec<Map<String, Value>> {
+    match value {
+        Value::Object(mut object) => match object.get("type").and_then(Value::as_str) {
+            Some(geojson_type) if geojson_type.eq_ignore_ascii_case("featurecollection") => {
+                match object.remove("features") {
+                    Some(Value::Array(features)) => {
+                        features.into_iter().flat_map(legacy_points).collect()
+                    }
+                    _ => vec![],
+                }
+            }
+            Some("Feature") => match object.remove("properties") {
+                Some(Value::Object(properties)) => vec![properties],
+                _ => vec![],
+            },
+            _ => vec![object],
+        },
+        Value::Array(values) => values.into_iter().flat_map(legacy_points).collect(),
+        _ => vec![],
+    }
+}
+
+/// Imports one legacy point file. Returns the ids of the imported points. The files of
+/// data/points/(type) are named after the matricule of their point, which is used when the point
+/// has none and `named` is true
+fn import_point_file(
+    path: &Path,
+    named: bool,
+    store: &mut dyn Store,
+    report: &mut ImportReport,
+) -> StoreResult<Vec<String>> {
+    let mut ids: Vec<String> = vec![];
+    let mut points = legacy_points(serde_json::from_str(fs::read_to_string(path)?.as_str())?);
+    if let ([point], Some(stem), true) = (points.as_mut_slice(), path.file_stem(), named) {
+        point
+            .entry("matricule")
+            .or_insert_with(|| Value::String(stem.to_string_lossy().to_string()));
+    }
+    for point in points {
+        let mut fields = LegacyFields::new(point, path);
+        match repère_from_legacy(&mut fields) {
+            Ok(repère) => {
+                let objet = Objet::Ngf(Box::new(repère));
+                store.save_objet(&objet)?;
+                ids.push(objet.object_id());
+                report.objets += 1;
+            }
+            Err(err) => fields.report("matricule", Value::Null, err.as_str()),
+        }
+        report.unmapped.append(&mut fields.finish());
+    }
+    Ok(ids)
+}
+
+/// Imports a legacy visit directory (data/visites/(year)/(month)/(day)/(id)) and its photos
+fn import_visite_directory(
+    directory: &Path,
+    date: NaiveDate,
+    store: &mut dyn Store,
+    report: &mut ImportReport,
+) -> StoreResult<()> {
+    let path = directory.join("visite.json");
+    let Value::Object(object) = serde_json::from_str(fs::read_to_string(&path)?.as_str())? else {
+        return Err(format!("{} is not a JSON object", path.display()).into());
+    };
+    let mut fields = LegacyFields::new(object, &path);
+    // {"point": {"type": …, "matricule": …}, "whatWasSeen": …}
+    let point = match fields.take("point") {
+        Value::Object(point) => point,
+        Value::Null => Map::new(),
+        value => {
+            fields.report("point", value, "not an object");
+            Map::new()
+        }
+    };
+    let mut point = LegacyFields::new(point, &path);
+    let point_type = point.string("type").unwrap_or("ngf".to_string());
+    let matricule = point.non_empty_string("matricule");
+    for mut field in point.finish() {
+        field.champ = format!("point.{}", field.champ);
+        fields.unmapped.push(field);
+    }
+    let Some(matricule) = matricule else {
+        fields.report(
+            "point.matricule",
+            Value::Null,
+            "the visit has no point, it was skipped",
+        );
+        report.unmapped.append(&mut fields.finish());
+        return Ok(());
+    };
+    if point_type.to_lowercase() != "ngf" {
+        fields.report(
+            "point.type",
+            Value::String(point_type),
+            "only NGF points can be imported",
+        );
+        report.unmapped.append(&mut fields.finish());
+        return Ok(());
+    }
+    // The visit is linked to the imported benchmark, whose id holds its cid
+    let objet = match matricule
+        .parse::<ngf::Matricule>()
+        .map_err(|err| err.to_string())
+        .and_then(|matricule| {
+            store
+                .id_of_matricule(&matricule)
+                .map_err(|err| err.to_string())
+        }) {
+        Ok(objet) => objet,
+        Err(err) => {
+            fields.report("point.matricule", Value::String(matricule), &err);
+            report.unmapped.append(&mut fields.finish());
+            return Ok(());
+        }
+    };
+    // What was seen is a state of ngf.json, or free text that is kept in the remarques
+    let (état, remarques) = match fields.non_empty_string("whatWasSeen") {
+        Some(seen) => match état_from_label(&seen) {
+            Some((code, true)) => (Some(code), "".to_string()),
+            Some((code, false)) => (Some(code), seen),
+            None => (None, seen),
+        },
+        None => (None, "".to_string()),
+    };
+    report.unmapped.append(&mut fields.finish());
+
+    // The visits already imported are remembered in the options, by the path of their directory
+    let clé = format!(
+        "{LEGACY_VISIT_OPTION}{}",
+        directory.canonicalize()?.display()
+    );
+    let mut options = store.options()?;
+    let visite_id = match options
+        .valeurs
+        .get(&clé)
+        .and_then(|id| id.parse::<u64>().ok())
+    {
+        Some(id) if store.visites()?.iter().any(|visite| visite.id == id) => {
+            report.déjà_importées += 1;
+            id
+        }
+        _ => {
+            let visite = Visite {
+                id: store.next_visite_id()?,
+                objet: objet.to_string(),
+                date,
+                heure: None,
+                état,
+                remarques,
+                à_vérifier: false,
+            };
+            store.save_visite(&visite)?;
+            options.valeurs.insert(clé, visite.id.to_string());
+            store.save_options(&options)?;
+            report.visites += 1;
+            visite.id
+        }
+    };
+
+    let photos = store.photos()?;
+    for entry in fs::read_dir(directory)? {
+        let photo_path = entry?.path();
+        if !photo_path.is_file() || photo_path.file_name() == Some("visite.json".as_ref()) {
+            continue;
+        }
+        // photo-(id).json describe the photos (path and caption), their format is not known
+        if photo_path.extension() == Some("json".as_ref()) {
+            report.unmapped.push(UnmappedField {
+                fichier: photo_path,
+                champ: "".to_string(),
+                valeur: Value::Null,
+                raison: "photo description, not imported".to_string(),
+            });
+            continue;
+        }
+        let fichier = photo_path.canonicalize()?.to_string_lossy().to_string();
+        if photos.iter().any(|photo| photo.fichier == fichier) {
+            continue;
+        }
+        store.save_photo(&Photo {
+            id: store.next_photo_id()?,
+            visite: visite_id,
+            fichier,
+            légende: "".to_string(),
+        })?;
+        report.photos += 1;
+    }
+    Ok(())
+}
+
+/// The numbered subdirectories of a directory of the visits (years, months or days). The other
+/// entries are reported and skipped
+fn numbered_directories(
+    directory: &Path,
+    report: &mut ImportReport,
+) -> StoreResult<Vec<(u32, PathBuf)>> {
+    let mut directories: Vec<(u32, PathBuf)> = vec![];
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let number = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u32>().ok());
+        match number {
+            Some(number) if path.is_dir() => directories.push((number, path)),
+            _ => report.unmapped.push(UnmappedField {
+                fichier: path,
+                champ: "".to_string(),
+                valeur: Value::Null,
+                raison: "not a visit directory, skipped".to_string(),
+            }),
+        }
+    }
+    directories.sort();
+    Ok(directories)
+}
+
+/// Returns the directories of the legacy saves in the config directory (the Python CLI has no
+/// profiles)
+pub fn legacy_saves() -> StoreResult<Vec<PathBuf>> {
+    let mut saves: Vec<PathBuf> = vec![];
+    let config_dir_path = PathBuf::from(base_config_directory()?);
+    if !config_dir_path.is_dir() {
+        return Ok(saves);
+    }
+    for entry in fs::read_dir(config_dir_path)? {
+        let path = entry?.path();
+        // The Rust store (see open_store) is not a legacy save
+        if path.is_dir() && path.join("data").is_dir() && !path.join("version").exists() {
+            saves.push(path);
+        }
+    }
+    saves.sort();
+    Ok(saves)
+}
+
+/// Imports a save of the Python CLI into the store. `path` is either the directory of the save,
+/// or a GeoJSON file written by `ign-nivf sauvematricule` or `ign-nivf sauveinsee`
+pub fn import_legacy_save(path: &Path, store: &mut dyn Store) -> StoreResult<ImportReport> {
+    let mut report = ImportReport::default();
+    if path.is_file() {
+        import_point_file(path, false, store, &mut report)?;
+        return Ok(report);
+    }
+
+    let points_directory = path.join("data").join("points");
+    if points_directory.is_dir() {
+        for type_entry in fs::read_dir(&points_directory)? {
+            let type_path = type_entry?.path();
+            if !type_path.is_dir() {
+                continue;
+            }
+            let is_ngf = type_path.file_name() == Some("ngf".as_ref());
+            for entry in fs::read_dir(&type_path)? {
+                let point_path = entry?.path();
+                if is_ngf {
+                    import_point_file(&point_path, true, store, &mut report)?;
+                } else {
+                    report.unmapped.push(UnmappedField {
+                        fichier: point_path,
+                        champ: "type".to_string(),
+                        valeur: Value::String(
+                            type_path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string(),
+                        ),
+                        raison: "only NGF points can be imported".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    let visites_directory = path.join("data").join("visites");
+    if visites_directory.is_dir() {
+        // (year)/(month)/(day)/(id)
+        for (year, year_path) in numbered_directories(&visites_directory, &mut report)? {
+            for (month, month_path) in numbered_directories(&year_path, &mut report)? {
+                for (day, day_path) in numbered_directories(&month_path, &mut report)? {
+                    let date = NaiveDate::from_ymd_opt(year as i32, month, day);
+                    // The ids of the visits are arbitrary names
+                    let mut directories: Vec<PathBuf> = vec![];
+                    for entry in fs::read_dir(&day_path)? {
+                        directories.push(entry?.path());
+                    }
+                    directories.sort();
+                    for directory in directories {
+                        let Some(date) = date.filter(|_| directory.join("visite.json").is_file())
+                        else {
+                            report.unmapped.push(UnmappedField {
+                                fichier: directory,
+                                champ: "".to_string(),
+                                valeur: Value::Null,
+                                raison: "invalid date or no visite.json, skipped".to_string(),
+                            });
+                            continue;
+                        };
+                        import_visite_directory(&directory, date, store, &mut report)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[test]
+fn test_import_legacy_save() {
+    // A copy, as the canonical paths of the imported directories are remembered
+    let path = temporary_directory("legacy-save");
+    let template = Path::new(env!("CARGO_MANIFEST_DIR")).join("template_db");
+    let visite = Path::new("data/visites/2025/07/12/thisisanidapparently");
+    for fichier in [
+        Path::new("data/points/ngf/T'.D.S3 - 17.json"),
+        &visite.join("visite.json"),
+        &visite.join("photo.jpg"),
+    ] {
+        fs::create_dir_all(path.join(fichier).parent().unwrap()).unwrap();
+        fs::copy(template.join(fichier), path.join(fichier)).unwrap();
+    }
+
+    let mut store = MemoryStore::default();
+    let report = import_legacy_save(&path, &mut store).unwrap();
+    assert_eq!((report.objets, report.visites, report.photos), (1, 1, 1));
+    let champs: Vec<&str> = report
+        .unmapped
+        .iter()
+        .map(|field| field.champ.as_str())
+        .collect();
+    assert_eq!(champs, vec!["cid"]);
+
+    let Some(Objet::Ngf(repère)) = store.objet("ngf/T'.D.S3 - 17").unwrap() else {
+        panic!("The benchmark was not imported");
+    };
+    assert_eq!(repère.matricule.to_string(), "T'.D.S3 - 17");
+    let visites = store.visites_of("ngf/T'.D.S3 - 17").unwrap();
+    assert_eq!(
+        visites[0].date,
+        NaiveDate::from_ymd_opt(2025, 7, 12).unwrap()
+    );
+    assert_eq!(visites[0].état, Some(RnÉtatCode::BonÉtat));
+    assert_eq!(visites[0].remarques, "");
+    assert!(store.photos().unwrap()[0].fichier.ends_with("photo.jpg"));
+
+    // Importing again does not duplicate the visits and the photos
+    let report = import_legacy_save(&path, &mut store).unwrap();
+    assert_eq!(
+        (report.visites, report.photos, report.déjà_importées),
+        (0, 0, 1)
+    );
+    assert_eq!(
+        (
+            store.visites().unwrap().len(),
+            store.photos().unwrap().len()
+        ),
+        (1, 1)
+    );
+    fs::remove_dir_all(path).unwrap();
+}
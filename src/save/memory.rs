@@ -0,0 +1,146 @@
+//! A store that keeps everything in memory. Useful for tests
+use super::*;
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    pub save: SaveJSON,
+    /// The user-defined point types, since they are not read from the config directory
+    pub types: Vec<PointType>,
+}
+
+impl MemoryStore {
+    pub fn new(save: SaveJSON) -> MemoryStore {
+        MemoryStore {
+            save,
+            types: vec![],
+        }
+    }
+}
+
+impl Store for MemoryStore {
+    fn objets(&self) -> StoreResult<Vec<Objet>> {
+        Ok(self.save.objets.clone())
+    }
+
+    fn save_objet(&mut self, objet: &Objet) -> StoreResult<()> {
+        let id = self.check_objet(objet)?.to_string();
+        match self
+            .save
+            .objets
+            .iter_mut()
+            .find(|stored| stored.object_id() == id)
+        {
+            Some(stored) => *stored = objet.clone(),
+            None => self.save.objets.push(objet.clone()),
+        }
+        Ok(())
+    }
+
+    fn remove_objet(&mut self, id: &str) -> StoreResult<()> {
+        self.save.objets.retain(|objet| objet.object_id() != id);
+        Ok(())
+    }
+
+    fn visites(&self) -> StoreResult<Vec<Visite>> {
+        Ok(self.save.visites.clone())
+    }
+
+    fn save_visite(&mut self, visite: &Visite) -> StoreResult<()> {
+        match self
+            .save
+            .visites
+            .iter_mut()
+            .find(|stored| stored.id == visite.id)
+        {
+            Some(stored) => *stored = visite.clone(),
+            None => self.save.visites.push(visite.clone()),
+        }
+        Ok(())
+    }
+
+    fn remove_visite(&mut self, id: u64) -> StoreResult<()> {
+        self.save.visites.retain(|visite| visite.id != id);
+        Ok(())
+    }
+
+    fn photos(&self) -> StoreResult<Vec<Photo>> {
+        Ok(self.save.photos.clone())
+    }
+
+    fn save_photo(&mut self, photo: &Photo) -> StoreResult<()> {
+        match self
+            .save
+            .photos
+            .iter_mut()
+            .find(|stored| stored.id == photo.id)
+        {
+            Some(stored) => *stored = photo.clone(),
+            None => self.save.photos.push(photo.clone()),
+        }
+        Ok(())
+    }
+
+    fn remove_photo(&mut self, id: u64) -> StoreResult<()> {
+        self.save.photos.retain(|photo| photo.id != id);
+        Ok(())
+    }
+
+    fn options(&self) -> StoreResult<Options> {
+        Ok(self.save.options.clone())
+    }
+
+    fn save_options(&mut self, options: &Options) -> StoreResult<()> {
+        self.save.options = options.clone();
+        Ok(())
+    }
+
+    fn schema_version(&self) -> StoreResult<Option<u32>> {
+        Ok(self.save.version)
+    }
+
+    fn set_schema_version(&mut self, version: u32) -> StoreResult<()> {
+        self.save.version = Some(version);
+        Ok(())
+    }
+
+    fn raw_save(&self) -> StoreResult<RawSave> {
+        Ok(RawSave {
+            objets: self
+                .save
+                .objets
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<serde_json::Value>, serde_json::Error>>()?,
+            visites: self
+                .save
+                .visites
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<serde_json::Value>, serde_json::Error>>()?,
+            photos: self
+                .save
+                .photos
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<serde_json::Value>, serde_json::Error>>()?,
+            options: serde_json::to_value(&self.save.options)?,
+        })
+    }
+
+    fn clear(&mut self) -> StoreResult<()> {
+        self.save = SaveJSON {
+            version: self.save.version,
+            ..SaveJSON::default()
+        };
+        Ok(())
+    }
+
+    fn backup(&self) -> StoreResult<Option<String>> {
+        // Nothing is ever written on the disk
+        Ok(None)
+    }
+
+    fn types(&self) -> StoreResult<Vec<PointType>> {
+        Ok(self.types.clone())
+    }
+}
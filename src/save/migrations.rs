@@ -0,0 +1,311 @@
+//! Versioning of the save format. Every store records the version of the format it was written
+//! with, and is upgraded step by step through the registered migrations when it is opened
+use super::*;
+use serde_json::Value;
+
+/// Version of the save format written by this version of Géodésie de Bureau
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// A whole save, as raw JSON. Migrations work on this, since old data may not match the
+/// current structs anymore
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RawSave {
+    pub objets: Vec<Value>,
+    pub visites: Vec<Value>,
+    pub photos: Vec<Value>,
+    pub options: Value,
+}
+
+/// Upgrades a save from version `from` to version `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut RawSave) -> StoreResult<()>,
+}
+
+/// All the migrations, sorted by version. A new one must be added every time SCHEMA_VERSION
+/// is increased
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "Records the key field of the points of user-defined types",
+        apply: |raw_save| {
+            for objet in raw_save.objets.iter_mut() {
+                let Some(point) = objet.get_mut("Autre").and_then(Value::as_object_mut) else {
+                    continue;
+                };
+                // Until then, the first field identified the point
+                let clé = point
+                    .get("champs")
+                    .and_then(Value::as_object)
+                    .and_then(|champs| champs.keys().next().cloned())
+                    .ok_or("A point of a user-defined type has no field")?;
+                point.insert("clé".to_string(), Value::String(clé));
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        from: 2,
+        description: "Adds the cid to the ids of the NGF benchmarks, which homonyms share",
+        apply: |raw_save| {
+            let mut ids: BTreeMap<String, String> = BTreeMap::new();
+            for objet in &raw_save.objets {
+                let Some(repère) = objet.get("NGF") else {
+                    continue;
+                };
+                let matricule = repère
+                    .get("matricule")
+                    .and_then(Value::as_str)
+                    .ok_or("A benchmark has no matricule")?;
+                match repère.get("cid").and_then(Value::as_i64) {
+                    Some(0) | None => (),
+                    Some(cid) => {
+                        ids.insert(format!("ngf/{matricule}"), format!("ngf/{matricule}@{cid}"));
+                    }
+                }
+            }
+            for visite in raw_save.visites.iter_mut() {
+                let visite = visite.as_object_mut().ok_or("A visit is not an object")?;
+                let id = visite
+                    .get("objet")
+                    .and_then(Value::as_str)
+                    .and_then(|id| ids.get(id));
+                if let Some(id) = id {
+                    visite.insert("objet".to_string(), Value::String(id.clone()));
+                }
+            }
+            Ok(())
+        },
+    },
+];
+
+/// Upgrades the store to SCHEMA_VERSION. Returns the version the store was at before, if it
+/// was upgraded
+pub fn upgrade(store: &mut dyn Store) -> StoreResult<Option<u32>> {
+    upgrade_with(store, MIGRATIONS, SCHEMA_VERSION)
+}
+
+/// Upgrades the store to `target`, using `migrations`
+pub fn upgrade_with(
+    store: &mut dyn Store,
+    migrations: &[Migration],
+    target: u32,
+) -> StoreResult<Option<u32>> {
+    let version = match store.schema_version()? {
+        Some(version) => version,
+        None => {
+            let raw_save = store.raw_save()?;
+            if raw_save.objets.is_empty()
+                && raw_save.visites.is_empty()
+                && raw_save.photos.is_empty()
+            {
+                // A brand new store
+                store.set_schema_version(target)?;
+                return Ok(None);
+            }
+            // Saves written before the format was versioned
+            1
+        }
+    };
+    if version > target {
+        return Err(format!(
+            "This save uses version {version} of the save format, but this version of \
+            Géodésie de Bureau only knows versions up to {target}. Please update Géodésie de Bureau"
+        )
+        .into());
+    }
+    if version == target {
+        return Ok(None);
+    }
+
+    let mut raw_save = store.raw_save()?;
+    for from in version..target {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == from)
+            .ok_or(format!(
+                "No migration from version {from} of the save format"
+            ))?;
+        (migration.apply)(&mut raw_save).map_err(|err| {
+            format!(
+                "Migration from version {from} ({}) failed: {err}",
+                migration.description
+            )
+        })?;
+    }
+    let save = SaveJSON {
+        version: Some(target),
+        options: serde_json::from_value::<Options>(raw_save.options)?,
+        objets: raw_save
+            .objets
+            .into_iter()
+            .map(serde_json::from_value::<Objet>)
+            .collect::<Result<Vec<Objet>, serde_json::Error>>()?,
+        visites: raw_save
+            .visites
+            .into_iter()
+            .map(serde_json::from_value::<Visite>)
+            .collect::<Result<Vec<Visite>, serde_json::Error>>()?,
+        photos: raw_save
+            .photos
+            .into_iter()
+            .map(serde_json::from_value::<Photo>)
+            .collect::<Result<Vec<Photo>, serde_json::Error>>()?,
+    };
+
+    // The upgraded save is first written to a store in memory, which checks every point against
+    // the types of the store: an invalid point stops the upgrade before the store is cleared
+    let mut upgraded = MemoryStore {
+        types: store.types()?,
+        ..MemoryStore::default()
+    };
+    migrate(&MemoryStore::new(save), &mut upgraded).map_err(|err| {
+        format!("The save can not be upgraded to version {target} of the save format: {err}")
+    })?;
+    let backup = store.backup()?;
+    store.clear()?;
+    if let Err(err) = migrate(&upgraded, store).and_then(|()| store.set_schema_version(target)) {
+        return Err(match backup {
+            Some(backup) => format!(
+                "The upgrade of the save failed while it was rewritten: {err}. The save before \
+                the upgrade was copied to {backup}"
+            ),
+            None => format!("The upgrade of the save failed while it was rewritten: {err}"),
+        }
+        .into());
+    }
+    Ok(Some(version))
+}
+
+#[cfg(test)]
+const TEST_MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Renames 'notes' into 'remarques' in the visits",
+    apply: |raw_save| {
+        for visite in raw_save.visites.iter_mut() {
+            let visite = visite.as_object_mut().ok_or("A visit is not an object")?;
+            let notes = visite.remove("notes").unwrap_or_default();
+            visite.insert("remarques".to_string(), notes);
+        }
+        Ok(())
+    },
+}];
+
+#[test]
+fn test_upgrade() {
+    let mut store = SqliteStore::open_in_memory().unwrap();
+    assert_eq!(upgrade(&mut store).unwrap(), None);
+    assert_eq!(store.schema_version().unwrap(), Some(SCHEMA_VERSION));
+
+    store.set_schema_version(SCHEMA_VERSION + 1).unwrap();
+    let err = upgrade(&mut store).unwrap_err();
+    assert!(err.to_string().contains("Please update"));
+    assert!(upgrade_with(&mut MemoryStore::default(), &[], 3).is_ok());
+}
+
+#[test]
+fn test_upgrade_json_dir_store() {
+    let path = temporary_directory("upgrade-json-dir-store");
+    let mut store = JsonDirStore::open(&path).unwrap();
+    store
+        .save_objet(&Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))))
+        .unwrap();
+    // A visit written with version 1, where 'remarques' was called 'notes'
+    let visite_directory = path.join("data/visites/2024/08/15/1");
+    fs::create_dir_all(&visite_directory).unwrap();
+    fs::write(
+        visite_directory.join("visite.json"),
+        r#"{"id": 1, "objet": "ngf/T'.D.S3 - 50", "date": "2024-08-15", "état": "E", "notes": "RAS"}"#,
+    )
+    .unwrap();
+    // A store written before the version was recorded
+    assert_eq!(store.schema_version().unwrap(), None);
+    assert!(store.visites().is_err());
+    assert_eq!(
+        upgrade_with(&mut store, TEST_MIGRATIONS, 2).unwrap(),
+        Some(1)
+    );
+    assert_eq!(store.schema_version().unwrap(), Some(2));
+    assert_eq!(store.objets().unwrap().len(), 1);
+    assert_eq!(store.visites().unwrap()[0].remarques, "RAS");
+
+    let backup_prefix = format!("{}.backup-v1-", path.file_name().unwrap().to_string_lossy());
+    let backups: Vec<std::path::PathBuf> = fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|backup| {
+            backup
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(backup_prefix.as_str())
+        })
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert!(backups[0]
+        .join("data/visites/2024/08/15/1/visite.json")
+        .exists());
+    fs::remove_dir_all(&backups[0]).unwrap();
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_upgrade_invalid_point() {
+    let point_type = PointType::from_definition("borne", r#"{"numero": "str"}"#).unwrap();
+    let point = point_type
+        .point_from_input(&[("numero".to_string(), "B-12".to_string())])
+        .unwrap();
+    let mut store = MemoryStore {
+        types: vec![point_type],
+        ..MemoryStore::default()
+    };
+    store
+        .save_objet(&Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))))
+        .unwrap();
+    store.save_objet(&Objet::Autre(point)).unwrap();
+    store.set_schema_version(1).unwrap();
+    // The type of the second point was removed since it was saved
+    store.types.clear();
+    let before = store.raw_save().unwrap();
+    assert!(upgrade_with(&mut store, TEST_MIGRATIONS, 2).is_err());
+    assert_eq!(store.raw_save().unwrap(), before);
+    assert_eq!(store.schema_version().unwrap(), Some(1));
+}
+
+#[test]
+fn test_migration_clé() {
+    let mut raw_save = RawSave {
+        objets: vec![serde_json::json!({"Autre": {
+            "type_name": "borne",
+            "champs": {"numero": "B-12", "commune": "Toulouse"}
+        }})],
+        ..Default::default()
+    };
+    (MIGRATIONS[0].apply)(&mut raw_save).unwrap();
+    let objet: Objet = serde_json::from_value(raw_save.objets.remove(0)).unwrap();
+    assert_eq!(objet.object_id(), "borne/B-12");
+}
+
+#[test]
+fn test_migration_cid() {
+    let mut repère = serde_json::to_value(repère_test("T'.D.S3 - 50")).unwrap();
+    let mut raw_save = RawSave {
+        objets: vec![serde_json::json!({ "NGF": repère.clone() })],
+        visites: vec![
+            serde_json::json!({"id": 1, "objet": "ngf/T'.D.S3 - 50"}),
+            serde_json::json!({"id": 2, "objet": "borne/B-12"}),
+        ],
+        ..Default::default()
+    };
+    (MIGRATIONS[1].apply)(&mut raw_save).unwrap();
+    assert_eq!(raw_save.visites[0]["objet"], "ngf/T'.D.S3 - 50@452592");
+    assert_eq!(raw_save.visites[1]["objet"], "borne/B-12");
+
+    // The benchmarks whose cid is unknown keep their id
+    repère["cid"] = 0.into();
+    raw_save.objets = vec![serde_json::json!({ "NGF": repère })];
+    raw_save.visites = vec![serde_json::json!({"id": 1, "objet": "ngf/T'.D.S3 - 50"})];
+    (MIGRATIONS[1].apply)(&mut raw_save).unwrap();
+    assert_eq!(raw_save.visites[0]["objet"], "ngf/T'.D.S3 - 50");
+}
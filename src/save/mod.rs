@@ -0,0 +1,664 @@
+//! Everything related to the persistence of points, visits, photos and options
+pub mod json_dir;
+pub mod legacy;
+pub mod memory;
+pub mod migrations;
+pub mod sqlite;
+
+pub use json_dir::JsonDirStore;
+pub use memory::MemoryStore;
+pub use migrations::{upgrade, RawSave, SCHEMA_VERSION};
+pub use sqlite::SqliteStore;
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use shellexpand;
+use std::{
+    collections::BTreeMap, error::Error, fmt, fs, path::PathBuf, result::Result, str::FromStr,
+    string::String, sync::OnceLock,
+};
+
+use crate::common_traits::{Identified, ObjectId};
+use crate::crue::RepèreDeCrue;
+use crate::custom_types::{self, PointPersonnalisé, PointType};
+use crate::geod::SiteGéodésique;
+use crate::ngf::{self, json_mappings::bbox::RnÉtatCode, Territoire};
+
+pub type StoreResult<T> = Result<T, Box<dyn Error>>;
+
+/// This struct should be able to serialize and deserialize a whole save
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct SaveJSON {
+    /// Version of the save format (see migrations::SCHEMA_VERSION)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    pub options: Options,
+    pub objets: Vec<Objet>,
+    pub visites: Vec<Visite>,
+    #[serde(default)]
+    pub photos: Vec<Photo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Options {
+    #[serde(flatten)]
+    pub valeurs: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Objet {
+    #[serde(rename = "NGF")]
+    Ngf(Box<ngf::json_mappings::repere::RepèreNivellement>),
+    /// An IGN geodetic site
+    Site(Box<SiteGéodésique>),
+    /// A flood mark
+    Crue(Box<RepèreDeCrue>),
+    /// A point of a user-defined type (see custom_types)
+    Autre(PointPersonnalisé),
+}
+
+impl Objet {
+    /// Name of the type of the point (used for instance as a directory name)
+    pub fn type_name(&self) -> &str {
+        match self {
+            Objet::Ngf(_) => "ngf",
+            Objet::Site(_) => "geod",
+            Objet::Crue(_) => "crue",
+            Objet::Autre(point) => point.type_name.as_str(),
+        }
+    }
+
+    /// The id of the point, or an error if it can not identify the point in a store
+    pub fn checked_id(&self) -> StoreResult<ObjectId> {
+        Ok(ObjectId::from_str(self.object_id().as_str())
+            .map_err(|err| format!("This point can not be stored: {err}"))?)
+    }
+
+    /// (longitude, latitude) of the point, if it is known
+    pub fn coordonnées(&self) -> Option<(f64, f64)> {
+        match self {
+            Objet::Ngf(repère) => Some((repère.longitude, repère.latitude)),
+            Objet::Site(site) => site.coordonnées(),
+            Objet::Crue(crue) => Some((crue.longitude, crue.latitude)),
+            Objet::Autre(point) => point.coordonnées(),
+        }
+    }
+}
+
+impl Identified for Objet {
+    fn object_id(&self) -> String {
+        match self {
+            Objet::Ngf(repère) => repère.object_id(),
+            Objet::Site(site) => site.object_id(),
+            Objet::Crue(crue) => crue.object_id(),
+            Objet::Autre(point) => point.object_id(),
+        }
+    }
+}
+
+impl fmt::Display for Objet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objet::Ngf(repère) => write!(f, "{repère}"),
+            Objet::Site(site) => write!(f, "{site}"),
+            Objet::Crue(crue) => write!(f, "{crue}"),
+            Objet::Autre(point) => write!(f, "{point}"),
+        }
+    }
+}
+
+/// A visit of a point, at a given date
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Visite {
+    pub id: u64,
+    /// The object id of the visited point (see Identified)
+    pub objet: String,
+    pub date: NaiveDate,
+    /// The local time of the visit, when known (such as estimated from a GPS track)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heure: Option<NaiveTime>,
+    /// The state in which the point was found
+    pub état: Option<RnÉtatCode>,
+    pub remarques: String,
+    /// The visit was suggested from a GPS track, and is yet to be confirmed by the user
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub à_vérifier: bool,
+}
+
+impl Identified for Visite {
+    fn object_id(&self) -> String {
+        format!("visite/{}", self.id)
+    }
+}
+
+/// A photo taken during a visit
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Photo {
+    pub id: u64,
+    pub visite: u64,
+    /// Path to the image file
+    pub fichier: String,
+    pub légende: String,
+}
+
+impl Identified for Photo {
+    fn object_id(&self) -> String {
+        format!("photo/{}", self.id)
+    }
+}
+
+/// Any way of storing points, visits, photos and options
+pub trait Store {
+    fn objets(&self) -> StoreResult<Vec<Objet>>;
+    /// Adds the point to the store, or replaces the point with the same id
+    fn save_objet(&mut self, objet: &Objet) -> StoreResult<()>;
+    fn remove_objet(&mut self, id: &str) -> StoreResult<()>;
+
+    fn visites(&self) -> StoreResult<Vec<Visite>>;
+    /// Adds the visit to the store, or replaces the visit with the same id
+    fn save_visite(&mut self, visite: &Visite) -> StoreResult<()>;
+    fn remove_visite(&mut self, id: u64) -> StoreResult<()>;
+
+    fn photos(&self) -> StoreResult<Vec<Photo>>;
+    /// Adds the photo to the store, or replaces the photo with the same id
+    fn save_photo(&mut self, photo: &Photo) -> StoreResult<()>;
+    fn remove_photo(&mut self, id: u64) -> StoreResult<()>;
+
+    fn options(&self) -> StoreResult<Options>;
+    fn save_options(&mut self, options: &Options) -> StoreResult<()>;
+
+    /// Version of the format of the stored data, or None if it was never written
+    fn schema_version(&self) -> StoreResult<Option<u32>>;
+    fn set_schema_version(&mut self, version: u32) -> StoreResult<()>;
+    /// Reads everything as raw JSON, without checking that it matches the current structs
+    fn raw_save(&self) -> StoreResult<RawSave>;
+    /// Removes all the points, visits, photos and options
+    fn clear(&mut self) -> StoreResult<()>;
+    /// Copies the whole store somewhere safe, and returns where, if the store can be backed up
+    fn backup(&self) -> StoreResult<Option<String>>;
+
+    /// The user-defined point types the points of this store are checked against
+    fn types(&self) -> StoreResult<Vec<PointType>> {
+        load_types()
+    }
+
+    /// Returns the id of the point, or an error if the point can not be saved in this store: its
+    /// id is invalid, or it does not match its user-defined type
+    fn check_objet(&self, objet: &Objet) -> StoreResult<ObjectId> {
+        let id = objet.checked_id()?;
+        if let Objet::Autre(point) = objet {
+            let types = self.types()?;
+            let point_type = types
+                .iter()
+                .find(|point_type| point_type.name == point.type_name)
+                .ok_or(format!("Unknown point type: '{}'", point.type_name))?;
+            if point.clé != point_type.key_field() {
+                return Err(format!(
+                    "{id} is identified by '{}', but the type '{}' by '{}'",
+                    point.clé,
+                    point_type.name,
+                    point_type.key_field()
+                )
+                .into());
+            }
+            point_type.validate(&point.champs).map_err(|errors| {
+                format!(
+                    "{id} is not a valid '{}': {}",
+                    point_type.name,
+                    errors.join(", ")
+                )
+            })?;
+        }
+        Ok(id)
+    }
+
+    /// The id of the saved NGF benchmark with this matricule. If none is saved, the id is the
+    /// matricule alone. Homonyms can not be told apart by their matricule, their ids are then
+    /// listed in the error
+    fn id_of_matricule(&self, matricule: &ngf::Matricule) -> StoreResult<ObjectId> {
+        let ids: Vec<ObjectId> = self
+            .objets()?
+            .iter()
+            .filter(|objet| matches!(objet, Objet::Ngf(repère) if &repère.matricule == matricule))
+            .map(Objet::checked_id)
+            .collect::<StoreResult<Vec<ObjectId>>>()?;
+        match ids.as_slice() {
+            [] => Ok(ObjectId::new("ngf", matricule.as_str())),
+            [id] => Ok(id.clone()),
+            _ => Err(format!(
+                "Several benchmarks have the matricule '{matricule}', use one of their ids: {}",
+                ids.iter()
+                    .map(ObjectId::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+            .into()),
+        }
+    }
+
+    fn objet(&self, id: &str) -> StoreResult<Option<Objet>> {
+        Ok(self
+            .objets()?
+            .into_iter()
+            .find(|objet| objet.object_id() == id))
+    }
+
+    /// Returns the visits of a point, sorted by date
+    fn visites_of(&self, objet_id: &str) -> StoreResult<Vec<Visite>> {
+        let mut visites: Vec<Visite> = self
+            .visites()?
+            .into_iter()
+            .filter(|visite| visite.objet == objet_id)
+            .collect();
+        visites.sort_by_key(|visite| (visite.date, visite.id));
+        Ok(visites)
+    }
+
+    fn photos_of(&self, visite_id: u64) -> StoreResult<Vec<Photo>> {
+        Ok(self
+            .photos()?
+            .into_iter()
+            .filter(|photo| photo.visite == visite_id)
+            .collect())
+    }
+
+    /// Returns an id that no visit uses yet
+    fn next_visite_id(&self) -> StoreResult<u64> {
+        Ok(self
+            .visites()?
+            .iter()
+            .map(|visite| visite.id + 1)
+            .max()
+            .unwrap_or(1))
+    }
+
+    /// Returns an id that no photo uses yet
+    fn next_photo_id(&self) -> StoreResult<u64> {
+        Ok(self
+            .photos()?
+            .iter()
+            .map(|photo| photo.id + 1)
+            .max()
+            .unwrap_or(1))
+    }
+}
+
+/// Copies everything in `from` into `to`. Data already in `to` with the same ids is replaced
+pub fn migrate(from: &dyn Store, to: &mut dyn Store) -> StoreResult<()> {
+    for objet in from.objets()? {
+        to.save_objet(&objet)?;
+    }
+    for visite in from.visites()? {
+        to.save_visite(&visite)?;
+    }
+    for photo in from.photos()? {
+        to.save_photo(&photo)?;
+    }
+    to.save_options(&from.options()?)?;
+    Ok(())
+}
+
+/// The different implementations of Store
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Backend::Json => "json",
+                Backend::Sqlite => "sqlite",
+            }
+        )
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Backend::Json),
+            "sqlite" => Ok(Backend::Sqlite),
+            _ => Err(format!(
+                "Unknown backend '{s}' (expected 'json' or 'sqlite')"
+            )),
+        }
+    }
+}
+
+/// Configuration of the program, stored in config.json in the config directory
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub backend: Backend,
+    /// The territory in which the benchmarks are searched by default
+    #[serde(default)]
+    pub territoire: Territoire,
+}
+
+/// The profile in use. Each profile has its own configuration and store, in the `profiles`
+/// directory of the config directory; without profile, they are in the config directory itself
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Uses the profile for the rest of the program. It must be chosen before anything is read from
+/// the config directory
+pub fn set_profile(name: &str) -> StoreResult<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid profile name: '{name}' (only letters, digits, '-' and '_' are allowed)"
+        )
+        .into());
+    }
+    PROFILE
+        .set(name.to_string())
+        .map_err(|_| "The profile is already chosen".into())
+}
+
+/// The profile in use, if any
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// The names of the existing profiles
+pub fn profiles() -> StoreResult<Vec<String>> {
+    let directory = PathBuf::from(base_config_directory()?).join("profiles");
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut profiles: Vec<String> = vec![];
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            profiles.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// The config directory of the profile in use (see set_profile)
+pub fn determine_config_directory() -> Result<String, String> {
+    let directory = base_config_directory()?;
+    Ok(match profile() {
+        Some(profile) => PathBuf::from(directory)
+            .join("profiles")
+            .join(profile)
+            .to_string_lossy()
+            .to_string(),
+        None => directory,
+    })
+}
+
+/// The config directory, without profile
+pub(crate) fn base_config_directory() -> Result<String, String> {
+    match std::env::consts::OS {
+        "ios" | "android" => {
+            Err("Sorry, 'ios' and 'android' are not supported by Géodésie de Bureau".to_string())
+        }
+
+        "macos" => {
+            Ok(shellexpand::tilde("~/Library/Preferences/org.jd-develop.geodesie/").to_string())
+        }
+
+        "windows" => {
+            let appdata = match std::env::var("APPDATA") {
+                Ok(value) => value,
+                Err(err) => return Err(err.to_string()),
+            };
+            Ok(format!("{}\\jd-develop\\geodesie", appdata).to_string())
+        }
+
+        _ => Ok(shellexpand::tilde("~/.config/jd-develop/geodesie").to_string()),
+    }
+}
+
+/// Reads the configuration on the disk, or returns the default one if there is none
+pub fn read_config() -> StoreResult<Config> {
+    let config_dir_path = determine_config_directory()?;
+    match fs::read_to_string(config_dir_path + "/config.json") {
+        Ok(content) => Ok(serde_json::from_str::<Config>(content.as_str())?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the configuration on the disk
+pub fn write_config(config: &Config) -> StoreResult<()> {
+    let config_dir_path = determine_config_directory()?;
+    fs::create_dir_all(&config_dir_path)?;
+    fs::write(
+        config_dir_path + "/config.json",
+        serde_json::to_string_pretty(config)?,
+    )?;
+    Ok(())
+}
+
+/// The directory that contains the definitions of the user-defined point types
+pub fn types_directory() -> StoreResult<PathBuf> {
+    Ok(PathBuf::from(determine_config_directory()?)
+        .join("save")
+        .join("data")
+        .join("types"))
+}
+
+/// Reads the user-defined point types
+pub fn load_types() -> StoreResult<Vec<PointType>> {
+    custom_types::load_types(&types_directory()?)
+}
+
+/// Opens the store of the given backend, in the config directory, and upgrades it to the current
+/// format if needed
+pub fn open_store(backend: Backend) -> StoreResult<Box<dyn Store>> {
+    let config_dir_path = determine_config_directory()?;
+    fs::create_dir_all(&config_dir_path)?; // Just to avoid some errors ;)
+    let mut store: Box<dyn Store> = match backend {
+        Backend::Json => Box::new(JsonDirStore::open(config_dir_path + "/save")?),
+        Backend::Sqlite => Box::new(SqliteStore::open(config_dir_path + "/save.sqlite")?),
+    };
+    upgrade(store.as_mut())?;
+    Ok(store)
+}
+
+#[cfg(test)]
+pub(crate) fn repère_test(matricule: &str) -> ngf::RepèreNivellement {
+    use ngf::json_mappings::bbox::*;
+    ngf::RepèreNivellement {
+        matricule: matricule.parse().unwrap(),
+        cid: 452592,
+        fiche_url: "https://geodesie.ign.fr/fiches/index.php?module=e&action=fichepdf&source=gp&rn_cid=452592&geo_cid=0".to_string(),
+        système_altimétrique: NivfReaCode::NgfIgn1969,
+        altitude: "125,719".to_string(),
+        altitude_complémentaire: "".to_string(),
+        altitude_type: HTypeCode::AltitudeNormale,
+        dernière_observation: "01/01/1920".to_string(),
+        nouveau_calcul: "1984".to_string(),
+        dernière_visite: "01/01/1920".to_string(),
+        état: RnÉtatCode::BonÉtat,
+        rn_type: RnTypeCode::RepèreCylindriqueDuNivellementGénéral,
+        type_complément: Some("".to_string()),
+        canex_info: "".to_string(),
+        type_complément_avec_canex: "".to_string(),
+        longitude: 1.409197,
+        latitude: 43.649276,
+        e: "571.63".to_string(),
+        n: "6284.65".to_string(),
+        référentiel: Default::default(),
+        département: "31".to_string(),
+        insee: "31555".to_string(),
+        commune: "Toulouse".to_string(),
+        voie_suivie: "GARONNE (LA)".to_string(),
+        voie_de: Some("LE PONT DE GRENADE".to_string()),
+        voie_vers: Some("LE PONT SAINT-MICHEL".to_string()),
+        voie_côté: VoieCôtéCode::Gauche,
+        voie_pk: Some("120,3".to_string()),
+        distance: None,
+        du_repère: "".to_string(),
+        localisation: Some("AU QUARTIER DE GINESTOUS".to_string()),
+        support: "PONCEAU".to_string(),
+        partie_support: Some("MUR EN RETOUR COTE FLEUVE, FACE AMONT".to_string()),
+        repèrement_horizontal: Some("A L'AXE".to_string()),
+        repèrement_vertical: Some("".to_string()),
+        hors_ign: "100063".to_string(),
+        remarques: "".to_string(),
+        exploitabilité_gps: RnGPSExploitCode::Empty,
+        géod_info: "".to_string(),
+        site_géodésique: None,
+        triplet_cid: None,
+        primordial_cid: None,
+    }
+}
+
+/// Runs the same scenario on any store
+#[cfg(test)]
+fn check_store(store: &mut dyn Store) {
+    let objet = Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50")));
+    let id = objet.object_id();
+    store.save_objet(&objet).unwrap();
+    store.save_objet(&objet).unwrap();
+    assert_eq!(store.objets().unwrap(), vec![objet.clone()]);
+    assert_eq!(store.objet(&id).unwrap(), Some(objet.clone()));
+    assert!(store
+        .save_objet(&Objet::Autre(PointPersonnalisé {
+            type_name: "borne".to_string(),
+            clé: String::new(),
+            champs: serde_json::Map::new(),
+        }))
+        .is_err());
+
+    let visite = Visite {
+        id: store.next_visite_id().unwrap(),
+        objet: id.clone(),
+        date: NaiveDate::from_ymd_opt(2024, 8, 15).unwrap(),
+        heure: None,
+        état: Some(RnÉtatCode::MauvaisÉtat),
+        remarques: "Caché derrière un buisson".to_string(),
+        à_vérifier: false,
+    };
+    store.save_visite(&visite).unwrap();
+    assert_eq!(store.visites_of(&id).unwrap(), vec![visite.clone()]);
+    assert_eq!(store.next_visite_id().unwrap(), visite.id + 1);
+
+    let photo = Photo {
+        id: store.next_photo_id().unwrap(),
+        visite: visite.id,
+        fichier: "IMG_0001.jpg".to_string(),
+        légende: "".to_string(),
+    };
+    store.save_photo(&photo).unwrap();
+    assert_eq!(store.photos_of(visite.id).unwrap(), vec![photo.clone()]);
+
+    let mut options = Options::default();
+    options
+        .valeurs
+        .insert("langue".to_string(), "fr".to_string());
+    store.save_options(&options).unwrap();
+    assert_eq!(store.options().unwrap(), options);
+
+    store.remove_photo(photo.id).unwrap();
+    store.remove_visite(visite.id).unwrap();
+    store.remove_objet(&id).unwrap();
+    assert!(store.photos().unwrap().is_empty());
+    assert!(store.visites().unwrap().is_empty());
+    assert!(store.objets().unwrap().is_empty());
+}
+
+#[cfg(test)]
+pub(crate) fn temporary_directory(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("geodesie-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn test_memory_store() {
+    check_store(&mut MemoryStore::default());
+}
+
+#[test]
+fn test_sqlite_store() {
+    check_store(&mut SqliteStore::open_in_memory().unwrap());
+}
+
+#[test]
+fn test_json_dir_store() {
+    let path = temporary_directory("json-dir-store");
+    check_store(&mut JsonDirStore::open(&path).unwrap());
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_homonymes() {
+    let stores: [Box<dyn Store>; 2] = [
+        Box::new(MemoryStore::default()),
+        Box::new(SqliteStore::open_in_memory().unwrap()),
+    ];
+    for mut store in stores {
+        let mut homonyme = repère_test("T'.D.S3 - 50");
+        homonyme.cid = 452593;
+        store
+            .save_objet(&Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))))
+            .unwrap();
+        store.save_objet(&Objet::Ngf(Box::new(homonyme))).unwrap();
+        assert_eq!(store.objets().unwrap().len(), 2);
+
+        let matricule: ngf::Matricule = "T'.D.S3 - 50".parse().unwrap();
+        assert!(store.id_of_matricule(&matricule).is_err());
+        store.remove_objet("ngf/T'.D.S3 - 50@452593").unwrap();
+        assert_eq!(
+            store.id_of_matricule(&matricule).unwrap().to_string(),
+            "ngf/T'.D.S3 - 50@452592"
+        );
+        store.remove_objet("ngf/T'.D.S3 - 50@452592").unwrap();
+        assert_eq!(
+            store.id_of_matricule(&matricule).unwrap().to_string(),
+            "ngf/T'.D.S3 - 50"
+        );
+    }
+}
+
+#[test]
+fn test_check_objet() {
+    let point_type =
+        PointType::from_definition("borne", r#"{"numero": "str", "altitude": "float|None"}"#)
+            .unwrap();
+    let mut store = MemoryStore::default();
+    let mut point = point_type
+        .point_from_input(&[("numero".to_string(), "B-12".to_string())])
+        .unwrap();
+    // The type is unknown to the store
+    assert!(store.save_objet(&Objet::Autre(point.clone())).is_err());
+    store.types.push(point_type);
+    store.save_objet(&Objet::Autre(point.clone())).unwrap();
+    point.champs.insert("altitude".to_string(), "haut".into());
+    assert!(store.save_objet(&Objet::Autre(point.clone())).is_err());
+    point
+        .champs
+        .insert("altitude".to_string(), serde_json::Value::Null);
+    point.clé = "altitude".to_string();
+    assert!(store.save_objet(&Objet::Autre(point)).is_err());
+}
+
+#[test]
+fn test_migrate() {
+    let mut from = MemoryStore::default();
+    from.save_objet(&Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))))
+        .unwrap();
+    from.save_objet(&Objet::Ngf(Box::new(repère_test("T'.D.S3 - 52"))))
+        .unwrap();
+    let mut to = SqliteStore::open_in_memory().unwrap();
+    migrate(&from, &mut to).unwrap();
+    assert_eq!(to.objets().unwrap().len(), 2);
+    assert_eq!(to.options().unwrap(), from.options().unwrap());
+}
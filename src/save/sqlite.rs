@@ -0,0 +1,266 @@
+//! A store in a SQLite database, for large collections. Points, visits and photos are stored as
+//! JSON, next to indexed columns used by the queries
+use super::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+pub struct SqliteStore {
+    connection: Connection,
+    path: Option<PathBuf>,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS objets (
+        id TEXT PRIMARY KEY,
+        type TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS objets_type ON objets (type);
+    CREATE TABLE IF NOT EXISTS visites (
+        id INTEGER PRIMARY KEY,
+        objet TEXT NOT NULL,
+        date TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS visites_objet ON visites (objet, date);
+    CREATE TABLE IF NOT EXISTS photos (
+        id INTEGER PRIMARY KEY,
+        visite INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS photos_visite ON photos (visite);
+    CREATE TABLE IF NOT EXISTS options (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+impl SqliteStore {
+    /// Opens (and creates if needed) the database located at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> StoreResult<SqliteStore> {
+        let path = path.as_ref().to_path_buf();
+        SqliteStore::from_connection(Connection::open(&path)?, Some(path))
+    }
+
+    pub fn open_in_memory() -> StoreResult<SqliteStore> {
+        SqliteStore::from_connection(Connection::open_in_memory()?, None)
+    }
+
+    fn from_connection(connection: Connection, path: Option<PathBuf>) -> StoreResult<SqliteStore> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteStore { connection, path })
+    }
+
+    /// Runs a query whose first column is JSON data, and deserializes every row
+    fn query_json<T: serde::de::DeserializeOwned, P: rusqlite::Params>(
+        &self,
+        query: &str,
+        parameters: P,
+    ) -> StoreResult<Vec<T>> {
+        let mut statement = self.connection.prepare(query)?;
+        let rows = statement.query_map(parameters, |row| row.get::<_, String>(0))?;
+        let mut result: Vec<T> = vec![];
+        for row in rows {
+            result.push(serde_json::from_str::<T>(row?.as_str())?);
+        }
+        Ok(result)
+    }
+}
+
+impl Store for SqliteStore {
+    fn objets(&self) -> StoreResult<Vec<Objet>> {
+        self.query_json("SELECT data FROM objets ORDER BY id", [])
+    }
+
+    fn objet(&self, id: &str) -> StoreResult<Option<Objet>> {
+        let data: Option<String> = self
+            .connection
+            .query_row("SELECT data FROM objets WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str::<Objet>(data.as_str())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_objet(&mut self, objet: &Objet) -> StoreResult<()> {
+        let id = self.check_objet(objet)?;
+        self.connection.execute(
+            "INSERT OR REPLACE INTO objets (id, type, data) VALUES (?1, ?2, ?3)",
+            params![id.to_string(), id.network, serde_json::to_string(objet)?],
+        )?;
+        Ok(())
+    }
+
+    fn remove_objet(&mut self, id: &str) -> StoreResult<()> {
+        self.connection
+            .execute("DELETE FROM objets WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    fn visites(&self) -> StoreResult<Vec<Visite>> {
+        self.query_json("SELECT data FROM visites ORDER BY id", [])
+    }
+
+    fn visites_of(&self, objet_id: &str) -> StoreResult<Vec<Visite>> {
+        self.query_json(
+            "SELECT data FROM visites WHERE objet = ?1 ORDER BY date, id",
+            [objet_id],
+        )
+    }
+
+    fn save_visite(&mut self, visite: &Visite) -> StoreResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO visites (id, objet, date, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                visite.id as i64,
+                visite.objet,
+                visite.date.to_string(),
+                serde_json::to_string(visite)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_visite(&mut self, id: u64) -> StoreResult<()> {
+        self.connection
+            .execute("DELETE FROM visites WHERE id = ?1", [id as i64])?;
+        Ok(())
+    }
+
+    fn photos(&self) -> StoreResult<Vec<Photo>> {
+        self.query_json("SELECT data FROM photos ORDER BY id", [])
+    }
+
+    fn photos_of(&self, visite_id: u64) -> StoreResult<Vec<Photo>> {
+        self.query_json(
+            "SELECT data FROM photos WHERE visite = ?1 ORDER BY id",
+            [visite_id as i64],
+        )
+    }
+
+    fn save_photo(&mut self, photo: &Photo) -> StoreResult<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO photos (id, visite, data) VALUES (?1, ?2, ?3)",
+            params![
+                photo.id as i64,
+                photo.visite as i64,
+                serde_json::to_string(photo)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_photo(&mut self, id: u64) -> StoreResult<()> {
+        self.connection
+            .execute("DELETE FROM photos WHERE id = ?1", [id as i64])?;
+        Ok(())
+    }
+
+    fn options(&self) -> StoreResult<Options> {
+        let mut statement = self.connection.prepare("SELECT key, value FROM options")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut options = Options::default();
+        for row in rows {
+            let (key, value) = row?;
+            options.valeurs.insert(key, value);
+        }
+        Ok(options)
+    }
+
+    fn save_options(&mut self, options: &Options) -> StoreResult<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM options", [])?;
+        for (key, value) in &options.valeurs {
+            transaction.execute(
+                "INSERT INTO options (key, value) VALUES (?1, ?2)",
+                [key, value],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn next_visite_id(&self) -> StoreResult<u64> {
+        let max: i64 =
+            self.connection
+                .query_row("SELECT COALESCE(MAX(id), 0) FROM visites", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(max as u64 + 1)
+    }
+
+    fn next_photo_id(&self) -> StoreResult<u64> {
+        let max: i64 =
+            self.connection
+                .query_row("SELECT COALESCE(MAX(id), 0) FROM photos", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(max as u64 + 1)
+    }
+
+    fn schema_version(&self) -> StoreResult<Option<u32>> {
+        // SQLite initializes user_version to 0, which is not a valid version
+        let version: u32 = self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(if version == 0 { None } else { Some(version) })
+    }
+
+    fn set_schema_version(&mut self, version: u32) -> StoreResult<()> {
+        self.connection
+            .pragma_update(None, "user_version", version)?;
+        Ok(())
+    }
+
+    fn raw_save(&self) -> StoreResult<RawSave> {
+        let mut options = serde_json::Map::new();
+        for (key, value) in self.options()?.valeurs {
+            options.insert(key, serde_json::Value::String(value));
+        }
+        Ok(RawSave {
+            objets: self.query_json("SELECT data FROM objets ORDER BY id", [])?,
+            visites: self.query_json("SELECT data FROM visites ORDER BY id", [])?,
+            photos: self.query_json("SELECT data FROM photos ORDER BY id", [])?,
+            options: serde_json::Value::Object(options),
+        })
+    }
+
+    fn clear(&mut self) -> StoreResult<()> {
+        self.connection.execute_batch(
+            "BEGIN;
+            DELETE FROM objets;
+            DELETE FROM visites;
+            DELETE FROM photos;
+            DELETE FROM options;
+            COMMIT;",
+        )?;
+        Ok(())
+    }
+
+    fn backup(&self) -> StoreResult<Option<String>> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        let mut backup_name = path
+            .file_name()
+            .ok_or("The store has no name")?
+            .to_os_string();
+        backup_name.push(format!(
+            ".backup-v{}-{}",
+            self.schema_version()?.unwrap_or(1),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let backup_path = path
+            .with_file_name(backup_name)
+            .to_string_lossy()
+            .to_string();
+        self.connection
+            .execute("VACUUM INTO ?1", [backup_path.as_str()])?;
+        Ok(Some(backup_path))
+    }
+}
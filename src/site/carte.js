@@ -0,0 +1,133 @@
+// The map of the points, drawn on a canvas without any library nor tiles, so that it works
+// offline from file://. The points are in POINTS (points.js): {nom, longitude, latitude, couleur,
+// page}. Drag to move, scroll to zoom, click on a point to open its page.
+(function () {
+    "use strict";
+    const canvas = document.getElementById("carte");
+    const infobulle = document.getElementById("infobulle");
+    const contexte = canvas.getContext("2d");
+    const RAYON = 5;
+    if (POINTS.length === 0) {
+        return;
+    }
+
+    // Equirectangular projection, with the longitudes scaled at the mean latitude
+    const latitudeMoyenne = POINTS.reduce((somme, point) => somme + point.latitude, 0) / POINTS.length;
+    const échelleLongitude = Math.cos(latitudeMoyenne * Math.PI / 180);
+    const projette = (point) => [point.longitude * échelleLongitude, -point.latitude];
+    const projetés = POINTS.map(projette);
+
+    let zoom = 1;
+    let décalage = [0, 0];
+
+    function ajuste() {
+        const xs = projetés.map((p) => p[0]);
+        const ys = projetés.map((p) => p[1]);
+        const [minX, maxX, minY, maxY] = [Math.min(...xs), Math.max(...xs), Math.min(...ys), Math.max(...ys)];
+        const largeur = Math.max(maxX - minX, 0.01);
+        const hauteur = Math.max(maxY - minY, 0.01);
+        zoom = 0.9 * Math.min(canvas.width / largeur, canvas.height / hauteur);
+        décalage = [
+            canvas.width / 2 - zoom * (minX + maxX) / 2,
+            canvas.height / 2 - zoom * (minY + maxY) / 2,
+        ];
+    }
+
+    const écran = (p) => [décalage[0] + zoom * p[0], décalage[1] + zoom * p[1]];
+
+    function dessine() {
+        contexte.clearRect(0, 0, canvas.width, canvas.height);
+        POINTS.forEach((point, index) => {
+            const [x, y] = écran(projetés[index]);
+            contexte.beginPath();
+            contexte.arc(x, y, RAYON, 0, 2 * Math.PI);
+            contexte.fillStyle = point.couleur;
+            contexte.fill();
+            contexte.strokeStyle = "#212121";
+            contexte.stroke();
+        });
+        // The scale bar: one degree of latitude is about 111 km
+        const mètresParPixel = 111320 / zoom;
+        const longueur = [10, 50, 100, 500, 1000, 5000, 10000, 50000, 100000]
+            .find((mètres) => mètres / mètresParPixel > 60) || 100000;
+        const pixels = longueur / mètresParPixel;
+        contexte.fillStyle = "#212121";
+        contexte.fillRect(10, canvas.height - 15, pixels, 3);
+        contexte.fillText(longueur >= 1000 ? longueur / 1000 + " km" : longueur + " m", 10, canvas.height - 20);
+    }
+
+    function redimensionne() {
+        canvas.width = canvas.clientWidth;
+        canvas.height = canvas.clientHeight;
+    }
+
+    // The index of the point under the mouse, if any
+    function pointSous(évènement) {
+        const rectangle = canvas.getBoundingClientRect();
+        const [mx, my] = [évènement.clientX - rectangle.left, évènement.clientY - rectangle.top];
+        let trouvé = -1;
+        let meilleure = (RAYON + 3) ** 2;
+        projetés.forEach((p, index) => {
+            const [x, y] = écran(p);
+            const distance = (x - mx) ** 2 + (y - my) ** 2;
+            if (distance <= meilleure) {
+                meilleure = distance;
+                trouvé = index;
+            }
+        });
+        return trouvé;
+    }
+
+    let glissement = null;
+    let déplacé = false;
+    canvas.addEventListener("mousedown", (évènement) => {
+        glissement = [évènement.clientX, évènement.clientY];
+        déplacé = false;
+    });
+    window.addEventListener("mouseup", () => {
+        glissement = null;
+    });
+    canvas.addEventListener("mousemove", (évènement) => {
+        if (glissement) {
+            décalage[0] += évènement.clientX - glissement[0];
+            décalage[1] += évènement.clientY - glissement[1];
+            déplacé = déplacé || Math.abs(évènement.clientX - glissement[0]) + Math.abs(évènement.clientY - glissement[1]) > 2;
+            glissement = [évènement.clientX, évènement.clientY];
+            dessine();
+        }
+        const index = pointSous(évènement);
+        if (index >= 0) {
+            infobulle.textContent = POINTS[index].nom;
+            infobulle.style.left = évènement.pageX + 12 + "px";
+            infobulle.style.top = évènement.pageY + 12 + "px";
+            infobulle.style.display = "block";
+            canvas.style.cursor = "pointer";
+        } else {
+            infobulle.style.display = "none";
+            canvas.style.cursor = glissement ? "grabbing" : "grab";
+        }
+    });
+    canvas.addEventListener("click", (évènement) => {
+        const index = pointSous(évènement);
+        if (index >= 0 && !déplacé) {
+            window.location.href = POINTS[index].page;
+        }
+    });
+    canvas.addEventListener("wheel", (évènement) => {
+        évènement.preventDefault();
+        const rectangle = canvas.getBoundingClientRect();
+        const [mx, my] = [évènement.clientX - rectangle.left, évènement.clientY - rectangle.top];
+        const facteur = évènement.deltaY < 0 ? 1.25 : 0.8;
+        décalage = [mx - (mx - décalage[0]) * facteur, my - (my - décalage[1]) * facteur];
+        zoom *= facteur;
+        dessine();
+    }, { passive: false });
+    window.addEventListener("resize", () => {
+        redimensionne();
+        dessine();
+    });
+
+    redimensionne();
+    ajuste();
+    dessine();
+})();
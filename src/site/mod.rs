@@ -0,0 +1,496 @@
+//! Static website of the collection, to publish it or browse it from `file://`: an index table,
+//! a page per point with its fiche, its visits and its photos, pages per commune and per
+//! levelling line, and a map. Everything is in the site directory, with no CDN
+use crate::common_traits::Identified;
+use crate::export::{couleur_état, kml::miniature, échappe_xml, PointExporté};
+use crate::save::{Objet, Photo, Visite};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt::Write as _,
+    fs,
+    path::Path,
+};
+
+const STYLE: &str = include_str!("style.css");
+const CARTE: &str = include_str!("carte.js");
+
+/// The text with its ANSI colours and bold (see the Display of the points) as HTML, and its URLs
+/// as links. The other escape sequences are dropped
+pub fn ansi_vers_html(texte: &str) -> String {
+    let mut html = String::new();
+    let mut couleur: Option<u8> = None;
+    let mut gras = false;
+    let mut span_ouvert = false;
+    let mut caractères = texte.chars().peekable();
+    let mut mot = String::new();
+    // The URLs are made links once the word is complete
+    let ajoute_mot = |html: &mut String, mot: &mut String| {
+        if mot.starts_with("https://") || mot.starts_with("http://") {
+            let url = échappe_xml(mot);
+            write!(html, "<a href=\"{url}\">{url}</a>").unwrap();
+        } else {
+            html.push_str(&échappe_xml(mot));
+        }
+        mot.clear();
+    };
+    while let Some(caractère) = caractères.next() {
+        if caractère != '\x1b' {
+            if caractère.is_whitespace() {
+                ajoute_mot(&mut html, &mut mot);
+                html.push(caractère);
+            } else {
+                mot.push(caractère);
+            }
+            continue;
+        }
+        ajoute_mot(&mut html, &mut mot);
+        if caractères.peek() != Some(&'[') {
+            continue;
+        }
+        caractères.next();
+        let mut paramètres = String::new();
+        while let Some(c) = caractères.next_if(|c| c.is_ascii_digit() || *c == ';') {
+            paramètres.push(c);
+        }
+        // Only the colour sequences, ending with m, are kept
+        if caractères.next_if_eq(&'m').is_none() {
+            continue;
+        }
+        for paramètre in paramètres.split(';') {
+            match paramètre.parse::<u8>().unwrap_or(0) {
+                0 => (couleur, gras) = (None, false),
+                1 => gras = true,
+                22 => gras = false,
+                39 => couleur = None,
+                code @ (30..=37 | 90..=97) => couleur = Some(code),
+                _ => {}
+            }
+        }
+        if span_ouvert {
+            html.push_str("</span>");
+        }
+        let classes: Vec<String> = couleur
+            .map(|code| format!("c{code}"))
+            .into_iter()
+            .chain(gras.then(|| "gras".to_string()))
+            .collect();
+        span_ouvert = !classes.is_empty();
+        if span_ouvert {
+            write!(html, "<span class=\"{}\">", classes.join(" ")).unwrap();
+        }
+    }
+    ajoute_mot(&mut html, &mut mot);
+    if span_ouvert {
+        html.push_str("</span>");
+    }
+    html
+}
+
+/// A file name for each name, made of ASCII letters, digits and dashes, and unique
+fn noms_de_fichiers<'a>(noms: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut utilisés: HashSet<String> = HashSet::new();
+    noms.map(|nom| {
+        let mut base = String::new();
+        for caractère in nom.chars() {
+            if caractère.is_ascii_alphanumeric() {
+                base.push(caractère);
+            } else if !base.ends_with('-') {
+                base.push('-');
+            }
+        }
+        let base = base.trim_matches('-').to_string();
+        let base = if base.is_empty() {
+            "point".to_string()
+        } else {
+            base
+        };
+        let mut nom_de_fichier = base.clone();
+        let mut numéro = 2;
+        while !utilisés.insert(nom_de_fichier.to_lowercase()) {
+            nom_de_fichier = format!("{base}-{numéro}");
+            numéro += 1;
+        }
+        nom_de_fichier
+    })
+    .collect()
+}
+
+/// An HTML page. `racine` is the relative path from the page to the root of the site
+fn page(titre: &str, racine: &str, corps: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+        <html lang=\"fr\">\n\
+        <head>\n\
+        <meta charset=\"utf-8\">\n\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+        <title>{titre} – Géodésie de Bureau</title>\n\
+        <link rel=\"stylesheet\" href=\"{racine}style.css\">\n\
+        </head>\n\
+        <body>\n\
+        <nav><a href=\"{racine}index.html\">Points</a><a href=\"{racine}communes.html\">Communes</a><a href=\"{racine}lignes.html\">Lignes</a><a href=\"{racine}carte.html\">Carte</a></nav>\n\
+        <h1>{titre}</h1>\n\
+        {corps}\
+        </body>\n\
+        </html>\n",
+        titre = échappe_xml(titre),
+    )
+}
+
+/// The levelling line of a benchmark
+fn ligne(point: &PointExporté) -> Option<String> {
+    match &point.objet {
+        Objet::Ngf(repère) => Some(repère.matricule.ligne().to_string()),
+        _ => None,
+    }
+}
+
+/// The commune and the département of a point
+fn commune(point: &PointExporté) -> Option<String> {
+    let commune = point.commune()?;
+    Some(match point.département() {
+        Some(département) => format!("{commune} ({département})"),
+        None => commune,
+    })
+}
+
+/// The table of the points, with links to their pages
+fn tableau(points: &[(&PointExporté, &str)], racine: &str) -> String {
+    let mut html = String::from(
+        "<table>\n<tr><th>Point</th><th>Type</th><th>Commune</th><th>Altitude</th><th>État</th><th>Visites</th><th>Dernière visite</th></tr>\n",
+    );
+    for (point, fichier) in points {
+        let état = point.état();
+        writeln!(
+            html,
+            "<tr><td><a href=\"{racine}points/{fichier}.html\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td><span class=\"etat\" style=\"background: {}\"></span>{}</td><td>{}</td><td>{}</td></tr>",
+            échappe_xml(&point.nom()),
+            échappe_xml(point.objet.type_name()),
+            échappe_xml(&commune(point).unwrap_or_default()),
+            point.altitude().map(|altitude| format!("{altitude} m")).unwrap_or_default(),
+            couleur_état(état.as_ref()),
+            état.map(|état| état.to_string()).unwrap_or_default(),
+            point.nombre_de_visites,
+            point.dernière_visite.map(|date| date.to_string()).unwrap_or_default(),
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// The list of the groups (communes or lines), with links to their pages
+fn liste_groupes(
+    groupes: &BTreeMap<String, Vec<usize>>,
+    fichiers: &[String],
+    dossier: &str,
+) -> String {
+    let mut html = String::from("<ul>\n");
+    for ((groupe, points), fichier) in groupes.iter().zip(fichiers) {
+        writeln!(
+            html,
+            "<li><a href=\"{dossier}/{fichier}.html\">{}</a> ({} points)</li>",
+            échappe_xml(groupe),
+            points.len()
+        )
+        .unwrap();
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/// The page of a point: its fiche, its confirmed visits and its photos. `photos` gives the
+/// thumbnail and the copy of each photo of the site
+fn page_point(
+    point: &PointExporté,
+    visites: &[&Visite],
+    photos: &HashMap<u64, (String, String)>,
+    liens: &[(String, String)],
+) -> String {
+    let mut corps = String::new();
+    if !liens.is_empty() {
+        let liens: Vec<String> = liens
+            .iter()
+            .map(|(texte, lien)| format!("<a href=\"{lien}\">{}</a>", échappe_xml(texte)))
+            .collect();
+        writeln!(corps, "<p>{}</p>", liens.join(" · ")).unwrap();
+    }
+    writeln!(
+        corps,
+        "<pre class=\"fiche\">{}</pre>",
+        ansi_vers_html(&point.objet.to_string())
+    )
+    .unwrap();
+    corps.push_str("<h2>Visites</h2>\n");
+    if visites.is_empty() {
+        corps.push_str("<p>Pas encore visité.</p>\n");
+    } else {
+        corps.push_str("<table>\n<tr><th>Date</th><th>État constaté</th><th>Remarques</th></tr>\n");
+        for visite in visites {
+            writeln!(
+                corps,
+                "<tr><td>{}{}</td><td>{}</td><td>{}</td></tr>",
+                visite.date,
+                visite
+                    .heure
+                    .map(|heure| heure.format(" %H:%M").to_string())
+                    .unwrap_or_default(),
+                visite
+                    .état
+                    .as_ref()
+                    .map(|état| état.to_string())
+                    .unwrap_or_default(),
+                échappe_xml(&visite.remarques)
+            )
+            .unwrap();
+        }
+        corps.push_str("</table>\n");
+    }
+    let galerie: Vec<String> = point
+        .photos
+        .iter()
+        .filter_map(|photo| {
+            let (miniature, original) = photos.get(&photo.id)?;
+            Some(format!(
+                "<figure><a href=\"../{original}\"><img src=\"../{miniature}\" alt=\"{légende}\"></a><figcaption>{légende}</figcaption></figure>",
+                légende = échappe_xml(&photo.légende)
+            ))
+        })
+        .collect();
+    if !galerie.is_empty() {
+        writeln!(
+            corps,
+            "<h2>Photos</h2>\n<div class=\"galerie\">\n{}\n</div>",
+            galerie.join("\n")
+        )
+        .unwrap();
+    }
+    page(&point.nom(), "../", &corps)
+}
+
+/// Writes the site of the points and their visits (among `visites`) in `dossier`. Returns the
+/// photos that could not be read, which are left out
+pub fn génère(
+    dossier: &Path,
+    points: &[PointExporté],
+    visites: &[Visite],
+) -> Result<Vec<Photo>, Box<dyn Error>> {
+    for sous_dossier in ["points", "communes", "lignes", "photos/originales"] {
+        fs::create_dir_all(dossier.join(sous_dossier))?;
+    }
+    fs::write(dossier.join("style.css"), STYLE)?;
+    fs::write(dossier.join("carte.js"), CARTE)?;
+
+    // The photos: a thumbnail for the gallery, and a copy of the original
+    let mut photos: HashMap<u64, (String, String)> = HashMap::new();
+    let mut illisibles: Vec<Photo> = vec![];
+    for photo in points.iter().flat_map(|point| &point.photos) {
+        if photos.contains_key(&photo.id) {
+            continue;
+        }
+        let extension = Path::new(&photo.fichier)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or("jpg".to_string());
+        let miniature_photo = format!("photos/{}.jpg", photo.id);
+        let original = format!("photos/originales/{}.{extension}", photo.id);
+        let copie = miniature(photo).and_then(|jpeg| {
+            fs::write(dossier.join(&miniature_photo), jpeg)?;
+            fs::copy(&photo.fichier, dossier.join(&original))?;
+            Ok(())
+        });
+        match copie {
+            Ok(()) => {
+                photos.insert(photo.id, (miniature_photo, original));
+            }
+            Err(_) => illisibles.push(photo.clone()),
+        }
+    }
+
+    let noms: Vec<String> = points.iter().map(|point| point.objet.object_id()).collect();
+    // Named without the cid of the benchmarks: homonyms are numbered by noms_de_fichiers
+    let noms_de_pages: Vec<String> = points
+        .iter()
+        .map(|point| format!("{}/{}", point.objet.type_name(), point.nom()))
+        .collect();
+    let fichiers = noms_de_fichiers(noms_de_pages.iter().map(String::as_str));
+    let mut communes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut lignes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, point) in points.iter().enumerate() {
+        if let Some(commune) = commune(point) {
+            communes.entry(commune).or_default().push(index);
+        }
+        if let Some(ligne) = ligne(point) {
+            lignes.entry(ligne).or_default().push(index);
+        }
+    }
+    let fichiers_communes = noms_de_fichiers(communes.keys().map(String::as_str));
+    let fichiers_lignes = noms_de_fichiers(lignes.keys().map(String::as_str));
+    let fichier_du_groupe =
+        |groupes: &BTreeMap<String, Vec<usize>>, fichiers: &[String], groupe: Option<String>| {
+            let position = groupes
+                .keys()
+                .position(|nom| Some(nom) == groupe.as_ref())?;
+            Some((groupe?, fichiers[position].clone()))
+        };
+
+    for (index, point) in points.iter().enumerate() {
+        let mut liens: Vec<(String, String)> = vec![];
+        if let Some((commune, fichier)) =
+            fichier_du_groupe(&communes, &fichiers_communes, commune(point))
+        {
+            liens.push((commune, format!("../communes/{fichier}.html")));
+        }
+        if let Some((ligne, fichier)) = fichier_du_groupe(&lignes, &fichiers_lignes, ligne(point)) {
+            liens.push((
+                format!("Ligne {ligne}"),
+                format!("../lignes/{fichier}.html"),
+            ));
+        }
+        let mut visites_point: Vec<&Visite> = visites
+            .iter()
+            .filter(|visite| visite.objet == noms[index] && !visite.à_vérifier)
+            .collect();
+        visites_point.sort_by_key(|visite| (visite.date, visite.heure, visite.id));
+        fs::write(
+            dossier
+                .join("points")
+                .join(format!("{}.html", fichiers[index])),
+            page_point(point, &visites_point, &photos, &liens),
+        )?;
+    }
+
+    let avec_fichiers = |indices: &[usize]| -> Vec<(&PointExporté, &str)> {
+        indices
+            .iter()
+            .map(|index| (&points[*index], fichiers[*index].as_str()))
+            .collect()
+    };
+    let tous: Vec<usize> = (0..points.len()).collect();
+    let visités = points
+        .iter()
+        .filter(|point| point.nombre_de_visites > 0)
+        .count();
+    fs::write(
+        dossier.join("index.html"),
+        page(
+            "Points",
+            "",
+            &format!(
+                "<p>{} points, dont {visités} visités.</p>\n{}",
+                points.len(),
+                tableau(&avec_fichiers(&tous), "")
+            ),
+        ),
+    )?;
+    for (groupes, fichiers_groupes, dossier_groupes, titre) in [
+        (&communes, &fichiers_communes, "communes", "Communes"),
+        (&lignes, &fichiers_lignes, "lignes", "Lignes de nivellement"),
+    ] {
+        fs::write(
+            dossier.join(format!("{dossier_groupes}.html")),
+            page(
+                titre,
+                "",
+                &liste_groupes(groupes, fichiers_groupes, dossier_groupes),
+            ),
+        )?;
+        for ((groupe, indices), fichier) in groupes.iter().zip(fichiers_groupes) {
+            fs::write(
+                dossier
+                    .join(dossier_groupes)
+                    .join(format!("{fichier}.html")),
+                page(groupe, "../", &tableau(&avec_fichiers(indices), "../")),
+            )?;
+        }
+    }
+
+    // The map reads the points from a script, as browsers do not let pages opened from file://
+    // fetch JSON files
+    let points_carte: Vec<serde_json::Value> = points
+        .iter()
+        .zip(&fichiers)
+        .filter_map(|(point, fichier)| {
+            let (longitude, latitude) = point.objet.coordonnées()?;
+            Some(json!({
+                "nom": point.nom(),
+                "longitude": longitude,
+                "latitude": latitude,
+                "couleur": couleur_état(point.état().as_ref()),
+                "page": format!("points/{fichier}.html"),
+            }))
+        })
+        .collect();
+    fs::write(
+        dossier.join("points.js"),
+        format!(
+            "const POINTS = {};\n",
+            serde_json::to_string(&points_carte)?
+        ),
+    )?;
+    fs::write(
+        dossier.join("carte.html"),
+        page(
+            "Carte",
+            "",
+            "<canvas id=\"carte\"></canvas>\n<div id=\"infobulle\"></div>\n\
+            <p>Glisser pour déplacer la carte, molette pour zoomer, clic sur un point pour ouvrir sa page.</p>\n\
+            <script src=\"points.js\"></script>\n<script src=\"carte.js\"></script>\n",
+        ),
+    )?;
+    Ok(illisibles)
+}
+
+#[test]
+fn test_ansi_vers_html() {
+    assert_eq!(
+        ansi_vers_html("\x1b[91m=== Support ===\x1b[39m\n\x1b[94mSupport\x1b[39m : <MUR>"),
+        "<span class=\"c91\">=== Support ===</span>\n<span class=\"c94\">Support</span> : &lt;MUR&gt;"
+    );
+    assert_eq!(
+        ansi_vers_html("\x1b[92;1m0\x1b[39;22m : https://ign.fr/?a=1&b=2"),
+        "<span class=\"c92 gras\">0</span> : <a href=\"https://ign.fr/?a=1&amp;b=2\">https://ign.fr/?a=1&amp;b=2</a>"
+    );
+    // Not a colour: the sequence is dropped
+    assert_eq!(ansi_vers_html("\x1b[93Mauvais"), "Mauvais");
+}
+
+#[test]
+fn test_site() {
+    use crate::save::repère_test;
+    let mut autre = repère_test("T.D.S3 - 50");
+    autre.commune = "BLAGNAC".to_string();
+    let points = vec![
+        PointExporté::new(Objet::Ngf(Box::new(repère_test("T'.D.S3 - 50"))), &[], &[]),
+        PointExporté::new(Objet::Ngf(Box::new(autre)), &[], &[]),
+    ];
+    let visites = vec![Visite {
+        id: 1,
+        objet: "ngf/T'.D.S3 - 50@452592".to_string(),
+        date: "2024-06-01".parse().unwrap(),
+        heure: None,
+        état: None,
+        remarques: "Sous le <pont>".to_string(),
+        à_vérifier: false,
+    }];
+    let dossier = crate::save::temporary_directory("site");
+    assert!(génère(&dossier, &points, &visites).unwrap().is_empty());
+
+    assert_eq!(
+        noms_de_fichiers(["ngf/T'.D.S3 - 50", "ngf/T.D.S3 - 50"].into_iter()),
+        vec!["ngf-T-D-S3-50", "ngf-T-D-S3-50-2"]
+    );
+    let page = fs::read_to_string(dossier.join("points/ngf-T-D-S3-50.html")).unwrap();
+    assert!(page.contains("<a href=\"../lignes/T-D-S3.html\">Ligne T'.D.S3</a>"));
+    assert!(page.contains("<td>Sous le &lt;pont&gt;</td>"));
+    assert!(page.contains("<span class=\"c94\">Matricule</span>\u{202f}: T'.D.S3 - 50"));
+    let index = fs::read_to_string(dossier.join("index.html")).unwrap();
+    assert!(index.contains("<a href=\"points/ngf-T-D-S3-50-2.html\">T.D.S3 - 50</a>"));
+    let communes = fs::read_to_string(dossier.join("communes.html")).unwrap();
+    assert_eq!(communes.matches("<li>").count(), 2);
+    let carte = fs::read_to_string(dossier.join("points.js")).unwrap();
+    assert!(carte.starts_with("const POINTS = [{\"nom\":\"T'.D.S3 - 50\""));
+    assert!(dossier.join("carte.js").exists() && dossier.join("style.css").exists());
+    let _ = fs::remove_dir_all(&dossier);
+}
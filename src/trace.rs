@@ -0,0 +1,261 @@
+//! Visits suggested from a recorded GPS track: the points within a corridor around the track of a
+//! GPX file, with the time when we were closest to each of them
+use crate::network::{distance, Area};
+use crate::save::Objet;
+use chrono::{DateTime, Utc};
+use quick_xml::{events::Event, Reader};
+use std::{error::Error, io::BufRead};
+
+/// The default half-width of the corridor around the track, in metres
+pub const CORRIDOR: f64 = 30.0;
+
+/// A point of a track
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PointTrace {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub heure: Option<DateTime<Utc>>,
+}
+
+/// Reads the segments of the tracks of a GPX file
+pub fn lis_gpx<R: BufRead>(lecteur: R) -> Result<Vec<Vec<PointTrace>>, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(lecteur);
+    let mut buffer = vec![];
+    let mut segments: Vec<Vec<PointTrace>> = vec![];
+    let mut point: Option<PointTrace> = None;
+    let mut dans_time = false;
+    loop {
+        let évènement = reader.read_event_into(&mut buffer)?;
+        // <trkpt/> has no time, and no end
+        let vide = matches!(évènement, Event::Empty(_));
+        match évènement {
+            Event::Start(élément) if élément.local_name().as_ref() == b"trkseg" => {
+                segments.push(vec![]);
+            }
+            Event::Start(élément) | Event::Empty(élément)
+                if élément.local_name().as_ref() == b"trkpt" =>
+            {
+                let coordonnée = |nom: &[u8]| -> Result<f64, Box<dyn Error>> {
+                    let attribut = élément
+                        .try_get_attribute(nom)?
+                        .ok_or("Track point without coordinates")?;
+                    Ok(attribut.unescape_value()?.trim().parse()?)
+                };
+                let trkpt = PointTrace {
+                    longitude: coordonnée(b"lon")?,
+                    latitude: coordonnée(b"lat")?,
+                    heure: None,
+                };
+                match segments.last_mut() {
+                    Some(segment) if vide => segment.push(trkpt),
+                    Some(_) => point = Some(trkpt),
+                    None => return Err("Track point outside of a track segment".into()),
+                }
+            }
+            Event::Start(élément) if élément.local_name().as_ref() == b"time" => {
+                dans_time = point.is_some();
+            }
+            Event::Text(texte) if dans_time => {
+                if let Some(point) = point.as_mut() {
+                    point.heure = Some(texte.decode()?.trim().parse()?);
+                }
+            }
+            Event::End(élément) if élément.local_name().as_ref() == b"time" => {
+                dans_time = false;
+            }
+            Event::End(élément) if élément.local_name().as_ref() == b"trkpt" => {
+                if let (Some(segment), Some(point)) = (segments.last_mut(), point.take()) {
+                    segment.push(point);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+    segments.retain(|segment| !segment.is_empty());
+    Ok(segments)
+}
+
+/// The area of the track, extended by `marge` degrees
+pub fn zone(segments: &[Vec<PointTrace>], marge: f64) -> Option<Area> {
+    let mut points = segments.iter().flatten();
+    let premier = points.next()?;
+    let mut zone = Area::around(premier.longitude, premier.latitude, marge);
+    for point in points {
+        zone.longitude_min = zone.longitude_min.min(point.longitude - marge);
+        zone.latitude_min = zone.latitude_min.min(point.latitude - marge);
+        zone.longitude_max = zone.longitude_max.max(point.longitude + marge);
+        zone.latitude_max = zone.latitude_max.max(point.latitude + marge);
+    }
+    Some(zone)
+}
+
+/// The closest point of the section [a, b] to `cible` (longitude, latitude), with the time we were
+/// there, interpolated between the times of a and b
+fn plus_proche_sur_section(a: &PointTrace, b: &PointTrace, cible: (f64, f64)) -> PointTrace {
+    // Over a few hundred metres, the coordinates can be seen as planar once the longitudes are
+    // scaled by the cosine of the latitude
+    let échelle = a.latitude.to_radians().cos();
+    let (dx, dy) = (
+        (b.longitude - a.longitude) * échelle,
+        b.latitude - a.latitude,
+    );
+    let (cx, cy) = ((cible.0 - a.longitude) * échelle, cible.1 - a.latitude);
+    let longueur = dx * dx + dy * dy;
+    let t = if longueur == 0.0 {
+        0.0
+    } else {
+        ((cx * dx + cy * dy) / longueur).clamp(0.0, 1.0)
+    };
+    PointTrace {
+        longitude: a.longitude + t * (b.longitude - a.longitude),
+        latitude: a.latitude + t * (b.latitude - a.latitude),
+        heure: match (a.heure, b.heure) {
+            (Some(début), Some(fin)) => {
+                let durée = (fin - début).num_milliseconds() as f64;
+                Some(début + chrono::Duration::milliseconds((t * durée).round() as i64))
+            }
+            (heure_a, heure_b) => heure_a.or(heure_b),
+        },
+    }
+}
+
+/// A point we passed by
+#[derive(Clone, PartialEq, Debug)]
+pub struct Passage {
+    pub objet: Objet,
+    /// The distance from the track to the point, in metres
+    pub distance: f64,
+    /// When we were closest to the point, if the track has times
+    pub heure: Option<DateTime<Utc>>,
+}
+
+/// The points within `corridor` metres of the track, in the order we passed by them: the order of
+/// the segments and of their sections, then the time and the distance along the section
+pub fn passages(segments: &[Vec<PointTrace>], objets: Vec<Objet>, corridor: f64) -> Vec<Passage> {
+    let mut passages: Vec<((usize, usize, f64), Passage)> = objets
+        .into_iter()
+        .filter_map(|objet| {
+            let cible = objet.coordonnées()?;
+            let (rang, point, distance) = segments
+                .iter()
+                .enumerate()
+                .flat_map(|(numéro_segment, segment)| {
+                    // A segment of one point is a section from the point to itself
+                    let suivants = segment.iter().skip(1).chain(segment.last());
+                    segment
+                        .iter()
+                        .zip(suivants)
+                        .take(segment.len().max(2) - 1)
+                        .enumerate()
+                        .map(move |(numéro_section, section)| {
+                            (numéro_segment, numéro_section, section)
+                        })
+                })
+                .map(|(numéro_segment, numéro_section, (a, b))| {
+                    let point = plus_proche_sur_section(a, b, cible);
+                    let avancement =
+                        distance((a.longitude, a.latitude), (point.longitude, point.latitude));
+                    (
+                        (numéro_segment, numéro_section, avancement),
+                        point,
+                        distance((point.longitude, point.latitude), cible),
+                    )
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2))?;
+            (distance <= corridor).then_some((
+                rang,
+                Passage {
+                    objet,
+                    distance,
+                    heure: point.heure,
+                },
+            ))
+        })
+        .collect();
+    // The times only order the points passed by on the same section, since the clock of the GPS
+    // may jump between two segments
+    passages.sort_by(|(rang_a, passage_a), (rang_b, passage_b)| {
+        (rang_a.0, rang_a.1)
+            .cmp(&(rang_b.0, rang_b.1))
+            .then(passage_a.heure.cmp(&passage_b.heure))
+            .then(rang_a.2.total_cmp(&rang_b.2))
+    });
+    passages.into_iter().map(|(_, passage)| passage).collect()
+}
+
+#[test]
+fn test_passages() {
+    use crate::save::repère_test;
+    let trace = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk><name>Randonnée</name><trkseg>
+    <trkpt lat="43.6" lon="1.40"><ele>150</ele><time>2024-06-01T08:00:00Z</time></trkpt>
+    <trkpt lat="43.6" lon="1.41"><time>2024-06-01T08:10:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+    let segments = lis_gpx(trace.as_bytes()).unwrap();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].len(), 2);
+    assert_eq!(
+        segments[0][1].heure,
+        Some("2024-06-01T08:10:00Z".parse().unwrap())
+    );
+
+    let repère = |matricule: &str, longitude: f64, latitude: f64| {
+        let mut repère = repère_test(matricule);
+        repère.longitude = longitude;
+        repère.latitude = latitude;
+        Objet::Ngf(Box::new(repère))
+    };
+    let objets = vec![
+        // Three quarters of the way, 11 m north of the track
+        repère("T'.D.S3 - 51", 1.4075, 43.6001),
+        // A quarter of the way, on the track
+        repère("T'.D.S3 - 50", 1.4025, 43.6),
+        // 1 km away
+        repère("T'.D.S3 - 52", 1.405, 43.61),
+    ];
+    let passages = passages(&segments, objets, CORRIDOR);
+    assert_eq!(passages.len(), 2);
+    assert!(passages[0].distance < 0.01);
+    assert_eq!(
+        passages[0].heure,
+        Some("2024-06-01T08:02:30Z".parse().unwrap())
+    );
+    assert!((passages[1].distance - 11.1).abs() < 0.1);
+    assert_eq!(
+        passages[1].heure,
+        Some("2024-06-01T08:07:30Z".parse().unwrap())
+    );
+    // The segments are in the order of the track, even if their times are not
+    use crate::common_traits::Identified;
+    let segments_inversés = vec![
+        vec![PointTrace {
+            heure: Some("2024-06-01T09:00:00Z".parse().unwrap()),
+            ..segments[0][0]
+        }],
+        vec![PointTrace {
+            heure: None,
+            ..segments[0][1]
+        }],
+    ];
+    let passages = crate::trace::passages(
+        &segments_inversés,
+        vec![
+            repère("T'.D.S3 - 51", 1.41, 43.6),
+            repère("T'.D.S3 - 50", 1.40, 43.6),
+        ],
+        CORRIDOR,
+    );
+    assert_eq!(
+        passages
+            .iter()
+            .map(|passage| passage.objet.object_id())
+            .collect::<Vec<_>>(),
+        vec!["ngf/T'.D.S3 - 50@452592", "ngf/T'.D.S3 - 51@452592"]
+    );
+    let zone = zone(&segments, 0.01).unwrap();
+    assert!(zone.contains(1.405, 43.609) && !zone.contains(1.405, 43.611));
+}